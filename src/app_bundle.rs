@@ -0,0 +1,196 @@
+//! Inspect macOS app bundles
+//!
+//! Provides a library to read the Info.plist of app bundles that login items point to, and to find the login item
+//! helper apps embedded in app bundles.
+
+use std::{
+    fs::{read_dir, symlink_metadata},
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::{error::LoginItemsError, loginitems_plist, target};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppBundleInfo {
+    pub info_plist: String,    // Path to the Info.plist
+    pub bundle_id: String,     // CFBundleIdentifier
+    pub executable: String,    // CFBundleExecutable
+    pub version: String,       // CFBundleVersion
+    pub short_version: String, // CFBundleShortVersionString
+    pub ui_element: bool,      // LSUIElement, app has no Dock icon or menu bar
+    pub background_only: bool, // LSBackgroundOnly, app has no user interface
+    pub minimum_os: String,    // LSMinimumSystemVersion
+}
+
+/// Check if a bookmark path points to an app bundle
+pub fn is_app_bundle(path: &[String]) -> bool {
+    match path.last() {
+        Some(name) => name.to_lowercase().ends_with(".app"),
+        None => false,
+    }
+}
+
+/// Read the Contents/Info.plist of an app bundle under a root directory
+pub fn app_bundle_info(root: &Path, bundle: &Path) -> Result<AppBundleInfo, LoginItemsError> {
+    let info_path = bundle.join("Contents/Info.plist");
+    if let Some(link) = target::symlink_below_root(root, &info_path) {
+        return Err(LoginItemsError::Symlink {
+            path: link.display().to_string(),
+        });
+    }
+    let path = info_path.display().to_string();
+    let info: Dictionary = loginitems_plist::read_plist(&path)?;
+
+    let get_string = |key: &str| match info.get(key) {
+        Some(Value::String(value)) => value.to_string(),
+        _ => String::new(),
+    };
+    // Boolean keys are sometimes stored as strings or numbers
+    let get_bool = |key: &str| match info.get(key) {
+        Some(Value::Boolean(value)) => *value,
+        Some(Value::String(value)) => {
+            value == "1" || value.eq_ignore_ascii_case("yes") || value.eq_ignore_ascii_case("true")
+        }
+        Some(Value::Integer(value)) => value.as_signed() == Some(1),
+        _ => false,
+    };
+
+    let bundle_info = AppBundleInfo {
+        info_plist: path.to_string(),
+        bundle_id: get_string("CFBundleIdentifier"),
+        executable: get_string("CFBundleExecutable"),
+        version: get_string("CFBundleVersion"),
+        short_version: get_string("CFBundleShortVersionString"),
+        ui_element: get_bool("LSUIElement"),
+        background_only: get_bool("LSBackgroundOnly"),
+        minimum_os: get_string("LSMinimumSystemVersion"),
+    };
+    Ok(bundle_info)
+}
+
+/// Get the helper apps in Contents/Library/LoginItems of an app bundle under a root directory. Helpers are
+/// registered with SMLoginItemSetEnabled and launched by launchd
+pub fn login_item_helpers(root: &Path, bundle: &Path) -> Vec<PathBuf> {
+    let helpers_directory = bundle.join("Contents/Library/LoginItems");
+    let mut helpers: Vec<PathBuf> = Vec::new();
+    if let Some(link) = target::symlink_below_root(root, &helpers_directory) {
+        warn!("Not following symlink {}", link.display());
+        return helpers;
+    }
+    // Most apps do not have helpers
+    if !helpers_directory.is_dir() {
+        return helpers;
+    }
+    let entries = match read_dir(&helpers_directory) {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!(
+                "Failed to read helpers directory {}: {:?}",
+                helpers_directory.display(),
+                err
+            );
+            return helpers;
+        }
+    };
+    for entry in entries.flatten() {
+        let helper = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let is_directory = match symlink_metadata(&helper) {
+            Ok(metadata) => metadata.is_dir(),
+            Err(_) => false,
+        };
+        if is_directory && is_app_bundle(&[name]) {
+            helpers.push(helper);
+        }
+    }
+    helpers.sort();
+    helpers
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{app_bundle_info, is_app_bundle, login_item_helpers};
+    use crate::error::LoginItemsError;
+
+    #[test]
+    fn test_app_bundle_info() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root");
+        let info = app_bundle_info(
+            &test_location,
+            &test_location.join("Applications/Syncthing.app"),
+        )
+        .unwrap();
+
+        assert!(info.bundle_id == "com.github.xor-gate.syncthing-macosx");
+        assert!(info.executable == "Syncthing");
+        assert!(info.version == "119000");
+        assert!(info.short_version == "1.19.0");
+        assert!(info.ui_element);
+        assert!(!info.background_only);
+        assert!(info.minimum_os == "10.13");
+    }
+
+    #[test]
+    fn test_app_bundle_info_missing() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root");
+        let result = app_bundle_info(
+            &test_location,
+            &test_location.join("Applications/Missing.app"),
+        );
+        assert!(matches!(result, Err(LoginItemsError::Io { .. })));
+    }
+
+    #[test]
+    fn test_login_item_helpers() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root");
+        let app = test_location.join("Users/alex/Applications/Docker.app");
+        let helpers = login_item_helpers(&test_location, &app);
+        assert!(helpers.len() == 1);
+        assert!(helpers[0].ends_with("Contents/Library/LoginItems/DockerHelper.app"));
+
+        let info = app_bundle_info(&test_location, &helpers[0]).unwrap();
+        assert!(info.bundle_id == "com.docker.helper");
+        assert!(info.background_only);
+
+        assert!(login_item_helpers(&test_location, &app.join("Contents/MacOS")).is_empty());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_app_bundle_symlink() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root/Users/alex/Applications/Docker.app/Contents");
+        let root = std::env::temp_dir().join(format!("loginitems_bundle_{}", std::process::id()));
+        let app = root.join("Applications/Docker.app");
+        std::fs::create_dir_all(&app).unwrap();
+        // Absolute link to a bundle outside of the root directory
+        std::os::unix::fs::symlink(&test_location, app.join("Contents")).unwrap();
+
+        let info = app_bundle_info(&root, &app);
+        let helpers = login_item_helpers(&root, &app);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(matches!(info, Err(LoginItemsError::Symlink { .. })));
+        assert!(helpers.is_empty());
+    }
+
+    #[test]
+    fn test_is_app_bundle() {
+        assert!(is_app_bundle(&[
+            "Applications".to_string(),
+            "Syncthing.app".to_string()
+        ]));
+        assert!(is_app_bundle(&["Evil.APP".to_string()]));
+        assert!(!is_app_bundle(&["usr".to_string(), "bin".to_string()]));
+        assert!(!is_app_bundle(&[]));
+    }
+}
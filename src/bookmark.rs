@@ -0,0 +1,1534 @@
+//! Parse macOS Bookmark data
+//!
+//! Provides a library to decode every Table of Contents record in a bookmark.
+
+use std::{
+    mem::size_of,
+    str::{from_utf8, Utf8Error},
+};
+
+use log::warn;
+use nom::{
+    bytes::streaming::take,
+    number::streaming::be_u32,
+    number::{complete::be_f64, streaming::le_u64},
+    number::{complete::le_f32, complete::le_f64},
+    number::{complete::le_i16, complete::le_i8},
+    number::{complete::le_i32, streaming::le_u16},
+    number::{complete::le_i64, streaming::le_u32},
+};
+use serde::Serialize;
+
+use crate::{error::LoginItemsError, loginitems_plist::join_url};
+
+// Bookmark documentation:
+// https://mac-alias.readthedocs.io/en/latest/bookmark_fmt.html
+// http://michaellynn.github.io/2015/10/24/apples-bookmarkdata-exposed/
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Bookmark {
+    pub version: u32,                 // Bookmark version from header
+    pub records: Vec<BookmarkRecord>, // Every record of every TOC, in TOC chain order
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BookmarkRecord {
+    pub record_type: u32,     // TOC record type
+    pub level: u32,           // Level of the TOC that contained the record
+    pub data_offset: u32,     // Offset to the record data when parsed (0 for new records)
+    pub value: BookmarkValue, // Decoded record data
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum BookmarkValue {
+    String(String),
+    Data(Vec<u8>),
+    Number {
+        value: i64,
+        data_type: u32,
+    }, // Data type keeps the number width
+    Float {
+        value: f64,
+        data_type: u32,
+    }, // Data type keeps the float width
+    Date(f64), // Seconds since 2001-01-01 (Cocoa epoch)
+    Bool(bool),
+    Array(Vec<BookmarkValue>),
+    Dictionary(Vec<(BookmarkValue, BookmarkValue)>),
+    Uuid(String),
+    Url(String),
+    RelativeUrl {
+        base: Box<BookmarkValue>,     // Base URL
+        relative: Box<BookmarkValue>, // URL or string relative to the base
+    },
+    Bookmark(Box<Bookmark>), // Nested bookmark (volume bookmark and mount point records)
+    Unknown {
+        data_type: u32,
+        data: Vec<u8>,
+    }, // Data type we cannot decode (yet)
+}
+
+impl BookmarkValue {
+    /// Get the URL of an absolute or relative URL value. Relative URLs are resolved against their base URL
+    pub fn url(&self) -> Option<String> {
+        match self {
+            BookmarkValue::Url(url) => Some(url.to_string()),
+            BookmarkValue::RelativeUrl { base, relative } => {
+                let base = base.url()?;
+                match relative.as_ref() {
+                    BookmarkValue::String(relative) | BookmarkValue::Url(relative) => {
+                        Some(join_url(&base, relative))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BookmarkHeader {
+    signature: u32,            // Bookmark Signature "book"
+    bookmark_data_length: u32, // Total size of bookmark
+    version: u32,              // Possible version number
+    bookmark_data_offset: u32, // Offset to start of bookmark data (always 0x30 (48)).
+                               // Followed by 32 bytes of empty/reserved space (48 bytes total)
+}
+
+#[derive(Debug)]
+struct BookmarkData {
+    table_of_contents_offset: u32, // Offset to start of Table of Contents (TOC)
+}
+
+#[derive(Debug)]
+struct TableOfContentsHeader {
+    data_length: u32, // Size of TOC
+    record_type: u16, // Unused TOC record/key type (Possible magic number along side flags (0xfffffffe))
+    flags: u16,       // Unused flag (Possible magic number along side record_type (0xfffffffe))
+}
+
+#[derive(Debug)]
+struct TableOfContentsData {
+    level: u32,              // TOC Data level or identifier (always 1?)
+    next_record_offset: u32, // Offset to next TOC record
+    number_of_records: u32,  // Number of records in TOC
+}
+
+#[derive(Debug)]
+struct TableOfContentsDataRecord {
+    record_type: u32, // Record/Key type
+    data_offset: u32, // Offset to record data
+    reserved: u32,    // Reserved (0)
+}
+
+#[derive(Debug)]
+struct StandardDataRecord {
+    data_length: u32,     // Length of data
+    data_type: u32,       // Type of data
+    record_data: Vec<u8>, // Data
+    record_type: u32,     // Record type (from TableOfContentsDataRecord)
+}
+
+impl Bookmark {
+    // Data types
+    pub const STRING_TYPE: u32 = 0x0101;
+    pub const DATA_TYPE: u32 = 0x0201;
+    pub const NUMBER_ONE_BYTE: u32 = 0x0301;
+    pub const NUMBER_TWO_BYTE: u32 = 0x0302;
+    pub const NUMBER_FOUR_BYTE: u32 = 0x0303;
+    pub const NUMBER_EIGHT_BYTE: u32 = 0x0304;
+    pub const NUMBER_FLOAT32: u32 = 0x0305;
+    pub const NUMBER_FLOAT64: u32 = 0x0306;
+    pub const DATE: u32 = 0x0400;
+    pub const BOOL_FALSE: u32 = 0x0500;
+    pub const BOOL_TRUE: u32 = 0x0501;
+    pub const ARRAY_TYPE: u32 = 0x0601;
+    pub const DICTIONARY: u32 = 0x0701;
+    pub const UUID: u32 = 0x0801;
+    pub const URL: u32 = 0x0901;
+    pub const URL_RELATIVE: u32 = 0x0902;
+
+    // Table of Contents Key types
+    pub const UNKNOWN: u32 = 0x1003;
+    pub const TARGET_PATH: u32 = 0x1004;
+    pub const TARGET_CNID_PATH: u32 = 0x1005;
+    pub const TARGET_FLAGS: u32 = 0x1010;
+    pub const TARGET_FILENAME: u32 = 0x1020;
+    pub const TARGET_CREATION_DATE: u32 = 0x1040;
+    pub const UNKNOWN2: u32 = 0x1054;
+    pub const UNKNOWN3: u32 = 0x1055;
+    pub const UNKNOWN4: u32 = 0x1056;
+    pub const UNKNOWN5: u32 = 0x1057;
+    pub const UNKNOWN6: u32 = 0x1101;
+    pub const UNKNOWN7: u32 = 0x1102;
+    pub const TOC_PATH: u32 = 0x2000;
+    pub const VOLUME_PATH: u32 = 0x2002;
+    pub const VOLUME_URL: u32 = 0x2005;
+    pub const VOLUME_NAME: u32 = 0x2010;
+    pub const VOLUME_UUID: u32 = 0x2011;
+    pub const VOLUME_SIZE: u32 = 0x2012;
+    pub const VOLUME_CREATION: u32 = 0x2013;
+    pub const VOLUME_BOOKMARK: u32 = 0x2040;
+    pub const VOLUME_FLAGS: u32 = 0x2020;
+    pub const VOLUME_ROOT: u32 = 0x2030;
+    pub const VOLUME_MOUNT_POINT: u32 = 0x2050;
+    pub const UNKNOWN8: u32 = 0x2070;
+    pub const CONTAIN_FOLDER_INDEX: u32 = 0xc001;
+    pub const CREATOR_USERNAME: u32 = 0xc011;
+    pub const CREATOR_UID: u32 = 0xc012;
+    pub const FILE_REF_FLAG: u32 = 0xd001;
+    pub const CREATION_OPTIONS: u32 = 0xd010;
+    pub const URL_LENGTH_ARRAY: u32 = 0xe003;
+    pub const LOCALIZED_NAME: u32 = 0xf017;
+    pub const UNKNOWN9: u32 = 0xf022;
+    pub const SECURITY_EXTENSION: u32 = 0xf080;
+    pub const UNKNOWN10: u32 = 0xf081;
+
+    // Limits for arrays and dictionaries that point at other arrays and dictionaries
+    const MAX_DEPTH: usize = 32;
+    const MAX_VALUES: usize = 65536;
+    // Limit for bookmarks nested inside volume records
+    const MAX_BOOKMARK_DEPTH: usize = 4;
+
+    /// Parse bookmark data (header, data and Table of Contents)
+    pub fn parse(data: &[u8]) -> Result<Bookmark, LoginItemsError> {
+        Bookmark::parse_nested(data, 0)
+    }
+
+    /// Parse bookmark data at a nesting depth
+    fn parse_nested(data: &[u8], depth: usize) -> Result<Bookmark, LoginItemsError> {
+        // Data too small for a bookmark header is not a bookmark
+        let (bookmark_data, bookmark_header) = match Bookmark::bookmark_header(data) {
+            Ok(results) => results,
+            Err(_) => return Err(LoginItemsError::NotABookmark),
+        };
+        let book_sig: u32 = 1802465122;
+        let book_data_offset: u32 = 48;
+
+        // Check for bookmark signature and expected offset
+        if bookmark_header.signature != book_sig {
+            return Err(LoginItemsError::NotABookmark);
+        }
+        if bookmark_header.bookmark_data_offset != book_data_offset {
+            return Err(LoginItemsError::UnsupportedVersion {
+                version: bookmark_header.version,
+            });
+        }
+        let mut records = Bookmark::bookmark_data(bookmark_data)?;
+        Bookmark::volume_bookmarks(&mut records, depth);
+        let bookmark = Bookmark {
+            version: bookmark_header.version,
+            records,
+        };
+        Ok(bookmark)
+    }
+
+    /// Get the first record of a type. Earlier TOCs take precedence if a record type appears more than once
+    pub fn record(&self, record_type: u32) -> Option<&BookmarkRecord> {
+        self.records
+            .iter()
+            .find(|record| record.record_type == record_type)
+    }
+
+    /// Parse the bookmarks embedded in the volume bookmark and mount point records.
+    /// Nested bookmarks that fail to parse are kept as data
+    fn volume_bookmarks(records: &mut [BookmarkRecord], depth: usize) {
+        for record in records.iter_mut() {
+            let record_type = record.record_type;
+            if record_type != Bookmark::VOLUME_BOOKMARK
+                && record_type != Bookmark::VOLUME_MOUNT_POINT
+            {
+                continue;
+            }
+            let nested = match &record.value {
+                BookmarkValue::Data(data) if data.starts_with(b"book") => {
+                    if depth + 1 >= Bookmark::MAX_BOOKMARK_DEPTH {
+                        warn!(
+                            "Bookmark in record {:#x} is nested too deeply, keeping data",
+                            record_type
+                        );
+                        continue;
+                    }
+                    Bookmark::parse_nested(data, depth + 1)
+                }
+                _ => continue,
+            };
+            match nested {
+                Ok(bookmark) => record.value = BookmarkValue::Bookmark(Box::new(bookmark)),
+                Err(err) => warn!(
+                    "Failed to parse nested bookmark in record {:#x}: {}",
+                    record_type, err
+                ),
+            }
+        }
+    }
+
+    /// Get the name of a TOC record type
+    pub fn record_name(record_type: u32) -> String {
+        let name = match record_type {
+            Bookmark::UNKNOWN => "UNKNOWN",
+            Bookmark::TARGET_PATH => "TARGET_PATH",
+            Bookmark::TARGET_CNID_PATH => "TARGET_CNID_PATH",
+            Bookmark::TARGET_FLAGS => "TARGET_FLAGS",
+            Bookmark::TARGET_FILENAME => "TARGET_FILENAME",
+            Bookmark::TARGET_CREATION_DATE => "TARGET_CREATION_DATE",
+            Bookmark::UNKNOWN2 => "UNKNOWN2",
+            Bookmark::UNKNOWN3 => "UNKNOWN3",
+            Bookmark::UNKNOWN4 => "UNKNOWN4",
+            Bookmark::UNKNOWN5 => "UNKNOWN5",
+            Bookmark::UNKNOWN6 => "UNKNOWN6",
+            Bookmark::UNKNOWN7 => "UNKNOWN7",
+            Bookmark::TOC_PATH => "TOC_PATH",
+            Bookmark::VOLUME_PATH => "VOLUME_PATH",
+            Bookmark::VOLUME_URL => "VOLUME_URL",
+            Bookmark::VOLUME_NAME => "VOLUME_NAME",
+            Bookmark::VOLUME_UUID => "VOLUME_UUID",
+            Bookmark::VOLUME_SIZE => "VOLUME_SIZE",
+            Bookmark::VOLUME_CREATION => "VOLUME_CREATION",
+            Bookmark::VOLUME_BOOKMARK => "VOLUME_BOOKMARK",
+            Bookmark::VOLUME_FLAGS => "VOLUME_FLAGS",
+            Bookmark::VOLUME_ROOT => "VOLUME_ROOT",
+            Bookmark::VOLUME_MOUNT_POINT => "VOLUME_MOUNT_POINT",
+            Bookmark::UNKNOWN8 => "UNKNOWN8",
+            Bookmark::CONTAIN_FOLDER_INDEX => "CONTAIN_FOLDER_INDEX",
+            Bookmark::CREATOR_USERNAME => "CREATOR_USERNAME",
+            Bookmark::CREATOR_UID => "CREATOR_UID",
+            Bookmark::FILE_REF_FLAG => "FILE_REF_FLAG",
+            Bookmark::CREATION_OPTIONS => "CREATION_OPTIONS",
+            Bookmark::URL_LENGTH_ARRAY => "URL_LENGTH_ARRAY",
+            Bookmark::LOCALIZED_NAME => "LOCALIZED_NAME",
+            Bookmark::UNKNOWN9 => "UNKNOWN9",
+            Bookmark::SECURITY_EXTENSION => "SECURITY_EXTENSION",
+            Bookmark::UNKNOWN10 => "UNKNOWN10",
+            _ => return format!("0x{:x}", record_type),
+        };
+        name.to_string()
+    }
+
+    /// Parse bookmark header
+    fn bookmark_header(data: &[u8]) -> nom::IResult<&[u8], BookmarkHeader> {
+        let mut bookmark_header = BookmarkHeader {
+            signature: 0,
+            bookmark_data_length: 0,
+            version: 0,
+            bookmark_data_offset: 0,
+        };
+
+        let (input, sig) = take(size_of::<u32>())(data)?;
+        let (input, data_length) = take(size_of::<u32>())(input)?;
+        let (input, version) = take(size_of::<u32>())(input)?;
+        let (input, data_offset) = take(size_of::<u32>())(input)?;
+
+        let filler_size: u32 = 32;
+        let (input, _) = take(filler_size)(input)?;
+
+        let (_, bookmark_sig) = le_u32(sig)?;
+        let (_, bookmark_data_length) = le_u32(data_length)?;
+        let (_, bookmark_version) = be_u32(version)?;
+        let (_, bookmark_data_offset) = le_u32(data_offset)?;
+
+        bookmark_header.signature = bookmark_sig;
+        bookmark_header.bookmark_data_length = bookmark_data_length;
+        bookmark_header.version = bookmark_version;
+        bookmark_header.bookmark_data_offset = bookmark_data_offset;
+        Ok((input, bookmark_header))
+    }
+
+    /// Parse the core bookmark data and decode every record in every TOC
+    pub(crate) fn bookmark_data(data: &[u8]) -> Result<Vec<BookmarkRecord>, LoginItemsError> {
+        let mut book_data = BookmarkData {
+            table_of_contents_offset: 0,
+        };
+        // Offsets in the bookmark data are relative to the end of the 48 byte header
+        let header_size: usize = 48;
+        let truncated_toc = |offset: u32| LoginItemsError::TruncatedToc {
+            offset: header_size + offset as usize,
+        };
+
+        let (core_data, offset) =
+            take::<_, _, ()>(size_of::<u32>())(data).map_err(|_| truncated_toc(0))?;
+        let (_, toc_offset) = le_u32::<_, ()>(offset).map_err(|_| truncated_toc(0))?;
+
+        book_data.table_of_contents_offset = toc_offset;
+        let toc_offset_size: u32 = 4;
+        if book_data.table_of_contents_offset < toc_offset_size {
+            return Err(truncated_toc(toc_offset));
+        }
+
+        let mut records: Vec<BookmarkRecord> = Vec::new();
+        // Containers can point at each other, limit the total number of values we decode
+        let mut budget = Bookmark::MAX_VALUES;
+        // Each TOC points to the next one. Zero ends the chain
+        let mut toc_offsets: Vec<u32> = Vec::new();
+        let mut next_toc_offset = book_data.table_of_contents_offset;
+        while next_toc_offset != 0 {
+            if toc_offsets.contains(&next_toc_offset) {
+                warn!(
+                    "Bookmark TOC at offset {:#x} was already parsed, stopping TOC chain",
+                    header_size + next_toc_offset as usize
+                );
+                break;
+            }
+            toc_offsets.push(next_toc_offset);
+
+            let toc_offset = next_toc_offset;
+            let (input, _) =
+                take::<_, _, ()>(toc_offset)(data).map_err(|_| truncated_toc(toc_offset))?;
+            let (input, toc_header) =
+                Bookmark::table_of_contents_header(input).map_err(|_| truncated_toc(toc_offset))?;
+
+            let (toc_record_data, toc_content_data) =
+                Bookmark::table_of_contents_data(input, toc_header.data_length)
+                    .map_err(|_| truncated_toc(toc_offset))?;
+
+            let (_, toc_content_data_record) = Bookmark::table_of_contents_record(
+                toc_record_data,
+                &toc_content_data.number_of_records,
+            )
+            .map_err(|_| truncated_toc(toc_offset))?;
+
+            for record in toc_content_data_record {
+                let truncated_record = |offset: u32| LoginItemsError::TruncatedRecord {
+                    offset: header_size + offset as usize,
+                    record_type: record.record_type,
+                };
+                let (_, standard_data) = Bookmark::bookmark_standard_data(core_data, &record)
+                    .map_err(|_| truncated_record(record.data_offset))?;
+
+                let value = Bookmark::decode_value(
+                    core_data,
+                    &standard_data,
+                    record.data_offset,
+                    0,
+                    &mut budget,
+                )?;
+                let bookmark_record = BookmarkRecord {
+                    record_type: record.record_type,
+                    level: toc_content_data.level,
+                    data_offset: record.data_offset,
+                    value,
+                };
+                records.push(bookmark_record);
+            }
+            next_toc_offset = toc_content_data.next_record_offset;
+        }
+        Ok(records)
+    }
+
+    /// Decode record data based on its data type. Data that cannot be decoded is kept as is
+    fn decode_value(
+        core_data: &[u8],
+        standard_data: &StandardDataRecord,
+        data_offset: u32,
+        depth: usize,
+        budget: &mut usize,
+    ) -> Result<BookmarkValue, LoginItemsError> {
+        let data = &standard_data.record_data;
+        let value =
+            match standard_data.data_type {
+                Bookmark::STRING_TYPE => Bookmark::bookmark_data_type_string(data)
+                    .ok()
+                    .map(BookmarkValue::String),
+                Bookmark::DATA_TYPE => Some(BookmarkValue::Data(data.to_vec())),
+                Bookmark::NUMBER_ONE_BYTE => Bookmark::bookmark_data_type_number_one(data)
+                    .ok()
+                    .map(|(_, number)| BookmarkValue::Number {
+                        value: number as i64,
+                        data_type: standard_data.data_type,
+                    }),
+                Bookmark::NUMBER_TWO_BYTE => Bookmark::bookmark_data_type_number_two(data)
+                    .ok()
+                    .map(|(_, number)| BookmarkValue::Number {
+                        value: number as i64,
+                        data_type: standard_data.data_type,
+                    }),
+                Bookmark::NUMBER_FOUR_BYTE => Bookmark::bookmark_data_type_number_four(data)
+                    .ok()
+                    .map(|(_, number)| BookmarkValue::Number {
+                        value: number as i64,
+                        data_type: standard_data.data_type,
+                    }),
+                Bookmark::NUMBER_EIGHT_BYTE => Bookmark::bookmark_data_type_number_eight(data)
+                    .ok()
+                    .map(|(_, number)| BookmarkValue::Number {
+                        value: number,
+                        data_type: standard_data.data_type,
+                    }),
+                Bookmark::NUMBER_FLOAT32 => {
+                    Bookmark::bookmark_data_type_float32(data)
+                        .ok()
+                        .map(|(_, number)| BookmarkValue::Float {
+                            value: number as f64,
+                            data_type: standard_data.data_type,
+                        })
+                }
+                Bookmark::NUMBER_FLOAT64 => {
+                    Bookmark::bookmark_data_type_float64(data)
+                        .ok()
+                        .map(|(_, number)| BookmarkValue::Float {
+                            value: number,
+                            data_type: standard_data.data_type,
+                        })
+                }
+                Bookmark::DATE => Bookmark::bookmark_data_type_date(data)
+                    .ok()
+                    .map(|(_, date)| BookmarkValue::Date(date)),
+                Bookmark::BOOL_FALSE => Some(BookmarkValue::Bool(false)),
+                Bookmark::BOOL_TRUE => Some(BookmarkValue::Bool(true)),
+                Bookmark::UUID => Bookmark::bookmark_data_type_uuid(data)
+                    .ok()
+                    .map(|(_, uuid)| BookmarkValue::Uuid(uuid)),
+                Bookmark::URL => Bookmark::bookmark_data_type_string(data)
+                    .ok()
+                    .map(BookmarkValue::Url),
+                Bookmark::ARRAY_TYPE | Bookmark::DICTIONARY | Bookmark::URL_RELATIVE => {
+                    return Bookmark::decode_container(
+                        core_data,
+                        standard_data,
+                        data_offset,
+                        depth,
+                        budget,
+                    );
+                }
+                _ => None,
+            };
+        match value {
+            Some(value) => Ok(value),
+            None => Ok(BookmarkValue::Unknown {
+                data_type: standard_data.data_type,
+                data: data.to_vec(),
+            }),
+        }
+    }
+
+    /// Decode arrays, dictionaries and relative URLs. Their data is offsets to other records
+    fn decode_container(
+        core_data: &[u8],
+        standard_data: &StandardDataRecord,
+        data_offset: u32,
+        depth: usize,
+        budget: &mut usize,
+    ) -> Result<BookmarkValue, LoginItemsError> {
+        let header_size: usize = 48;
+        let record_type = standard_data.record_type;
+        if depth >= Bookmark::MAX_DEPTH {
+            return Err(LoginItemsError::NestingLimit {
+                offset: header_size + data_offset as usize,
+                record_type,
+            });
+        }
+        let truncated_record = LoginItemsError::TruncatedRecord {
+            offset: header_size + data_offset as usize,
+            record_type,
+        };
+
+        let offsets = if standard_data.record_data.is_empty() {
+            Vec::new()
+        } else {
+            match Bookmark::bookmark_array(&standard_data.record_data) {
+                Ok((_, offsets)) => offsets,
+                Err(_) => return Err(truncated_record),
+            }
+        };
+        let record = TableOfContentsDataRecord {
+            record_type,
+            data_offset,
+            reserved: 0,
+        };
+        let std_data_vec = match Bookmark::bookmark_array_data(core_data, offsets.clone(), &record)
+        {
+            Ok((_, std_data_vec)) => std_data_vec,
+            Err(_) => return Err(truncated_record),
+        };
+
+        let mut values: Vec<BookmarkValue> = Vec::new();
+        for (offset, element) in offsets.iter().zip(&std_data_vec) {
+            if *budget == 0 {
+                return Err(LoginItemsError::NestingLimit {
+                    offset: header_size + *offset as usize,
+                    record_type,
+                });
+            }
+            *budget -= 1;
+            let value = Bookmark::decode_value(core_data, element, *offset, depth + 1, budget)?;
+            values.push(value);
+        }
+
+        let value = match standard_data.data_type {
+            // Dictionary data is key and value offset pairs
+            Bookmark::DICTIONARY => {
+                let mut entries: Vec<(BookmarkValue, BookmarkValue)> = Vec::new();
+                let mut pairs = values.into_iter();
+                while let (Some(key), Some(value)) = (pairs.next(), pairs.next()) {
+                    entries.push((key, value));
+                }
+                BookmarkValue::Dictionary(entries)
+            }
+            // Relative URL data is the offset to the base URL followed by the offset to the relative string
+            Bookmark::URL_RELATIVE => match values.as_slice() {
+                [base, relative @ (BookmarkValue::String(_) | BookmarkValue::Url(_))]
+                    if base.url().is_some() =>
+                {
+                    BookmarkValue::RelativeUrl {
+                        base: Box::new(base.clone()),
+                        relative: Box::new(relative.clone()),
+                    }
+                }
+                _ => BookmarkValue::Unknown {
+                    data_type: standard_data.data_type,
+                    data: standard_data.record_data.to_vec(),
+                },
+            },
+            _ => BookmarkValue::Array(values),
+        };
+        Ok(value)
+    }
+
+    /// Parse the Table of Contents (TOC) header
+    fn table_of_contents_header(data: &[u8]) -> nom::IResult<&[u8], TableOfContentsHeader> {
+        let mut toc_header = TableOfContentsHeader {
+            data_length: 0,
+            record_type: 0,
+            flags: 0,
+        };
+
+        let (input, length) = take(size_of::<u32>())(data)?;
+        let (input, record_type) = take(size_of::<u16>())(input)?;
+        let (input, flags) = take(size_of::<u16>())(input)?;
+
+        let (_, toc_length) = le_u32(length)?;
+        let (_, toc_record_type) = le_u16(record_type)?;
+        let (_, toc_flags) = le_u16(flags)?;
+
+        toc_header.data_length = toc_length;
+        toc_header.record_type = toc_record_type;
+        toc_header.flags = toc_flags;
+
+        Ok((input, toc_header))
+    }
+
+    /// Parse the TOC data
+    fn table_of_contents_data(
+        data: &[u8],
+        data_length: u32,
+    ) -> nom::IResult<&[u8], TableOfContentsData> {
+        let mut toc_data = TableOfContentsData {
+            level: 0,
+            next_record_offset: 0,
+            number_of_records: 0,
+        };
+
+        let (input, level) = take(size_of::<u32>())(data)?;
+        let (input, next_record_offset) = take(size_of::<u32>())(input)?;
+        let (input, number_of_records) = take(size_of::<u32>())(input)?;
+
+        let mut final_input = input;
+
+        let (_, toc_level) = le_u32(level)?;
+        let (_, toc_next_record) = le_u32(next_record_offset)?;
+        let (_, toc_number_records) = le_u32(number_of_records)?;
+
+        toc_data.level = toc_level;
+        toc_data.next_record_offset = toc_next_record;
+        toc_data.number_of_records = toc_number_records;
+
+        let record_size: u32 = 12;
+        // Record count is untrusted, a count that overflows cannot fit in the bookmark
+        let record_data = match record_size.checked_mul(toc_data.number_of_records) {
+            Some(record_data) => record_data,
+            None => {
+                return Err(nom::Err::Error(nom::error::Error::new(
+                    input,
+                    nom::error::ErrorKind::TooLarge,
+                )))
+            }
+        };
+
+        // Verify TOC data length is equal to number of records (Number of Records * Record Size (12 bytes))
+        // Some TOC headers may give incorrect? data length (they are 8 bytes short, https://mac-alias.readthedocs.io/en/latest/bookmark_fmt.html)
+        if record_data > data_length {
+            let (_, actual_record_data) = take(record_data)(input)?;
+            final_input = actual_record_data;
+        }
+        Ok((final_input, toc_data))
+    }
+
+    /// Parse the TOC data record
+    fn table_of_contents_record<'a>(
+        data: &'a [u8],
+        records: &u32,
+    ) -> nom::IResult<&'a [u8], Vec<TableOfContentsDataRecord>> {
+        let mut input_data = data;
+        let mut record: u32 = 0;
+        let mut toc_records_vec: Vec<TableOfContentsDataRecord> = Vec::new();
+
+        // Loop through until all records have been parsed
+        loop {
+            if &record == records {
+                break;
+            }
+            record += 1;
+            let mut toc_data_record = TableOfContentsDataRecord {
+                record_type: 0,
+                data_offset: 0,
+                reserved: 0,
+            };
+
+            let (input, record_type) = take(size_of::<u32>())(input_data)?;
+            let (input, offset) = take(size_of::<u32>())(input)?;
+            let (input, reserved) = take(size_of::<u32>())(input)?;
+            input_data = input;
+
+            let (_, toc_record) = le_u32(record_type)?;
+            let (_, toc_offset) = le_u32(offset)?;
+            let (_, toc_reserved) = le_u32(reserved)?;
+
+            toc_data_record.record_type = toc_record;
+            toc_data_record.data_offset = toc_offset;
+            toc_data_record.reserved = toc_reserved;
+            toc_records_vec.push(toc_data_record);
+        }
+        Ok((input_data, toc_records_vec))
+    }
+
+    /// Parse the bookmark standard data
+    fn bookmark_standard_data<'a>(
+        bookmark_data: &'a [u8],
+        toc_record: &TableOfContentsDataRecord,
+    ) -> nom::IResult<&'a [u8], StandardDataRecord> {
+        let mut toc_standard_data = StandardDataRecord {
+            data_length: 0,
+            record_data: Vec::new(),
+            data_type: 0,
+            record_type: 0,
+        };
+        let toc_offset_value: u32 = 4;
+
+        // Offset points inside the bookmark data header
+        if toc_record.data_offset < toc_offset_value {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                bookmark_data,
+                nom::error::ErrorKind::Verify,
+            )));
+        }
+
+        // Subtract toc offset value from data offset since we already nom'd the value
+        let offset = (toc_record.data_offset - toc_offset_value) as usize;
+
+        // Nom data til standard data info
+        let (input, _) = take(offset)(bookmark_data)?;
+
+        let (input, length) = take(size_of::<u32>())(input)?;
+        let (input, data_type) = take(size_of::<u32>())(input)?;
+
+        let (_, standard_length) = le_u32(length)?;
+        let (_, standard_data_type) = le_u32(data_type)?;
+
+        let (input, record_data) = take(standard_length)(input)?;
+
+        toc_standard_data.data_length = standard_length;
+        toc_standard_data.data_type = standard_data_type;
+        toc_standard_data.record_data = record_data.to_vec();
+        toc_standard_data.record_type = toc_record.record_type;
+
+        Ok((input, toc_standard_data))
+    }
+
+    /// Parse the records the bookmark array data points to
+    fn bookmark_array_data<'a>(
+        data: &'a [u8],
+        array_offsets: Vec<u32>,
+        record: &TableOfContentsDataRecord,
+    ) -> nom::IResult<&'a [u8], Vec<StandardDataRecord>> {
+        let mut standard_data_vec: Vec<StandardDataRecord> = Vec::new();
+
+        for offset in array_offsets {
+            let data_record = TableOfContentsDataRecord {
+                record_type: record.record_type,
+                data_offset: offset,
+                reserved: 0,
+            };
+            let (_, results) = Bookmark::bookmark_standard_data(data, &data_record)?;
+            standard_data_vec.push(results);
+        }
+
+        Ok((data, standard_data_vec))
+    }
+
+    /// Get the offsets for the array data
+    fn bookmark_array(standard_data: &[u8]) -> nom::IResult<&[u8], Vec<u32>> {
+        let mut array_offsets: Vec<u32> = Vec::new();
+        let mut input = standard_data;
+        let offset_size: u32 = 4;
+
+        loop {
+            let (input_data, offset) = take(offset_size)(input)?;
+            let (_, data_offsets) = le_u32(offset)?;
+
+            array_offsets.push(data_offsets);
+            input = input_data;
+            if input_data.is_empty() {
+                break;
+            }
+        }
+        Ok((input, array_offsets))
+    }
+
+    /// Get the path/strings related to bookmark
+    pub(crate) fn bookmark_data_type_string(standard_data: &[u8]) -> Result<String, Utf8Error> {
+        let path = from_utf8(standard_data)?;
+        Ok(path.to_string())
+    }
+
+    /// Get bookmark target flags
+    pub(crate) fn bookmark_target_flags(standard_data: &[u8]) -> nom::IResult<&[u8], Vec<u64>> {
+        let mut input = standard_data;
+        let mut array_flags: Vec<u64> = Vec::new();
+        let max_flag_size = 3;
+
+        // Target flags are composed of three (3) 8 byte values
+        loop {
+            let (data, flag) = take(size_of::<u64>())(input)?;
+            input = data;
+            let (_, flags) = le_u64(flag)?;
+            array_flags.push(flags);
+            if input.is_empty() || array_flags.len() == max_flag_size {
+                break;
+            }
+        }
+        Ok((input, array_flags))
+    }
+
+    /// Get bookmark volume size
+    fn bookmark_data_type_number_eight(standard_data: &[u8]) -> nom::IResult<&[u8], i64> {
+        let (data, size) = le_i64(standard_data)?;
+        Ok((data, size))
+    }
+
+    /// Get bookmark one byte number
+    fn bookmark_data_type_number_one(standard_data: &[u8]) -> nom::IResult<&[u8], i8> {
+        let (data, number) = le_i8(standard_data)?;
+        Ok((data, number))
+    }
+
+    /// Get bookmark two byte number
+    fn bookmark_data_type_number_two(standard_data: &[u8]) -> nom::IResult<&[u8], i16> {
+        let (data, number) = le_i16(standard_data)?;
+        Ok((data, number))
+    }
+
+    /// Get bookmark float32 number
+    fn bookmark_data_type_float32(standard_data: &[u8]) -> nom::IResult<&[u8], f32> {
+        let (data, number) = le_f32(standard_data)?;
+        Ok((data, number))
+    }
+
+    /// Get bookmark float64 number
+    fn bookmark_data_type_float64(standard_data: &[u8]) -> nom::IResult<&[u8], f64> {
+        let (data, number) = le_f64(standard_data)?;
+        Ok((data, number))
+    }
+
+    /// Get bookmark UUID as a hyphenated string
+    fn bookmark_data_type_uuid(standard_data: &[u8]) -> nom::IResult<&[u8], String> {
+        let uuid_size: usize = 16;
+        let (data, uuid_data) = take(uuid_size)(standard_data)?;
+        let hex: Vec<String> = uuid_data
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+        let uuid = format!(
+            "{}-{}-{}-{}-{}",
+            hex[0..4].concat(),
+            hex[4..6].concat(),
+            hex[6..8].concat(),
+            hex[8..10].concat(),
+            hex[10..16].concat()
+        );
+        Ok((data, uuid))
+    }
+
+    /// Get bookmark folder index
+    fn bookmark_data_type_number_four(standard_data: &[u8]) -> nom::IResult<&[u8], i32> {
+        let (data, index) = le_i32(standard_data)?;
+        Ok((data, index))
+    }
+
+    /// Get bookmark creation timestamps
+    fn bookmark_data_type_date(standard_data: &[u8]) -> nom::IResult<&[u8], f64> {
+        //Apple stores timestamps as Big Endian Float64
+        let (data, creation) = be_f64(standard_data)?;
+        Ok((data, creation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{Bookmark, BookmarkValue, TableOfContentsDataRecord};
+    use crate::{error::LoginItemsError, loginitems_plist::get_bookmarks};
+
+    #[test]
+    fn test_parse() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/backgrounditems_sierra.btm");
+        let bookmarks = get_bookmarks(&test_location.display().to_string()).unwrap();
+        let bookmark = Bookmark::parse(&bookmarks[0]).unwrap();
+
+        assert!(bookmark.version == 1040);
+        assert!(bookmark.records.len() == 14);
+
+        let path = BookmarkValue::Array(vec![
+            BookmarkValue::String("Applications".to_string()),
+            BookmarkValue::String("Syncthing.app".to_string()),
+        ]);
+        assert!(bookmark.record(Bookmark::TARGET_PATH).unwrap().value == path);
+        assert!(
+            bookmark.record(Bookmark::VOLUME_URL).unwrap().value
+                == BookmarkValue::Url("file:///".to_string())
+        );
+        assert!(bookmark.record(Bookmark::VOLUME_ROOT).unwrap().value == BookmarkValue::Bool(true));
+    }
+
+    #[test]
+    fn test_bookmark_data_all_records() {
+        let test_data = [
+            28, 0, 0, 0, 4, 0, 0, 0, 1, 1, 0, 0, 116, 101, 115, 116, 4, 0, 0, 0, 3, 3, 0, 0, 7, 0,
+            0, 0, 32, 0, 0, 0, 254, 255, 255, 255, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 32, 16, 0,
+            0, 4, 0, 0, 0, 0, 0, 0, 0, 34, 240, 0, 0, 16, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        let records = Bookmark::bookmark_data(test_data.as_slice()).unwrap();
+
+        // Records LoginItemsData ignores are still decoded
+        assert!(records.len() == 2);
+        assert!(
+            records[0].record_type == Bookmark::TARGET_FILENAME
+                && records[0].value == BookmarkValue::String("test".to_string())
+        );
+        assert!(records[1].record_type == Bookmark::UNKNOWN9);
+        assert!(
+            records[1].value
+                == BookmarkValue::Number {
+                    value: 7,
+                    data_type: Bookmark::NUMBER_FOUR_BYTE,
+                }
+        );
+        assert!(records[1].data_offset == 16);
+    }
+
+    #[test]
+    fn test_record_name() {
+        assert!(Bookmark::record_name(Bookmark::TARGET_FILENAME) == "TARGET_FILENAME");
+        assert!(Bookmark::record_name(Bookmark::UNKNOWN2) == "UNKNOWN2");
+        assert!(Bookmark::record_name(0x1234) == "0x1234");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/backgrounditems_sierra.btm");
+        let bookmarks = get_bookmarks(&test_location.display().to_string()).unwrap();
+        let bookmark = &bookmarks[0];
+
+        let result = Bookmark::parse(&bookmark[..20]);
+        assert!(matches!(result, Err(LoginItemsError::NotABookmark)));
+
+        let result = Bookmark::parse(&bookmark[..100]);
+        assert!(matches!(
+            result,
+            Err(LoginItemsError::TruncatedToc { offset: 568 })
+        ));
+
+        // Point the first TOC record (TARGET_PATH) past the end of the bookmark
+        let mut bad_record = bookmark.to_vec();
+        let record_offset = 48 + 520 + 8 + 12 + 4;
+        bad_record[record_offset..record_offset + 4].copy_from_slice(&[0xf0, 0xff, 0, 0]);
+        let result = Bookmark::parse(&bad_record);
+        assert!(matches!(
+            result,
+            Err(LoginItemsError::TruncatedRecord {
+                offset: 0x10020,
+                record_type: 0x1004
+            })
+        ));
+    }
+
+    #[test]
+    fn test_bookmark_header() {
+        let test_header = [
+            98, 111, 111, 107, 72, 2, 0, 0, 0, 0, 4, 16, 48, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        let (_, header) = Bookmark::bookmark_header(test_header.as_slice()).unwrap();
+        let book_sig: u32 = 1802465122;
+        let book_length: u32 = 584;
+        let book_offset: u32 = 48;
+        let book_version: u32 = 1040;
+        assert!(header.signature == book_sig);
+        assert!(header.bookmark_data_length == book_length);
+        assert!(header.bookmark_data_offset == book_offset);
+        assert!(header.version == book_version);
+    }
+
+    #[test]
+    fn test_table_of_contents_header() {
+        let test_header = [192, 0, 0, 0, 254, 255, 255, 255];
+        let (_, header) = Bookmark::table_of_contents_header(test_header.as_slice()).unwrap();
+        let toc_length: u32 = 192;
+        let toc_record_type: u16 = 65534;
+        let toc_flags: u16 = 65535;
+        assert!(header.data_length == toc_length);
+        assert!(header.record_type == toc_record_type);
+        assert!(header.flags == toc_flags);
+    }
+
+    #[test]
+    fn test_table_of_contents_data() {
+        let test_data = [
+            1, 0, 0, 0, 0, 0, 0, 0, 15, 0, 0, 0, 4, 16, 0, 0, 52, 0, 0, 0, 0, 0, 0, 0, 5, 16, 0, 0,
+        ];
+        let record_data_size = 192;
+        let (_, toc_data) =
+            Bookmark::table_of_contents_data(test_data.as_slice(), record_data_size).unwrap();
+        let level = 1;
+        let next_record_offset = 0;
+        let number_of_records = 15;
+        assert!(toc_data.level == level);
+        assert!(toc_data.next_record_offset == next_record_offset);
+        assert!(toc_data.number_of_records == number_of_records);
+    }
+
+    #[test]
+    fn test_table_of_contents_record() {
+        let test_record = [
+            4, 16, 0, 0, 48, 0, 0, 0, 0, 0, 0, 0, 5, 16, 0, 0, 96, 0, 0, 0, 0, 0, 0, 0, 16, 16, 0,
+            0, 128, 0, 0, 0, 0, 0, 0, 0, 64, 16, 0, 0, 112, 0, 0, 0, 0, 0, 0, 0, 2, 32, 0, 0, 48,
+            1, 0, 0, 0, 0, 0, 0, 5, 32, 0, 0, 160, 0, 0, 0, 0, 0, 0, 0, 16, 32, 0, 0, 176, 0, 0, 0,
+            0, 0, 0, 0, 17, 32, 0, 0, 228, 0, 0, 0, 0, 0, 0, 0, 18, 32, 0, 0, 196, 0, 0, 0, 0, 0,
+            0, 0, 19, 32, 0, 0, 212, 0, 0, 0, 0, 0, 0, 0, 32, 32, 0, 0, 16, 1, 0, 0, 0, 0, 0, 0,
+            48, 32, 0, 0, 60, 1, 0, 0, 0, 0, 0, 0, 23, 240, 0, 0, 68, 1, 0, 0, 0, 0, 0, 0, 128,
+            240, 0, 0, 88, 1, 0, 0, 0, 0, 0, 0,
+        ];
+        let records = 14;
+
+        let (_, record) =
+            Bookmark::table_of_contents_record(test_record.as_slice(), &records).unwrap();
+        let record_type = 4100;
+        let record_offset = 48;
+        let record_reserved = 0;
+
+        assert!(record[0].record_type == record_type);
+        assert!(record[0].data_offset == record_offset);
+        assert!(record[0].reserved == record_reserved);
+        assert!(record.len() == usize::try_from(records).unwrap());
+    }
+
+    #[test]
+    fn test_bookmark_standard_data() {
+        let bookmark_data = [
+            12, 0, 0, 0, 1, 1, 0, 0, 65, 112, 112, 108, 105, 99, 97, 116, 105, 111, 110, 115, 13,
+            0, 0, 0, 1, 1, 0, 0, 83, 121, 110, 99, 116, 104, 105, 110, 103, 46, 97, 112, 112, 0, 0,
+            0, 8, 0, 0, 0, 1, 6, 0, 0, 4, 0, 0, 0, 24, 0, 0, 0, 8, 0, 0, 0, 4, 3, 0, 0, 103, 0, 0,
+            0, 0, 0, 0, 0, 8, 0, 0, 0, 4, 3, 0, 0, 42, 198, 10, 0, 0, 0, 0, 0, 8, 0, 0, 0, 1, 6, 0,
+            0, 64, 0, 0, 0, 80, 0, 0, 0, 8, 0, 0, 0, 0, 4, 0, 0, 65, 195, 213, 41, 226, 128, 0, 0,
+            24, 0, 0, 0, 1, 2, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 15, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 8, 0, 0, 0, 1, 9, 0, 0, 102, 105, 108, 101, 58, 47, 47, 47, 12, 0, 0, 0, 1,
+            1, 0, 0, 77, 97, 99, 105, 110, 116, 111, 115, 104, 32, 72, 68, 8, 0, 0, 0, 4, 3, 0, 0,
+            0, 96, 127, 115, 37, 0, 0, 0, 8, 0, 0, 0, 0, 4, 0, 0, 65, 172, 190, 215, 104, 0, 0, 0,
+            36, 0, 0, 0, 1, 1, 0, 0, 48, 65, 56, 49, 70, 51, 66, 49, 45, 53, 49, 68, 57, 45, 51,
+            51, 51, 53, 45, 66, 51, 69, 51, 45, 49, 54, 57, 67, 51, 54, 52, 48, 51, 54, 48, 68, 24,
+            0, 0, 0, 1, 2, 0, 0, 129, 0, 0, 0, 1, 0, 0, 0, 239, 19, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, 0, 47, 0, 0, 0, 0, 0, 0, 0, 1, 5, 0, 0, 9, 0, 0, 0, 1,
+            1, 0, 0, 83, 121, 110, 99, 116, 104, 105, 110, 103, 0, 0, 0, 166, 0, 0, 0, 1, 2, 0, 0,
+            54, 52, 99, 98, 55, 101, 97, 97, 57, 97, 49, 98, 98, 99, 99, 99, 52, 101, 49, 51, 57,
+            55, 99, 57, 102, 50, 97, 52, 49, 49, 101, 98, 101, 53, 51, 57, 99, 100, 50, 57, 59, 48,
+            48, 48, 48, 48, 48, 48, 48, 59, 48, 48, 48, 48, 48, 48, 48, 48, 59, 48, 48, 48, 48, 48,
+            48, 48, 48, 48, 48, 48, 48, 48, 48, 50, 48, 59, 99, 111, 109, 46, 97, 112, 112, 108,
+            101, 46, 97, 112, 112, 45, 115, 97, 110, 100, 98, 111, 120, 46, 114, 101, 97, 100, 45,
+            119, 114, 105, 116, 101, 59, 48, 49, 59, 48, 49, 48, 48, 48, 48, 48, 52, 59, 48, 48,
+            48, 48, 48, 48, 48, 48, 48, 48, 48, 97, 99, 54, 50, 97, 59, 47, 97, 112, 112, 108, 105,
+            99, 97, 116, 105, 111, 110, 115, 47, 115, 121, 110, 99, 116, 104, 105, 110, 103, 46,
+            97, 112, 112, 0, 0, 0,
+        ];
+        let toc_record = TableOfContentsDataRecord {
+            record_type: 8209,
+            data_offset: 228,
+            reserved: 0,
+        };
+        let (_, std_data) =
+            Bookmark::bookmark_standard_data(bookmark_data.as_slice(), &toc_record).unwrap();
+
+        let data_length = 36;
+        let data_type = 257;
+        let record_data = [
+            48, 65, 56, 49, 70, 51, 66, 49, 45, 53, 49, 68, 57, 45, 51, 51, 51, 53, 45, 66, 51, 69,
+            51, 45, 49, 54, 57, 67, 51, 54, 52, 48, 51, 54, 48, 68,
+        ];
+        let record_type = 8209;
+
+        assert!(std_data.data_length == data_length);
+        assert!(std_data.data_type == data_type);
+        assert!(std_data.record_data == record_data);
+        assert!(std_data.record_type == record_type);
+    }
+
+    #[test]
+    fn test_bookmark_array_data() {
+        let test_data = [
+            12, 0, 0, 0, 1, 1, 0, 0, 65, 112, 112, 108, 105, 99, 97, 116, 105, 111, 110, 115, 13,
+            0, 0, 0, 1, 1, 0, 0, 83, 121, 110, 99, 116, 104, 105, 110, 103, 46, 97, 112, 112, 0, 0,
+            0, 8, 0, 0, 0, 1, 6, 0, 0, 4, 0, 0, 0, 24, 0, 0, 0, 8, 0, 0, 0, 4, 3, 0, 0, 103, 0, 0,
+            0, 0, 0, 0, 0, 8, 0, 0, 0, 4, 3, 0, 0, 42, 198, 10, 0, 0, 0, 0, 0, 8, 0, 0, 0, 1, 6, 0,
+            0, 64, 0, 0, 0, 80, 0, 0, 0, 8, 0, 0, 0, 0, 4, 0, 0, 65, 195, 213, 41, 226, 128, 0, 0,
+            24, 0, 0, 0, 1, 2, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 15, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 8, 0, 0, 0, 1, 9, 0, 0, 102, 105, 108, 101, 58, 47, 47, 47, 12, 0, 0, 0, 1,
+            1, 0, 0, 77, 97, 99, 105, 110, 116, 111, 115, 104, 32, 72, 68, 8, 0, 0, 0, 4, 3, 0, 0,
+            0, 96, 127, 115, 37, 0, 0, 0, 8, 0, 0, 0, 0, 4, 0, 0, 65, 172, 190, 215, 104, 0, 0, 0,
+            36, 0, 0, 0, 1, 1, 0, 0, 48, 65, 56, 49, 70, 51, 66, 49, 45, 53, 49, 68, 57, 45, 51,
+            51, 51, 53, 45, 66, 51, 69, 51, 45, 49, 54, 57, 67, 51, 54, 52, 48, 51, 54, 48, 68, 24,
+            0, 0, 0, 1, 2, 0, 0, 129, 0, 0, 0, 1, 0, 0, 0, 239, 19, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, 0, 47, 0, 0, 0, 0, 0, 0, 0, 1, 5, 0, 0, 9, 0, 0, 0, 1,
+            1, 0, 0, 83, 121, 110, 99, 116, 104, 105, 110, 103, 0, 0, 0, 166, 0, 0, 0, 1, 2, 0, 0,
+            54, 52, 99, 98, 55, 101, 97, 97, 57, 97, 49, 98, 98, 99, 99, 99, 52, 101, 49, 51, 57,
+            55, 99, 57, 102, 50, 97, 52, 49, 49, 101, 98, 101, 53, 51, 57, 99, 100, 50, 57, 59, 48,
+            48, 48, 48, 48, 48, 48, 48, 59, 48, 48, 48, 48, 48, 48, 48, 48, 59, 48, 48, 48, 48, 48,
+            48, 48, 48, 48, 48, 48, 48, 48, 48, 50, 48, 59, 99, 111, 109, 46, 97, 112, 112, 108,
+            101, 46, 97, 112, 112, 45, 115, 97, 110, 100, 98, 111, 120, 46, 114, 101, 97, 100, 45,
+            119, 114, 105, 116, 101, 59, 48, 49, 59, 48, 49, 48, 48, 48, 48, 48, 52, 59, 48, 48,
+            48, 48, 48, 48, 48, 48, 48, 48, 48, 97, 99, 54, 50, 97, 59, 47, 97, 112, 112, 108, 105,
+            99, 97, 116, 105, 111, 110, 115, 47, 115, 121, 110, 99, 116, 104, 105, 110, 103, 46,
+            97, 112, 112, 0, 0, 0,
+        ];
+        let test_array_offsets = [4, 24];
+        let toc_record = TableOfContentsDataRecord {
+            record_type: 4100,
+            data_offset: 48,
+            reserved: 0,
+        };
+        let records = 2;
+
+        let (_, std_record) = Bookmark::bookmark_array_data(
+            test_data.as_slice(),
+            test_array_offsets.to_vec(),
+            &toc_record,
+        )
+        .unwrap();
+        let record_type = 4100;
+        let data_type = 257;
+        let record_data = [65, 112, 112, 108, 105, 99, 97, 116, 105, 111, 110, 115];
+        let data_length = 12;
+
+        assert!(std_record[0].record_type == record_type);
+        assert!(std_record[0].data_type == data_type);
+        assert!(std_record[0].record_data == record_data);
+        assert!(std_record[0].data_length == data_length);
+
+        assert!(std_record.len() == usize::try_from(records).unwrap());
+    }
+
+    #[test]
+    fn test_bookmark_array() {
+        let test_array = [4, 0, 0, 0, 24, 0, 0, 0];
+
+        let (_, book_array) = Bookmark::bookmark_array(test_array.as_slice()).unwrap();
+        let offset = 4;
+        let offset_2 = 24;
+
+        let offsets = 2;
+        assert!(book_array.len() == offsets);
+
+        assert!(book_array[0] == offset);
+        assert!(book_array[1] == offset_2);
+    }
+
+    #[test]
+    fn test_bookmark_data_type_string() {
+        let test_path = [83, 121, 110, 99, 116, 104, 105, 110, 103];
+
+        let book_path = Bookmark::bookmark_data_type_string(test_path.as_slice()).unwrap();
+        let path = "Syncthing";
+        assert!(book_path == path);
+    }
+
+    #[test]
+    fn test_bookmark_cnid() {
+        let test_cnid = [42, 198, 10, 0, 0, 0, 0, 0];
+
+        let (_, book_cnid) =
+            Bookmark::bookmark_data_type_number_eight(test_cnid.as_slice()).unwrap();
+        let cnid = 706090;
+        assert!(book_cnid == cnid);
+    }
+
+    #[test]
+    fn test_bookmark_target_flags() {
+        let test_flags = [
+            129, 0, 0, 0, 1, 0, 0, 0, 239, 19, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+
+        let (_, book_flags) = Bookmark::bookmark_target_flags(test_flags.as_slice()).unwrap();
+        let flag = 4294967425;
+        let flag_2 = 4294972399;
+        let flag_3 = 0;
+
+        let flags = 3;
+
+        assert!(book_flags.len() == flags);
+        assert!(book_flags[0] == flag);
+        assert!(book_flags[1] == flag_2);
+        assert!(book_flags[2] == flag_3);
+    }
+
+    #[test]
+    fn test_bookmark_data_type_number_eight() {
+        let test_volume_size = [0, 96, 127, 115, 37, 0, 0, 0];
+
+        let (_, book_size) =
+            Bookmark::bookmark_data_type_number_eight(test_volume_size.as_slice()).unwrap();
+        let size = 160851517440;
+
+        assert!(book_size == size);
+    }
+
+    #[test]
+    fn test_bookmark_data_type_date() {
+        let test_creation = [65, 172, 190, 215, 104, 0, 0, 0];
+
+        let (_, book_creation) =
+            Bookmark::bookmark_data_type_date(test_creation.as_slice()).unwrap();
+        let creation = 241134516.0;
+
+        assert!(book_creation == creation);
+    }
+
+    #[test]
+    fn test_bookmark_data_type_number_four() {
+        let test_creation = [0, 0, 0, 32];
+
+        let (_, creation_options) =
+            Bookmark::bookmark_data_type_number_four(test_creation.as_slice()).unwrap();
+        let options = 536870912;
+        assert!(creation_options == options);
+    }
+
+    #[test]
+    fn test_bookmark_data_type_number_one() {
+        let (_, number) = Bookmark::bookmark_data_type_number_one([0xff].as_slice()).unwrap();
+        assert!(number == -1);
+    }
+
+    #[test]
+    fn test_bookmark_data_type_number_two() {
+        let (_, number) = Bookmark::bookmark_data_type_number_two([0x34, 0x12].as_slice()).unwrap();
+        assert!(number == 0x1234);
+    }
+
+    #[test]
+    fn test_bookmark_data_type_float32() {
+        let test_float = 0.5f32.to_le_bytes();
+        let (_, number) = Bookmark::bookmark_data_type_float32(test_float.as_slice()).unwrap();
+        assert!(number == 0.5);
+    }
+
+    #[test]
+    fn test_bookmark_data_type_float64() {
+        let test_float = 1.5f64.to_le_bytes();
+        let (_, number) = Bookmark::bookmark_data_type_float64(test_float.as_slice()).unwrap();
+        assert!(number == 1.5);
+    }
+
+    #[test]
+    fn test_bookmark_data_type_uuid() {
+        let test_uuid = [
+            0xa1, 0xb2, 0xc3, 0xd4, 0, 0, 0x40, 0, 0x80, 0, 0, 0, 0, 0, 0x01, 0xf5,
+        ];
+        let (_, uuid) = Bookmark::bookmark_data_type_uuid(test_uuid.as_slice()).unwrap();
+        assert!(uuid == "A1B2C3D4-0000-4000-8000-0000000001F5");
+
+        assert!(Bookmark::bookmark_data_type_uuid(&test_uuid[..10]).is_err());
+    }
+
+    /// Build a standard data record padded to four bytes
+    fn test_record(data_type: u32, data: &[u8]) -> Vec<u8> {
+        let mut record = (data.len() as u32).to_le_bytes().to_vec();
+        record.extend_from_slice(&data_type.to_le_bytes());
+        record.extend_from_slice(data);
+        while !record.len().is_multiple_of(4) {
+            record.push(0);
+        }
+        record
+    }
+
+    /// Build bookmark data (records followed by chained TOCs). TOC entries refer to records by index
+    fn test_data_area(records: &[Vec<u8>], tocs: &[&[(u32, usize)]]) -> Vec<u8> {
+        let mut offsets: Vec<u32> = Vec::new();
+        let mut data: Vec<u8> = Vec::new();
+        for record in records {
+            offsets.push(4 + data.len() as u32);
+            data.extend_from_slice(record);
+        }
+
+        let mut area = (4 + data.len() as u32).to_le_bytes().to_vec();
+        area.append(&mut data);
+        for (index, toc) in tocs.iter().enumerate() {
+            let toc_length = 12 + 12 * toc.len() as u32;
+            let level = index as u32 + 1;
+            let mut next_toc = 0;
+            if level < tocs.len() as u32 {
+                next_toc = area.len() as u32 + 8 + toc_length;
+            }
+            for value in [toc_length, 0xfffffffe, level, next_toc, toc.len() as u32] {
+                area.extend_from_slice(&value.to_le_bytes());
+            }
+            for (record_type, index) in toc.iter() {
+                area.extend_from_slice(&record_type.to_le_bytes());
+                area.extend_from_slice(&offsets[*index].to_le_bytes());
+                area.extend_from_slice(&[0, 0, 0, 0]);
+            }
+        }
+        area
+    }
+
+    /// Offsets to records as array data
+    fn test_offsets(offsets: &[u32]) -> Vec<u8> {
+        offsets
+            .iter()
+            .flat_map(|offset| offset.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_bookmark_data_all_types() {
+        // Record offsets: 4, 16, 28, 44, 60, 92, 116, 132, 156, 164, 180, 192
+        let records = vec![
+            test_record(Bookmark::STRING_TYPE, b"key"),
+            test_record(Bookmark::NUMBER_ONE_BYTE, &[0xff]),
+            test_record(Bookmark::ARRAY_TYPE, &test_offsets(&[4, 16])),
+            test_record(Bookmark::DICTIONARY, &test_offsets(&[4, 28])),
+            test_record(Bookmark::URL, b"file:///Applications/"),
+            test_record(Bookmark::STRING_TYPE, b"Syncthing.app"),
+            test_record(Bookmark::URL_RELATIVE, &test_offsets(&[60, 92])),
+            test_record(Bookmark::UUID, &[0x11; 16]),
+            test_record(Bookmark::BOOL_FALSE, &[]),
+            test_record(Bookmark::NUMBER_FLOAT64, &1.5f64.to_le_bytes()),
+            test_record(Bookmark::NUMBER_FLOAT32, &0.5f32.to_le_bytes()),
+            test_record(Bookmark::NUMBER_TWO_BYTE, &[0x34, 0x12]),
+        ];
+        let toc = [
+            (0xf001, 3),
+            (0xf002, 6),
+            (0xf003, 7),
+            (0xf004, 8),
+            (0xf005, 9),
+            (0xf006, 10),
+            (0xf007, 11),
+        ];
+        let test_data = test_data_area(&records, &[&toc]);
+        let records = Bookmark::bookmark_data(&test_data).unwrap();
+
+        let array = BookmarkValue::Array(vec![
+            BookmarkValue::String("key".to_string()),
+            BookmarkValue::Number {
+                value: -1,
+                data_type: Bookmark::NUMBER_ONE_BYTE,
+            },
+        ]);
+        let dictionary =
+            BookmarkValue::Dictionary(vec![(BookmarkValue::String("key".to_string()), array)]);
+        assert!(records[0].value == dictionary);
+        assert!(
+            records[1].value
+                == BookmarkValue::RelativeUrl {
+                    base: Box::new(BookmarkValue::Url("file:///Applications/".to_string())),
+                    relative: Box::new(BookmarkValue::String("Syncthing.app".to_string())),
+                }
+        );
+        assert!(records[1].value.url() == Some("file:///Applications/Syncthing.app".to_string()));
+        assert!(records[1].data_offset == 116);
+        assert!(
+            records[2].value
+                == BookmarkValue::Uuid("11111111-1111-1111-1111-111111111111".to_string())
+        );
+        assert!(records[3].value == BookmarkValue::Bool(false));
+        assert!(
+            records[4].value
+                == BookmarkValue::Float {
+                    value: 1.5,
+                    data_type: Bookmark::NUMBER_FLOAT64,
+                }
+        );
+        assert!(
+            records[5].value
+                == BookmarkValue::Float {
+                    value: 0.5,
+                    data_type: Bookmark::NUMBER_FLOAT32,
+                }
+        );
+        assert!(
+            records[6].value
+                == BookmarkValue::Number {
+                    value: 0x1234,
+                    data_type: Bookmark::NUMBER_TWO_BYTE,
+                }
+        );
+    }
+
+    #[test]
+    fn test_bookmark_data_nesting_limit() {
+        // Array that contains itself
+        let records = vec![test_record(Bookmark::ARRAY_TYPE, &test_offsets(&[4]))];
+        let test_data = test_data_area(&records, &[&[(0xf001, 0)]]);
+        let result = Bookmark::bookmark_data(&test_data);
+        assert!(matches!(
+            result,
+            Err(LoginItemsError::NestingLimit {
+                offset: 52,
+                record_type: 0xf001
+            })
+        ));
+
+        // Array element that points past the end of the data
+        let records = vec![test_record(Bookmark::ARRAY_TYPE, &test_offsets(&[0x1000]))];
+        let test_data = test_data_area(&records, &[&[(0xf001, 0)]]);
+        let result = Bookmark::bookmark_data(&test_data);
+        assert!(matches!(
+            result,
+            Err(LoginItemsError::TruncatedRecord {
+                offset: 52,
+                record_type: 0xf001
+            })
+        ));
+    }
+
+    #[test]
+    fn test_bookmark_data_toc_chain() {
+        let records = vec![
+            test_record(Bookmark::STRING_TYPE, b"first"),
+            test_record(Bookmark::STRING_TYPE, b"second"),
+            test_record(Bookmark::STRING_TYPE, b"duplicate"),
+        ];
+        let first_toc = [(0xf001, 0)];
+        let second_toc = [(0xf002, 1), (0xf001, 2)];
+        let mut test_data = test_data_area(&records, &[&first_toc, &second_toc]);
+        let records = Bookmark::bookmark_data(&test_data).unwrap();
+
+        // Records of every TOC are kept, including record types an earlier TOC already had
+        assert!(records.len() == 3);
+        assert!(records[0].record_type == 0xf001 && records[0].level == 1);
+        assert!(records[0].value == BookmarkValue::String("first".to_string()));
+        assert!(records[1].record_type == 0xf002 && records[1].level == 2);
+        assert!(records[1].value == BookmarkValue::String("second".to_string()));
+        assert!(records[2].record_type == 0xf001 && records[2].level == 2);
+        assert!(records[2].value == BookmarkValue::String("duplicate".to_string()));
+
+        // Lookups get the record from the earliest TOC
+        let bookmark = Bookmark {
+            version: 1040,
+            records,
+        };
+        assert!(bookmark.record(0xf001).unwrap().level == 1);
+        assert!(bookmark.record(0xf003).is_none());
+
+        // Point the second TOC back at the first TOC
+        let first_toc_offset = u32::from_le_bytes(test_data[0..4].try_into().unwrap()) as usize;
+        let second_toc_offset = first_toc_offset + 8 + 24;
+        let next_offset = second_toc_offset + 12;
+        test_data[next_offset..next_offset + 4]
+            .copy_from_slice(&(first_toc_offset as u32).to_le_bytes());
+        let records = Bookmark::bookmark_data(&test_data).unwrap();
+        assert!(records.len() == 3);
+
+        // Next TOC past the end of the data
+        test_data[next_offset..next_offset + 4].copy_from_slice(&[0, 0x10, 0, 0]);
+        let result = Bookmark::bookmark_data(&test_data);
+        assert!(matches!(
+            result,
+            Err(LoginItemsError::TruncatedToc { offset: 0x1030 })
+        ));
+    }
+
+    #[test]
+    fn test_bookmark_data_toc_record_count_overflow() {
+        let records = vec![test_record(Bookmark::STRING_TYPE, b"first")];
+        let mut test_data = test_data_area(&records, &[&[(0xf001, 0)]]);
+
+        // Record count * 12 does not fit in a u32
+        let toc_offset = u32::from_le_bytes(test_data[0..4].try_into().unwrap());
+        let count_offset = toc_offset as usize + 16;
+        test_data[count_offset..count_offset + 4].copy_from_slice(&0x2000_0000u32.to_le_bytes());
+        let result = Bookmark::bookmark_data(&test_data);
+        assert!(matches!(
+            result,
+            Err(LoginItemsError::TruncatedToc { offset }) if offset == 48 + toc_offset as usize
+        ));
+    }
+
+    /// Build a bookmark (header followed by the data area)
+    fn test_bookmark(data_area: &[u8]) -> Vec<u8> {
+        let mut bookmark = b"book".to_vec();
+        bookmark.extend_from_slice(&(48 + data_area.len() as u32).to_le_bytes());
+        bookmark.extend_from_slice(&0x10040000u32.to_be_bytes());
+        bookmark.extend_from_slice(&48u32.to_le_bytes());
+        bookmark.extend_from_slice(&[0; 32]);
+        bookmark.extend_from_slice(data_area);
+        bookmark
+    }
+
+    #[test]
+    fn test_parse_volume_bookmark() {
+        let records = vec![test_record(Bookmark::URL, b"smb://server/share")];
+        let volume_bookmark =
+            test_bookmark(&test_data_area(&records, &[&[(Bookmark::VOLUME_URL, 0)]]));
+
+        let records = vec![
+            test_record(Bookmark::DATA_TYPE, &volume_bookmark),
+            test_record(Bookmark::URL, b"file:///Volumes/share/"),
+        ];
+        let toc = [
+            (Bookmark::VOLUME_BOOKMARK, 0),
+            (Bookmark::VOLUME_MOUNT_POINT, 1),
+        ];
+        let bookmark = Bookmark::parse(&test_bookmark(&test_data_area(&records, &[&toc]))).unwrap();
+
+        let nested = match &bookmark.record(Bookmark::VOLUME_BOOKMARK).unwrap().value {
+            BookmarkValue::Bookmark(nested) => nested,
+            _ => panic!("Volume bookmark was not parsed"),
+        };
+        assert!(
+            nested.record(Bookmark::VOLUME_URL).unwrap().value
+                == BookmarkValue::Url("smb://server/share".to_string())
+        );
+        assert!(
+            bookmark.record(Bookmark::VOLUME_MOUNT_POINT).unwrap().value
+                == BookmarkValue::Url("file:///Volumes/share/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_volume_bookmark_depth() {
+        let mut bookmark = test_bookmark(&test_data_area(&[], &[&[]]));
+        for _ in 0..Bookmark::MAX_BOOKMARK_DEPTH {
+            let records = vec![test_record(Bookmark::DATA_TYPE, &bookmark)];
+            bookmark = test_bookmark(&test_data_area(
+                &records,
+                &[&[(Bookmark::VOLUME_BOOKMARK, 0)]],
+            ));
+        }
+        let mut bookmark = Bookmark::parse(&bookmark).unwrap();
+
+        // Deepest bookmark is kept as data
+        let mut depth = 0;
+        while let BookmarkValue::Bookmark(nested) =
+            &bookmark.record(Bookmark::VOLUME_BOOKMARK).unwrap().value
+        {
+            bookmark = *nested.clone();
+            depth += 1;
+        }
+        assert!(depth == Bookmark::MAX_BOOKMARK_DEPTH - 1);
+        assert!(matches!(
+            bookmark.record(Bookmark::VOLUME_BOOKMARK).unwrap().value,
+            BookmarkValue::Data(_)
+        ));
+    }
+}
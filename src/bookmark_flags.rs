@@ -0,0 +1,271 @@
+//! Decode macOS Bookmark property flags
+//!
+//! Provides typed target (resource) and volume property flags. Bookmarks store three 8 byte words:
+//! the property flags, a mask of which flags are valid and a reserved word.
+
+use serde::Serialize;
+
+// Flag values from the CFURL resource and volume property keys
+// https://mac-alias.readthedocs.io/en/latest/bookmark_fmt.html
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TargetFlags {
+    pub flags: u64, // Resource property flags
+    pub valid: u64, // Mask of flags that were set when the bookmark was created
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct VolumeFlags {
+    pub flags: u64, // Volume property flags
+    pub valid: u64, // Mask of flags that were set when the bookmark was created
+}
+
+impl TargetFlags {
+    pub const IS_REGULAR_FILE: u64 = 0x1;
+    pub const IS_DIRECTORY: u64 = 0x2;
+    pub const IS_SYMBOLIC_LINK: u64 = 0x4;
+    pub const IS_VOLUME: u64 = 0x8;
+    pub const IS_PACKAGE: u64 = 0x10;
+    pub const IS_SYSTEM_IMMUTABLE: u64 = 0x20;
+    pub const IS_USER_IMMUTABLE: u64 = 0x40;
+    pub const IS_HIDDEN: u64 = 0x80;
+    pub const HAS_HIDDEN_EXTENSION: u64 = 0x100;
+    pub const IS_APPLICATION: u64 = 0x200;
+    pub const IS_COMPRESSED: u64 = 0x400;
+    pub const CAN_SET_HIDDEN_EXTENSION: u64 = 0x800;
+    pub const IS_READABLE: u64 = 0x1000;
+    pub const IS_WRITEABLE: u64 = 0x2000;
+    pub const IS_EXECUTABLE: u64 = 0x4000;
+    pub const IS_ALIAS_FILE: u64 = 0x8000;
+    pub const IS_MOUNT_TRIGGER: u64 = 0x10000;
+
+    const NAMES: [(u64, &'static str); 17] = [
+        (TargetFlags::IS_REGULAR_FILE, "IsRegularFile"),
+        (TargetFlags::IS_DIRECTORY, "IsDirectory"),
+        (TargetFlags::IS_SYMBOLIC_LINK, "IsSymbolicLink"),
+        (TargetFlags::IS_VOLUME, "IsVolume"),
+        (TargetFlags::IS_PACKAGE, "IsPackage"),
+        (TargetFlags::IS_SYSTEM_IMMUTABLE, "IsSystemImmutable"),
+        (TargetFlags::IS_USER_IMMUTABLE, "IsUserImmutable"),
+        (TargetFlags::IS_HIDDEN, "IsHidden"),
+        (TargetFlags::HAS_HIDDEN_EXTENSION, "HasHiddenExtension"),
+        (TargetFlags::IS_APPLICATION, "IsApplication"),
+        (TargetFlags::IS_COMPRESSED, "IsCompressed"),
+        (
+            TargetFlags::CAN_SET_HIDDEN_EXTENSION,
+            "CanSetHiddenExtension",
+        ),
+        (TargetFlags::IS_READABLE, "IsReadable"),
+        (TargetFlags::IS_WRITEABLE, "IsWriteable"),
+        (TargetFlags::IS_EXECUTABLE, "IsExecutable"),
+        (TargetFlags::IS_ALIAS_FILE, "IsAliasFile"),
+        (TargetFlags::IS_MOUNT_TRIGGER, "IsMountTrigger"),
+    ];
+
+    /// Get flags from the bookmark flag words (flags, valid mask, reserved)
+    pub fn new(words: &[u64]) -> TargetFlags {
+        let (flags, valid) = flag_words(words);
+        TargetFlags { flags, valid }
+    }
+
+    /// Check a flag. Returns None if the flag is not in the valid mask
+    pub fn get(&self, flag: u64) -> Option<bool> {
+        flag_value(self.flags, self.valid, flag)
+    }
+
+    /// Get the names of the valid flags that are set
+    pub fn names(&self) -> Vec<String> {
+        flag_names(self.flags, self.valid, &TargetFlags::NAMES)
+    }
+}
+
+impl VolumeFlags {
+    pub const IS_LOCAL: u64 = 0x1;
+    pub const IS_AUTOMOUNT: u64 = 0x2;
+    pub const DONT_BROWSE: u64 = 0x4;
+    pub const IS_READ_ONLY: u64 = 0x8;
+    pub const IS_QUARANTINED: u64 = 0x10;
+    pub const IS_EJECTABLE: u64 = 0x20;
+    pub const IS_REMOVABLE: u64 = 0x40;
+    pub const IS_INTERNAL: u64 = 0x80;
+    pub const IS_EXTERNAL: u64 = 0x100;
+    pub const IS_DISK_IMAGE: u64 = 0x200;
+    pub const IS_FILE_VAULT: u64 = 0x400;
+    pub const IS_LOCAL_IDISK_MIRROR: u64 = 0x800;
+    pub const IS_IPOD: u64 = 0x1000;
+    pub const IS_IDISK: u64 = 0x2000;
+    pub const IS_CD: u64 = 0x4000;
+    pub const IS_DVD: u64 = 0x8000;
+    pub const IS_DEVICE_FILE_SYSTEM: u64 = 0x10000;
+    pub const SUPPORTS_PERSISTENT_IDS: u64 = 0x100000000;
+    pub const SUPPORTS_SEARCH_FS: u64 = 0x200000000;
+    pub const SUPPORTS_EXCHANGE: u64 = 0x400000000;
+    pub const SUPPORTS_SYMBOLIC_LINKS: u64 = 0x1000000000;
+    pub const SUPPORTS_DENY_MODES: u64 = 0x2000000000;
+    pub const SUPPORTS_COPY_FILE: u64 = 0x4000000000;
+    pub const SUPPORTS_READ_DIR_ATTR: u64 = 0x8000000000;
+    pub const SUPPORTS_JOURNALING: u64 = 0x10000000000;
+    pub const SUPPORTS_RENAME: u64 = 0x20000000000;
+    pub const SUPPORTS_FAST_STAT_FS: u64 = 0x40000000000;
+    pub const SUPPORTS_CASE_SENSITIVE_NAMES: u64 = 0x80000000000;
+    pub const SUPPORTS_CASE_PRESERVED_NAMES: u64 = 0x100000000000;
+    pub const SUPPORTS_FLOCK: u64 = 0x200000000000;
+    pub const HAS_NO_ROOT_DIRECTORY_TIMES: u64 = 0x400000000000;
+    pub const SUPPORTS_EXTENDED_SECURITY: u64 = 0x800000000000;
+    pub const SUPPORTS_2TB_FILE_SIZE: u64 = 0x1000000000000;
+    pub const SUPPORTS_HARD_LINKS: u64 = 0x2000000000000;
+    pub const SUPPORTS_MANDATORY_BYTE_RANGE_LOCKS: u64 = 0x4000000000000;
+    pub const SUPPORTS_PATH_FROM_ID: u64 = 0x8000000000000;
+    pub const IS_JOURNALING: u64 = 0x20000000000000;
+    pub const SUPPORTS_SPARSE_FILES: u64 = 0x40000000000000;
+    pub const SUPPORTS_ZERO_RUNS: u64 = 0x80000000000000;
+    pub const SUPPORTS_VOLUME_SIZES: u64 = 0x100000000000000;
+    pub const SUPPORTS_REMOTE_EVENTS: u64 = 0x200000000000000;
+    pub const SUPPORTS_HIDDEN_FILES: u64 = 0x400000000000000;
+    pub const SUPPORTS_DECMPFS_COMPRESSION: u64 = 0x800000000000000;
+    pub const HAS_64BIT_OBJECT_IDS: u64 = 0x1000000000000000;
+
+    const NAMES: [(u64, &'static str); 44] = [
+        (VolumeFlags::IS_LOCAL, "IsLocal"),
+        (VolumeFlags::IS_AUTOMOUNT, "IsAutomount"),
+        (VolumeFlags::DONT_BROWSE, "DontBrowse"),
+        (VolumeFlags::IS_READ_ONLY, "IsReadOnly"),
+        (VolumeFlags::IS_QUARANTINED, "IsQuarantined"),
+        (VolumeFlags::IS_EJECTABLE, "IsEjectable"),
+        (VolumeFlags::IS_REMOVABLE, "IsRemovable"),
+        (VolumeFlags::IS_INTERNAL, "IsInternal"),
+        (VolumeFlags::IS_EXTERNAL, "IsExternal"),
+        (VolumeFlags::IS_DISK_IMAGE, "IsDiskImage"),
+        (VolumeFlags::IS_FILE_VAULT, "IsFileVault"),
+        (VolumeFlags::IS_LOCAL_IDISK_MIRROR, "IsLocaliDiskMirror"),
+        (VolumeFlags::IS_IPOD, "IsiPod"),
+        (VolumeFlags::IS_IDISK, "IsiDisk"),
+        (VolumeFlags::IS_CD, "IsCD"),
+        (VolumeFlags::IS_DVD, "IsDVD"),
+        (VolumeFlags::IS_DEVICE_FILE_SYSTEM, "IsDeviceFileSystem"),
+        (
+            VolumeFlags::SUPPORTS_PERSISTENT_IDS,
+            "SupportsPersistentIDs",
+        ),
+        (VolumeFlags::SUPPORTS_SEARCH_FS, "SupportsSearchFS"),
+        (VolumeFlags::SUPPORTS_EXCHANGE, "SupportsExchange"),
+        (
+            VolumeFlags::SUPPORTS_SYMBOLIC_LINKS,
+            "SupportsSymbolicLinks",
+        ),
+        (VolumeFlags::SUPPORTS_DENY_MODES, "SupportsDenyModes"),
+        (VolumeFlags::SUPPORTS_COPY_FILE, "SupportsCopyFile"),
+        (VolumeFlags::SUPPORTS_READ_DIR_ATTR, "SupportsReadDirAttr"),
+        (VolumeFlags::SUPPORTS_JOURNALING, "SupportsJournaling"),
+        (VolumeFlags::SUPPORTS_RENAME, "SupportsRename"),
+        (VolumeFlags::SUPPORTS_FAST_STAT_FS, "SupportsFastStatFS"),
+        (
+            VolumeFlags::SUPPORTS_CASE_SENSITIVE_NAMES,
+            "SupportsCaseSensitiveNames",
+        ),
+        (
+            VolumeFlags::SUPPORTS_CASE_PRESERVED_NAMES,
+            "SupportsCasePreservedNames",
+        ),
+        (VolumeFlags::SUPPORTS_FLOCK, "SupportsFLock"),
+        (
+            VolumeFlags::HAS_NO_ROOT_DIRECTORY_TIMES,
+            "HasNoRootDirectoryTimes",
+        ),
+        (
+            VolumeFlags::SUPPORTS_EXTENDED_SECURITY,
+            "SupportsExtendedSecurity",
+        ),
+        (VolumeFlags::SUPPORTS_2TB_FILE_SIZE, "Supports2TBFileSize"),
+        (VolumeFlags::SUPPORTS_HARD_LINKS, "SupportsHardLinks"),
+        (
+            VolumeFlags::SUPPORTS_MANDATORY_BYTE_RANGE_LOCKS,
+            "SupportsMandatoryByteRangeLocks",
+        ),
+        (VolumeFlags::SUPPORTS_PATH_FROM_ID, "SupportsPathFromID"),
+        (VolumeFlags::IS_JOURNALING, "IsJournaling"),
+        (VolumeFlags::SUPPORTS_SPARSE_FILES, "SupportsSparseFiles"),
+        (VolumeFlags::SUPPORTS_ZERO_RUNS, "SupportsZeroRuns"),
+        (VolumeFlags::SUPPORTS_VOLUME_SIZES, "SupportsVolumeSizes"),
+        (VolumeFlags::SUPPORTS_REMOTE_EVENTS, "SupportsRemoteEvents"),
+        (VolumeFlags::SUPPORTS_HIDDEN_FILES, "SupportsHiddenFiles"),
+        (
+            VolumeFlags::SUPPORTS_DECMPFS_COMPRESSION,
+            "SupportsDecmpFSCompression",
+        ),
+        (VolumeFlags::HAS_64BIT_OBJECT_IDS, "Has64BitObjectIDs"),
+    ];
+
+    /// Get flags from the bookmark flag words (flags, valid mask, reserved)
+    pub fn new(words: &[u64]) -> VolumeFlags {
+        let (flags, valid) = flag_words(words);
+        VolumeFlags { flags, valid }
+    }
+
+    /// Check a flag. Returns None if the flag is not in the valid mask
+    pub fn get(&self, flag: u64) -> Option<bool> {
+        flag_value(self.flags, self.valid, flag)
+    }
+
+    /// Get the names of the valid flags that are set
+    pub fn names(&self) -> Vec<String> {
+        flag_names(self.flags, self.valid, &VolumeFlags::NAMES)
+    }
+}
+
+/// Get the flags and valid mask words. Missing words are treated as zero
+fn flag_words(words: &[u64]) -> (u64, u64) {
+    let flags = words.first().copied().unwrap_or_default();
+    let valid = words.get(1).copied().unwrap_or_default();
+    (flags, valid)
+}
+
+fn flag_value(flags: u64, valid: u64, flag: u64) -> Option<bool> {
+    if valid & flag != flag {
+        return None;
+    }
+    Some(flags & flag == flag)
+}
+
+/// Decode the valid flags that are set. Unnamed flags are reported as hex
+fn flag_names(flags: u64, valid: u64, names: &[(u64, &str)]) -> Vec<String> {
+    let set_flags = flags & valid;
+    let mut flag_names: Vec<String> = Vec::new();
+    let mut known: u64 = 0;
+    for (flag, name) in names {
+        known |= flag;
+        if set_flags & flag == *flag {
+            flag_names.push(name.to_string());
+        }
+    }
+    if set_flags & !known != 0 {
+        flag_names.push(format!("unknown (0x{:x})", set_flags & !known));
+    }
+    flag_names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TargetFlags, VolumeFlags};
+
+    #[test]
+    fn test_volume_flags() {
+        let flags = VolumeFlags::new(&[4294967425, 4294972399, 0]);
+        assert!(flags.names() == ["IsLocal", "IsInternal", "SupportsPersistentIDs"]);
+        assert!(flags.get(VolumeFlags::IS_LOCAL) == Some(true));
+        assert!(flags.get(VolumeFlags::IS_READ_ONLY) == Some(false));
+        assert!(flags.get(VolumeFlags::IS_EXTERNAL) == Some(false));
+        assert!(flags.get(VolumeFlags::IS_DVD).is_none());
+    }
+
+    #[test]
+    fn test_target_flags_valid_mask() {
+        // Directory flag is set but not valid
+        let flags = TargetFlags::new(&[0x212, 0x210, 0]);
+        assert!(flags.names() == ["IsPackage", "IsApplication"]);
+        assert!(flags.get(TargetFlags::IS_DIRECTORY).is_none());
+
+        let flags = TargetFlags::new(&[0x100000, 0x100000]);
+        assert!(flags.names() == ["unknown (0x100000)"]);
+        assert!(TargetFlags::new(&[]).names().is_empty());
+    }
+}
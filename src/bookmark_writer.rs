@@ -0,0 +1,318 @@
+//! Build macOS Bookmark data
+//!
+//! Provides a library to serialize a Bookmark back into bookmark data (header, data and Table of Contents).
+
+use log::warn;
+
+use crate::bookmark::{Bookmark, BookmarkValue};
+
+impl Bookmark {
+    /// Serialize the bookmark. Record data is written in its original order and each run of records with the same
+    /// level is written as a TOC, chained in record order
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // Data starts with the offset to the first TOC
+        let mut writer = BookmarkWriter { data: vec![0; 4] };
+
+        // Parsed records keep their data offset. New records have an offset of 0 and keep the record order
+        let mut write_order: Vec<usize> = (0..self.records.len()).collect();
+        write_order.sort_by_key(|index| self.records[*index].data_offset);
+        let mut offsets: Vec<u32> = vec![0; self.records.len()];
+        for index in write_order {
+            offsets[index] = writer.write_value(&self.records[index].value);
+        }
+
+        let mut tocs: Vec<(u32, Vec<(u32, u32)>)> = Vec::new();
+        for (record, offset) in self.records.iter().zip(offsets) {
+            match tocs.last_mut() {
+                Some((level, toc_records)) if *level == record.level => {
+                    toc_records.push((record.record_type, offset));
+                }
+                _ => tocs.push((record.level, vec![(record.record_type, offset)])),
+            }
+        }
+        // Bookmarks always have at least one TOC
+        if tocs.is_empty() {
+            tocs.push((1, Vec::new()));
+        }
+
+        let toc_offset = writer.data.len() as u32;
+        writer.data[0..4].copy_from_slice(&toc_offset.to_le_bytes());
+
+        let toc_count = tocs.len();
+        for (index, (level, toc_records)) in tocs.iter().enumerate() {
+            // TOC length covers the level, next TOC offset, record count and the records
+            let toc_length = 12 + 12 * toc_records.len() as u32;
+            let toc_header_size = 8;
+            let mut next_toc_offset = 0;
+            if index + 1 < toc_count {
+                next_toc_offset = writer.data.len() as u32 + toc_header_size + toc_length;
+            }
+
+            let toc_magic = 0xfffffffe;
+            for value in [
+                toc_length,
+                toc_magic,
+                *level,
+                next_toc_offset,
+                toc_records.len() as u32,
+            ] {
+                writer.write_u32(value);
+            }
+            for (record_type, offset) in toc_records {
+                writer.write_u32(*record_type);
+                writer.write_u32(*offset);
+                writer.write_u32(0);
+            }
+        }
+
+        let book_sig = b"book";
+        let header_size: u32 = 48;
+        let mut bookmark = book_sig.to_vec();
+        bookmark.extend_from_slice(&(header_size + writer.data.len() as u32).to_le_bytes());
+        bookmark.extend_from_slice(&self.version.to_be_bytes());
+        bookmark.extend_from_slice(&header_size.to_le_bytes());
+        // 32 bytes of empty/reserved space
+        bookmark.extend_from_slice(&[0; 32]);
+        bookmark.append(&mut writer.data);
+        bookmark
+    }
+}
+
+struct BookmarkWriter {
+    data: Vec<u8>, // Bookmark data, offsets are relative to the start
+}
+
+impl BookmarkWriter {
+    fn write_u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Write a standard data record padded to four bytes. Returns the offset to the record
+    fn write_record(&mut self, data_type: u32, record_data: &[u8]) -> u32 {
+        let offset = self.data.len() as u32;
+        self.write_u32(record_data.len() as u32);
+        self.write_u32(data_type);
+        self.data.extend_from_slice(record_data);
+        while !self.data.len().is_multiple_of(4) {
+            self.data.push(0);
+        }
+        offset
+    }
+
+    /// Write a value and any records it points to. Returns the offset to the value record
+    fn write_value(&mut self, value: &BookmarkValue) -> u32 {
+        match value {
+            BookmarkValue::String(value) => {
+                self.write_record(Bookmark::STRING_TYPE, value.as_bytes())
+            }
+            BookmarkValue::Data(data) => self.write_record(Bookmark::DATA_TYPE, data),
+            BookmarkValue::Number { value, data_type } => match *data_type {
+                Bookmark::NUMBER_ONE_BYTE => {
+                    self.write_record(*data_type, &(*value as i8).to_le_bytes())
+                }
+                Bookmark::NUMBER_TWO_BYTE => {
+                    self.write_record(*data_type, &(*value as i16).to_le_bytes())
+                }
+                Bookmark::NUMBER_FOUR_BYTE => {
+                    self.write_record(*data_type, &(*value as i32).to_le_bytes())
+                }
+                // Eight bytes holds every value
+                _ => self.write_record(Bookmark::NUMBER_EIGHT_BYTE, &value.to_le_bytes()),
+            },
+            BookmarkValue::Float { value, data_type } => match *data_type {
+                Bookmark::NUMBER_FLOAT32 => {
+                    self.write_record(*data_type, &(*value as f32).to_le_bytes())
+                }
+                _ => self.write_record(Bookmark::NUMBER_FLOAT64, &value.to_le_bytes()),
+            },
+            BookmarkValue::Date(date) => self.write_record(Bookmark::DATE, &date.to_be_bytes()),
+            BookmarkValue::Bool(true) => self.write_record(Bookmark::BOOL_TRUE, &[]),
+            BookmarkValue::Bool(false) => self.write_record(Bookmark::BOOL_FALSE, &[]),
+            BookmarkValue::Array(values) => {
+                let offsets: Vec<u32> =
+                    values.iter().map(|value| self.write_value(value)).collect();
+                self.write_record(Bookmark::ARRAY_TYPE, &offset_data(&offsets))
+            }
+            BookmarkValue::Dictionary(entries) => {
+                let mut offsets: Vec<u32> = Vec::new();
+                for (key, value) in entries {
+                    offsets.push(self.write_value(key));
+                    offsets.push(self.write_value(value));
+                }
+                self.write_record(Bookmark::DICTIONARY, &offset_data(&offsets))
+            }
+            BookmarkValue::Uuid(uuid) => match uuid_bytes(uuid) {
+                Some(uuid_data) => self.write_record(Bookmark::UUID, &uuid_data),
+                None => {
+                    warn!("Invalid bookmark UUID {}, writing as string", uuid);
+                    self.write_record(Bookmark::STRING_TYPE, uuid.as_bytes())
+                }
+            },
+            BookmarkValue::Url(url) => self.write_record(Bookmark::URL, url.as_bytes()),
+            BookmarkValue::RelativeUrl { base, relative } => {
+                let offsets = [self.write_value(base), self.write_value(relative)];
+                self.write_record(Bookmark::URL_RELATIVE, &offset_data(&offsets))
+            }
+            BookmarkValue::Bookmark(bookmark) => {
+                self.write_record(Bookmark::DATA_TYPE, &bookmark.to_bytes())
+            }
+            BookmarkValue::Unknown { data_type, data } => self.write_record(*data_type, data),
+        }
+    }
+}
+
+/// Offsets to records as array or dictionary data
+fn offset_data(offsets: &[u32]) -> Vec<u8> {
+    offsets
+        .iter()
+        .flat_map(|offset| offset.to_le_bytes())
+        .collect()
+}
+
+/// Convert a hyphenated UUID string to its 16 bytes
+fn uuid_bytes(uuid: &str) -> Option<Vec<u8>> {
+    let hex: String = uuid.chars().filter(|value| *value != '-').collect();
+    let uuid_size = 16;
+    if hex.len() != uuid_size * 2 || !hex.is_ascii() {
+        return None;
+    }
+    let mut uuid_data: Vec<u8> = Vec::new();
+    for index in (0..hex.len()).step_by(2) {
+        uuid_data.push(u8::from_str_radix(&hex[index..index + 2], 16).ok()?);
+    }
+    Some(uuid_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::uuid_bytes;
+    use crate::{
+        bookmark::{Bookmark, BookmarkRecord, BookmarkValue},
+        loginitems_plist::get_bookmarks,
+    };
+
+    #[test]
+    fn test_to_bytes_round_trip() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/backgrounditems_sierra.btm");
+        let bookmarks = get_bookmarks(&test_location.display().to_string()).unwrap();
+
+        let bookmark = Bookmark::parse(&bookmarks[0]).unwrap();
+        assert!(bookmark.to_bytes() == bookmarks[0]);
+
+        let round_trip = Bookmark::parse(&bookmark.to_bytes()).unwrap();
+        assert!(round_trip == bookmark);
+    }
+
+    #[test]
+    fn test_to_bytes_all_types() {
+        let record = |record_type: u32, level: u32, value: BookmarkValue| BookmarkRecord {
+            record_type,
+            level,
+            data_offset: 0,
+            value,
+        };
+        // The only record of the nested bookmark is written right after the TOC offset
+        let nested = Bookmark {
+            version: 1040,
+            records: vec![BookmarkRecord {
+                record_type: Bookmark::VOLUME_URL,
+                level: 1,
+                data_offset: 4,
+                value: BookmarkValue::Url("smb://server/share".to_string()),
+            }],
+        };
+
+        let mut records = Vec::new();
+        let values = [
+            BookmarkValue::String("Syncthing.app".to_string()),
+            BookmarkValue::Data(vec![1, 2, 3]),
+            BookmarkValue::Number {
+                value: -2,
+                data_type: Bookmark::NUMBER_ONE_BYTE,
+            },
+            BookmarkValue::Number {
+                value: -300,
+                data_type: Bookmark::NUMBER_TWO_BYTE,
+            },
+            BookmarkValue::Number {
+                value: 501,
+                data_type: Bookmark::NUMBER_FOUR_BYTE,
+            },
+            BookmarkValue::Float {
+                value: 0.25,
+                data_type: Bookmark::NUMBER_FLOAT32,
+            },
+            BookmarkValue::Float {
+                value: 0.1,
+                data_type: Bookmark::NUMBER_FLOAT64,
+            },
+            BookmarkValue::Date(665473989.0),
+            BookmarkValue::Bool(true),
+            BookmarkValue::Bool(false),
+            BookmarkValue::Array(vec![
+                BookmarkValue::String("Applications".to_string()),
+                BookmarkValue::Number {
+                    value: 12345,
+                    data_type: Bookmark::NUMBER_EIGHT_BYTE,
+                },
+            ]),
+            BookmarkValue::Dictionary(vec![(
+                BookmarkValue::String("key".to_string()),
+                BookmarkValue::Array(Vec::new()),
+            )]),
+            BookmarkValue::Uuid("A1B2C3D4-0000-4000-8000-0000000001F5".to_string()),
+            BookmarkValue::Url("file:///Applications/Syncthing.app".to_string()),
+            BookmarkValue::RelativeUrl {
+                base: Box::new(BookmarkValue::Url("file:///Applications/".to_string())),
+                relative: Box::new(BookmarkValue::String("Syncthing.app".to_string())),
+            },
+            BookmarkValue::Unknown {
+                data_type: 0x0a01,
+                data: vec![9, 9],
+            },
+        ];
+        for (index, value) in values.into_iter().enumerate() {
+            records.push(record(0xf100 + index as u32, 1, value));
+        }
+        // Same record type in a later TOC is kept
+        records.push(record(
+            0xf100,
+            2,
+            BookmarkValue::String("Second TOC".to_string()),
+        ));
+        records.push(record(
+            Bookmark::VOLUME_BOOKMARK,
+            2,
+            BookmarkValue::Bookmark(Box::new(nested)),
+        ));
+        // Back to level 1 starts a third TOC
+        records.push(record(0xf001, 1, BookmarkValue::Bool(true)));
+        let bookmark = Bookmark {
+            version: 1040,
+            records,
+        };
+
+        let data = bookmark.to_bytes();
+        let round_trip = Bookmark::parse(&data).unwrap();
+        assert!(round_trip.records.len() == bookmark.records.len());
+        for (parsed, record) in round_trip.records.iter().zip(&bookmark.records) {
+            assert!(parsed.record_type == record.record_type);
+            assert!(parsed.level == record.level);
+            assert!(parsed.value == record.value);
+        }
+        // Parsed records keep their offsets, so serializing again gives the same data
+        assert!(round_trip.to_bytes() == data);
+    }
+
+    #[test]
+    fn test_uuid_bytes() {
+        let uuid = uuid_bytes("A1B2C3D4-0000-4000-8000-0000000001F5").unwrap();
+        assert!(uuid.len() == 16);
+        assert!(uuid[0] == 0xa1);
+        assert!(uuid[15] == 0xf5);
+        assert!(uuid_bytes("not-a-uuid").is_none());
+    }
+}
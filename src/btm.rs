@@ -0,0 +1,230 @@
+//! Parse macOS Ventura+ Background Task Management data
+//!
+//! Provides a library to parse the NSKeyedArchiver `Storage` object found in BackgroundItems-v*.btm files.
+
+use log::warn;
+use plist::Value;
+use serde::Serialize;
+
+use crate::{
+    error::LoginItemsError,
+    loginitems_plist::{self, KeyedArchive},
+};
+
+#[derive(Debug, Serialize)]
+pub struct BtmStorage {
+    pub version: u64,        // Storage format version
+    pub items: Vec<BtmItem>, // Item records for all users
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BtmItem {
+    pub user_identifier: String,        // UUID of the user that owns the item
+    pub uuid: String,                   // Item UUID
+    pub name: String,                   // Item display name
+    pub item_type: u64,                 // Item type bitfield
+    pub item_type_names: Vec<String>,   // Decoded item type names
+    pub disposition: u64,               // Item disposition bitfield
+    pub disposition_names: Vec<String>, // Decoded disposition names
+    pub identifier: String,             // Item identifier (type prefixed)
+    pub developer_name: String,         // Developer name from code signature
+    pub team_id: String,                // Team ID from code signature
+    pub executable_path: String,        // Path to executable
+    pub url: String,                    // URL to item (app bundle or plist)
+    pub bundle_id: String,              // Bundle identifier
+    pub generation: u64,                // Item generation
+    pub parent_identifier: String,      // Identifier of the containing item
+    pub embedded_identifiers: Vec<String>, // Identifiers of items contained in this item
+    #[serde(skip)]
+    pub bookmark: Vec<u8>, // Raw bookmark data for the item
+}
+
+// Item types and dispositions as reported by `sfltool dumpbtm`
+const ITEM_TYPES: [(u64, &str); 8] = [
+    (0x1, "user item"),
+    (0x2, "app"),
+    (0x4, "login item"),
+    (0x8, "agent"),
+    (0x10, "daemon"),
+    (0x20, "developer"),
+    (0x10000, "legacy"),
+    (0x80000, "curated"),
+];
+
+const DISPOSITION_ENABLED: u64 = 0x1;
+const DISPOSITION_ALLOWED: u64 = 0x2;
+const DISPOSITION_HIDDEN: u64 = 0x4;
+const DISPOSITION_NOTIFIED: u64 = 0x8;
+
+/// Parse BTM file and get the item records
+pub fn parse_btm(path: &str) -> Result<BtmStorage, LoginItemsError> {
+    let archive = loginitems_plist::get_keyed_archive(path)?;
+    Ok(parse_btm_archive(&archive))
+}
+
+/// Check if keyed archive contains a Ventura+ `Storage` object
+pub fn is_btm_storage(archive: &KeyedArchive) -> bool {
+    match archive.root() {
+        Some(root) => archive.class_name(root) == Some("Storage"),
+        None => false,
+    }
+}
+
+/// Rebuild the `Storage` object from the keyed archive
+pub fn parse_btm_archive(archive: &KeyedArchive) -> BtmStorage {
+    let mut storage = BtmStorage {
+        version: 0,
+        items: Vec::new(),
+    };
+    let root = match archive.root() {
+        Some(root) => root,
+        None => {
+            warn!("No root object in BTM archive");
+            return storage;
+        }
+    };
+    storage.version = archive.get_integer(root, "version");
+
+    let users = match archive.get(root, "itemsByUserIdentifier") {
+        Some(users) => users,
+        None => return storage,
+    };
+
+    // Items are grouped by user UUID
+    for (user, records) in archive.dictionary(users) {
+        let user_identifier = user.as_string().unwrap_or_default();
+        for record in archive.collection(records) {
+            if archive.class_name(record) != Some("ItemRecord") {
+                warn!(
+                    "Unexpected BTM item class: {:?}",
+                    archive.class_name(record)
+                );
+                continue;
+            }
+            storage
+                .items
+                .push(parse_item_record(archive, record, user_identifier));
+        }
+    }
+    storage
+}
+
+/// Get the fields of an `ItemRecord` object
+fn parse_item_record(archive: &KeyedArchive, record: &Value, user_identifier: &str) -> BtmItem {
+    let item_type = archive.get_integer(record, "type");
+    let disposition = archive.get_integer(record, "disposition");
+
+    let mut embedded_identifiers: Vec<String> = Vec::new();
+    if let Some(embedded) = archive.get(record, "embeddedItemIdentifiers") {
+        for identifier in archive.collection(embedded) {
+            if let Some(value) = identifier.as_string() {
+                embedded_identifiers.push(value.to_string());
+            }
+        }
+    }
+
+    BtmItem {
+        user_identifier: user_identifier.to_string(),
+        uuid: archive.get_string(record, "uuid"),
+        name: archive.get_string(record, "name"),
+        item_type,
+        item_type_names: item_type_names(item_type),
+        disposition,
+        disposition_names: disposition_names(disposition),
+        identifier: archive.get_string(record, "identifier"),
+        developer_name: archive.get_string(record, "developerName"),
+        team_id: archive.get_string(record, "teamIdentifier"),
+        executable_path: archive.get_string(record, "executablePath"),
+        url: archive.get_string(record, "url"),
+        bundle_id: archive.get_string(record, "bundleIdentifier"),
+        generation: archive.get_integer(record, "generation"),
+        parent_identifier: archive.get_string(record, "parentIdentifier"),
+        embedded_identifiers,
+        bookmark: archive.get_data(record, "bookmark"),
+    }
+}
+
+/// Decode item type bitfield
+fn item_type_names(item_type: u64) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut known: u64 = 0;
+    for (flag, name) in ITEM_TYPES {
+        known |= flag;
+        if item_type & flag == flag {
+            names.push(name.to_string());
+        }
+    }
+    if item_type & !known != 0 {
+        names.push(format!("unknown (0x{:x})", item_type & !known));
+    }
+    names
+}
+
+/// Decode item disposition bitfield
+fn disposition_names(disposition: u64) -> Vec<String> {
+    let flag_name = |flag: u64, set: &str, unset: &str| {
+        if disposition & flag == flag {
+            set.to_string()
+        } else {
+            unset.to_string()
+        }
+    };
+    vec![
+        flag_name(DISPOSITION_ENABLED, "enabled", "disabled"),
+        flag_name(DISPOSITION_ALLOWED, "allowed", "disallowed"),
+        flag_name(DISPOSITION_HIDDEN, "hidden", "visible"),
+        flag_name(DISPOSITION_NOTIFIED, "notified", "not notified"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{disposition_names, item_type_names, parse_btm};
+
+    #[test]
+    fn test_parse_btm() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/BackgroundItems-v4.btm");
+        let storage = parse_btm(&test_location.display().to_string()).unwrap();
+
+        assert!(storage.version == 4);
+        assert!(storage.items.len() == 3);
+
+        let app = &storage.items[0];
+        assert!(app.user_identifier == "A1B2C3D4-0000-4000-8000-0000000001F5");
+        assert!(app.item_type_names == ["app"]);
+        assert!(app.identifier == "2.com.github.xor-gate.syncthing-macosx");
+        assert!(app.developer_name == "Jakob Borg");
+        assert!(app.team_id == "LQE5SYM783");
+        assert!(app.url == "file:///Applications/Syncthing.app/");
+        assert!(app.embedded_identifiers == ["4.com.github.xor-gate.syncthing-macosx"]);
+        assert!(app.bookmark.is_empty());
+
+        let login_item = &storage.items[1];
+        assert!(login_item.item_type_names == ["login item"]);
+        assert!(login_item.parent_identifier == app.identifier);
+        assert!(login_item.generation == 2);
+        assert!(!login_item.bookmark.is_empty());
+
+        let agent = &storage.items[2];
+        assert!(agent.item_type_names == ["agent", "legacy"]);
+        assert!(agent.executable_path == "/Library/Application Support/Evil/agent");
+    }
+
+    #[test]
+    fn test_item_type_names() {
+        assert!(item_type_names(0x4) == ["login item"]);
+        assert!(item_type_names(0x10010) == ["daemon", "legacy"]);
+        assert!(item_type_names(0x100) == ["unknown (0x100)"]);
+    }
+
+    #[test]
+    fn test_disposition_names() {
+        let names = disposition_names(0xb);
+        assert!(names == ["enabled", "allowed", "visible", "notified"]);
+        let names = disposition_names(0x2);
+        assert!(names == ["disabled", "allowed", "visible", "not notified"]);
+    }
+}
@@ -0,0 +1,207 @@
+//! Check LoginItems bookmark consistency
+//!
+//! Provides checks for bookmark fields that should agree with each other. Hand-crafted or tampered bookmarks
+//! often do not.
+
+use std::{fmt, fs::metadata, path::Path, time::UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::{bookmark::Bookmark, loginitems::LoginItemsData, timestamp::CocoaTimestamp};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Anomaly {
+    /// Path and CNID path have a different number of components
+    PathLengthMismatch { path: usize, cnid_path: usize },
+    /// Security extension inode is not the last CNID in the CNID path
+    InodeMismatch { extension_inode: u64, cnid: i64 },
+    /// Security extension path is not the target path (ignoring case)
+    PathMismatch {
+        extension_path: String,
+        path: String,
+    },
+    /// Record timestamp is after the LoginItems file was last modified
+    CreatedAfterModified {
+        record: String,
+        created: CocoaTimestamp,
+        modified: CocoaTimestamp,
+    },
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Anomaly::PathLengthMismatch { path, cnid_path } => write!(
+                f,
+                "Path has {} components but CNID path has {}",
+                path, cnid_path
+            ),
+            Anomaly::InodeMismatch {
+                extension_inode,
+                cnid,
+            } => write!(
+                f,
+                "Security extension inode {} does not match CNID {}",
+                extension_inode, cnid
+            ),
+            Anomaly::PathMismatch {
+                extension_path,
+                path,
+            } => write!(
+                f,
+                "Security extension path {} does not match path {}",
+                extension_path, path
+            ),
+            Anomaly::CreatedAfterModified {
+                record,
+                created,
+                modified,
+            } => write!(
+                f,
+                "{} {} is after the file modified time {}",
+                record, created, modified
+            ),
+        }
+    }
+}
+
+/// Check the loginitem bookmark fields against each other and the modified time of the file that contained it
+pub fn check_loginitem(
+    loginitem: &LoginItemsData,
+    file_modified: Option<CocoaTimestamp>,
+) -> Vec<Anomaly> {
+    let mut anomalies: Vec<Anomaly> = Vec::new();
+
+    // Every path component should have a CNID
+    if loginitem.path.len() != loginitem.cnid_path.len() {
+        anomalies.push(Anomaly::PathLengthMismatch {
+            path: loginitem.path.len(),
+            cnid_path: loginitem.cnid_path.len(),
+        });
+    }
+
+    if let Some(extension) = &loginitem.security_extension_token {
+        if let Some(cnid) = loginitem.cnid_path.last() {
+            if extension.inode != *cnid as u64 {
+                anomalies.push(Anomaly::InodeMismatch {
+                    extension_inode: extension.inode,
+                    cnid: *cnid,
+                });
+            }
+        }
+
+        // Security extension path is lower-cased (including non-ASCII characters) and may end with a slash
+        let path = format!("/{}", loginitem.path.join("/"));
+        if !loginitem.path.is_empty() && extension.path.trim_end_matches('/') != path.to_lowercase()
+        {
+            anomalies.push(Anomaly::PathMismatch {
+                extension_path: extension.path.to_string(),
+                path,
+            });
+        }
+    }
+
+    if let Some(modified) = file_modified {
+        let records = [
+            (Bookmark::TARGET_CREATION_DATE, loginitem.creation),
+            (Bookmark::VOLUME_CREATION, loginitem.volume_creation),
+        ];
+        for (record_type, created) in records {
+            if created.is_valid() && created.raw > modified.raw {
+                anomalies.push(Anomaly::CreatedAfterModified {
+                    record: Bookmark::record_name(record_type),
+                    created,
+                    modified,
+                });
+            }
+        }
+    }
+    anomalies
+}
+
+/// Get the modified time of a file
+pub fn file_modified(path: &Path) -> Option<CocoaTimestamp> {
+    let modified = metadata(path).ok()?.modified().ok()?;
+    let unix = modified.duration_since(UNIX_EPOCH).ok()?.as_secs_f64();
+    Some(CocoaTimestamp::from_unix(unix))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{check_loginitem, file_modified, Anomaly};
+    use crate::{
+        loginitems::LoginItemsData, security_extension::SecurityExtension,
+        timestamp::CocoaTimestamp,
+    };
+
+    fn sierra_loginitem() -> LoginItemsData {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/backgrounditems_sierra.btm");
+        let results = LoginItemsData::parse_loginitems(&test_location.display().to_string())
+            .unwrap()
+            .results;
+        results.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn test_check_loginitem() {
+        let loginitem = sierra_loginitem();
+        assert!(loginitem.anomalies.is_empty());
+        assert!(check_loginitem(&loginitem, None).is_empty());
+    }
+
+    #[test]
+    fn test_check_loginitem_tampered() {
+        let mut loginitem = sierra_loginitem();
+        loginitem.path[1] = "Evil.app".to_string();
+        loginitem.cnid_path.push(706091);
+        let modified = CocoaTimestamp::new(600000000.0);
+
+        let anomalies = check_loginitem(&loginitem, Some(modified));
+        assert!(anomalies.len() == 4);
+        assert!(
+            anomalies[0]
+                == Anomaly::PathLengthMismatch {
+                    path: 2,
+                    cnid_path: 3
+                }
+        );
+        assert!(
+            anomalies[1]
+                == Anomaly::InodeMismatch {
+                    extension_inode: 706090,
+                    cnid: 706091
+                }
+        );
+        assert!(
+            anomalies[2].to_string()
+                == "Security extension path /applications/syncthing.app does not match path /Applications/Evil.app"
+        );
+        assert!(
+            anomalies[3].to_string()
+                == "TARGET_CREATION_DATE 2022-02-02T05:53:09Z is after the file modified time 2020-01-06T10:40:00Z"
+        );
+    }
+
+    #[test]
+    fn test_check_loginitem_unicode_path() {
+        let mut loginitem = sierra_loginitem();
+        loginitem.path[1] = "ÄPP.app".to_string();
+        let token = "64cb7eaa9a1bbccc4e1397c9f2a411ebe539cd29;00000000;00000000;0000000000000020;com.apple.app-sandbox.read-write;01;01000004;00000000000ac62a;/Applications/ÄPP.app\u{0}";
+        loginitem.security_extension_token = SecurityExtension::parse(token);
+        assert!(
+            loginitem.security_extension_token.as_ref().unwrap().path == "/applications/äpp.app"
+        );
+        assert!(check_loginitem(&loginitem, None).is_empty());
+    }
+
+    #[test]
+    fn test_file_modified() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/backgrounditems_sierra.btm");
+        assert!(file_modified(&test_location).unwrap().is_valid());
+        assert!(file_modified(&test_location.join("missing")).is_none());
+    }
+}
@@ -0,0 +1,208 @@
+//! Compare LoginItems results
+//!
+//! Provides a library to find the login items that were added, removed or changed between two scans or files.
+
+use std::{collections::BTreeMap, fmt};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::loginitems::{LoginItemsData, LoginItemsResults};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum ChangeType {
+    /// Item is only in the newer results
+    Added,
+    /// Item is only in the older results
+    Removed,
+    /// Item is in both results but its configuration or target changed
+    Changed,
+}
+
+impl fmt::Display for ChangeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoginItemsChange<'a> {
+    pub change: ChangeType,       // Added, Removed or Changed
+    pub key: String,              // Identity used to match items between results
+    pub source: &'a str,          // File that contained the item (older results for removed items)
+    pub owner: &'a str,           // User that owns the source file
+    pub item: &'a LoginItemsData, // Item from the newer results (older results for removed items)
+}
+
+struct KeyedItem<'a> {
+    source: &'a str,
+    owner: &'a str,
+    item: &'a LoginItemsData,
+}
+
+/// Compare two sets of results. Changes are sorted by key
+pub fn diff_results<'a>(
+    old: &'a [LoginItemsResults],
+    new: &'a [LoginItemsResults],
+) -> Vec<LoginItemsChange<'a>> {
+    let old_items = keyed_items(old);
+    let new_items = keyed_items(new);
+
+    let mut changes: Vec<LoginItemsChange> = Vec::new();
+    for (key, new_item) in &new_items {
+        let change = match old_items.get(key) {
+            None => ChangeType::Added,
+            Some(old_item) if fingerprint(old_item.item) != fingerprint(new_item.item) => {
+                ChangeType::Changed
+            }
+            Some(_) => continue,
+        };
+        changes.push(LoginItemsChange {
+            change,
+            key: key.to_string(),
+            source: new_item.source,
+            owner: new_item.owner,
+            item: new_item.item,
+        });
+    }
+    for (key, old_item) in &old_items {
+        if new_items.contains_key(key) {
+            continue;
+        }
+        changes.push(LoginItemsChange {
+            change: ChangeType::Removed,
+            key: key.to_string(),
+            source: old_item.source,
+            owner: old_item.owner,
+            item: old_item.item,
+        });
+    }
+    changes.sort_by(|first, second| first.key.cmp(&second.key));
+    changes
+}
+
+/// Get the identity of an item. Source paths are not used since they differ between roots
+pub fn item_key(owner: &str, item: &LoginItemsData) -> String {
+    let identity = if let Some(job) = &item.launchd {
+        format!("{}:{}", job.job_type, job.label)
+    } else if let Some(btm) = &item.btm {
+        format!("btm:{}:{}", btm.user_identifier, btm.identifier)
+    } else if item.is_bundled {
+        format!("bundled:{}:{}", item.app_id, item.app_binary)
+    } else {
+        format!("bookmark:/{}", item.path.join("/"))
+    };
+    format!("{}|{}", owner, identity)
+}
+
+/// Key every item. Duplicate keys get a counter so they are still compared
+fn keyed_items(results: &[LoginItemsResults]) -> BTreeMap<String, KeyedItem<'_>> {
+    let mut items: BTreeMap<String, KeyedItem> = BTreeMap::new();
+    for result in results {
+        for item in &result.results {
+            let base_key = item_key(&result.owner, item);
+            let mut key = base_key.to_string();
+            let mut count = 1;
+            while items.contains_key(&key) {
+                count += 1;
+                key = format!("{}#{}", base_key, count);
+            }
+            items.insert(
+                key,
+                KeyedItem {
+                    source: &result.path,
+                    owner: &result.owner,
+                    item,
+                },
+            );
+        }
+    }
+    items
+}
+
+/// Fields that make an item run something different. Filesystem paths under the root are left out
+fn fingerprint(item: &LoginItemsData) -> Value {
+    let signature = item
+        .executable
+        .as_ref()
+        .and_then(|executable| executable.code_signature());
+    json!({
+        "path": item.path,
+        "creation": item.creation,
+        "app_id": item.app_id,
+        "app_binary": item.app_binary,
+        "disabled": item.disabled,
+        "launchd": item.launchd,
+        "btm_disposition": item.btm.as_ref().map(|btm| btm.disposition),
+        "sha256": item.target.as_ref().map(|target| &target.sha256),
+        "signing_status": item.executable.as_ref().map(|executable| executable.signing_status()),
+        "signing_identifier": signature.map(|signature| &signature.identifier),
+        "team_id": signature.map(|signature| &signature.team_id),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{diff_results, item_key, ChangeType};
+    use crate::parser::{parse_loginitems_path, parse_loginitems_root};
+
+    #[test]
+    fn test_diff_results() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root");
+        let old = parse_loginitems_root(&test_location).unwrap().results;
+        let mut new = parse_loginitems_root(&test_location).unwrap().results;
+        assert!(diff_results(&old, &new).is_empty());
+
+        // Re-enable a helper and remove a launch job
+        for results in &mut new {
+            if results.path.ends_with("loginitems.501.plist") {
+                for loginitem in &mut results.results {
+                    if loginitem.app_binary == "com.csaba.fitzl.shield.ShieldHelper" {
+                        loginitem.disabled = Some(false);
+                    }
+                }
+            }
+            if results.path.ends_with("Library/LaunchDaemons") {
+                results.results.clear();
+            }
+        }
+        let changes = diff_results(&old, &new);
+        assert!(changes.len() == 2);
+        assert!(changes[0].change == ChangeType::Removed);
+        assert!(changes[0].key == "|LaunchDaemon:com.docker.vmnetd");
+        assert!(changes[1].change == ChangeType::Changed);
+        assert!(
+            changes[1].key == "|bundled:com.csaba.fitzl.shield:com.csaba.fitzl.shield.ShieldHelper"
+        );
+    }
+
+    #[test]
+    fn test_diff_results_files() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/backgrounditems_sierra.btm");
+        let old = parse_loginitems_path(&test_location.display().to_string()).unwrap();
+        test_location.pop();
+        test_location.push("BackgroundItems-v4.btm");
+        let new = parse_loginitems_path(&test_location.display().to_string()).unwrap();
+
+        let changes = diff_results(std::slice::from_ref(&old), std::slice::from_ref(&new));
+        let removed: Vec<&str> = changes
+            .iter()
+            .filter(|change| change.change == ChangeType::Removed)
+            .map(|change| change.key.as_str())
+            .collect();
+        assert!(removed == ["|bookmark:/Applications/Syncthing.app"]);
+        assert!(changes.len() == new.results.len() + 1);
+    }
+
+    #[test]
+    fn test_item_key() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/backgrounditems_sierra.btm");
+        let results = parse_loginitems_path(&test_location.display().to_string()).unwrap();
+        assert!(item_key("sam", &results.results[0]) == "sam|bookmark:/Applications/Syncthing.app");
+    }
+}
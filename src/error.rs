@@ -0,0 +1,135 @@
+//! LoginItems parsing errors
+//!
+//! Provides the error type returned by the LoginItems parsers.
+
+use std::{fmt, io, path::Path};
+
+use serde::{Serialize, Serializer};
+
+#[derive(Debug)]
+pub enum LoginItemsError {
+    /// Failed to read a file or directory
+    Io { path: String, source: io::Error },
+    /// File could not be decoded as a PLIST
+    PlistDecode { path: String, source: plist::Error },
+    /// PLIST decoded but did not have the expected structure
+    UnexpectedPlist { path: String, message: String },
+    /// Data does not start with a bookmark header
+    NotABookmark,
+    /// Bookmark header has a layout we do not understand
+    UnsupportedVersion { version: u32 },
+    /// Table of Contents runs past the end of the bookmark. Offset is from the start of the bookmark
+    TruncatedToc { offset: usize },
+    /// Record data runs past the end of the bookmark. Offset is from the start of the bookmark
+    TruncatedRecord { offset: usize, record_type: u32 },
+    /// Record nests too deeply or references itself. Offset is from the start of the bookmark
+    NestingLimit { offset: usize, record_type: u32 },
+    /// Path under the root directory goes through a symlink. Links are not followed
+    Symlink { path: String },
+    /// Data does not start with a Mach-O or fat header
+    NotMachO,
+    /// Mach-O header or load command runs past the end of the file. Offset is from the start of the file
+    TruncatedMachO { offset: usize },
+    /// No LoginItems files were found
+    NoLoginItems,
+}
+
+impl LoginItemsError {
+    pub(crate) fn io(path: &Path, source: io::Error) -> LoginItemsError {
+        LoginItemsError::Io {
+            path: path.display().to_string(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for LoginItemsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoginItemsError::Io { path, source } => {
+                write!(f, "Failed to read {}: {}", path, source)
+            }
+            LoginItemsError::PlistDecode { path, source } => {
+                write!(f, "Failed to decode PLIST {}: {}", path, source)
+            }
+            LoginItemsError::UnexpectedPlist { path, message } => {
+                write!(f, "Unexpected PLIST structure in {}: {}", path, message)
+            }
+            LoginItemsError::NotABookmark => write!(f, "Data is not a bookmark"),
+            LoginItemsError::UnsupportedVersion { version } => {
+                write!(f, "Unsupported bookmark version: {:#x}", version)
+            }
+            LoginItemsError::TruncatedToc { offset } => {
+                write!(
+                    f,
+                    "Truncated bookmark Table of Contents at offset {:#x}",
+                    offset
+                )
+            }
+            LoginItemsError::TruncatedRecord {
+                offset,
+                record_type,
+            } => write!(
+                f,
+                "Truncated bookmark record {:#x} at offset {:#x}",
+                record_type, offset
+            ),
+            LoginItemsError::NestingLimit {
+                offset,
+                record_type,
+            } => write!(
+                f,
+                "Bookmark record {:#x} nests too deeply at offset {:#x}",
+                record_type, offset
+            ),
+            LoginItemsError::Symlink { path } => write!(f, "Not following symlink {}", path),
+            LoginItemsError::NotMachO => write!(f, "Data is not a Mach-O binary"),
+            LoginItemsError::TruncatedMachO { offset } => {
+                write!(f, "Truncated Mach-O data at offset {:#x}", offset)
+            }
+            LoginItemsError::NoLoginItems => write!(f, "No LoginItems files found"),
+        }
+    }
+}
+
+/// Errors are serialized as their message
+impl Serialize for LoginItemsError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl std::error::Error for LoginItemsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoginItemsError::Io { source, .. } => Some(source),
+            LoginItemsError::PlistDecode { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, io, path::Path};
+
+    use super::LoginItemsError;
+
+    #[test]
+    fn test_io_error() {
+        let source = io::Error::new(io::ErrorKind::NotFound, "missing");
+        let err = LoginItemsError::io(Path::new("/var/db/test.plist"), source);
+        assert!(err.to_string() == "Failed to read /var/db/test.plist: missing");
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn test_truncated_record_error() {
+        let err = LoginItemsError::TruncatedRecord {
+            offset: 0x60,
+            record_type: 0x1004,
+        };
+        assert!(err.to_string() == "Truncated bookmark record 0x1004 at offset 0x60");
+        assert!(err.source().is_none());
+    }
+}
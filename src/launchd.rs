@@ -0,0 +1,182 @@
+//! Parse macOS LaunchAgents and LaunchDaemons
+//!
+//! Provides a library to parse launchd job PLIST files. Launch jobs are reviewed alongside LoginItems since they
+//! are the other common way to persist on macOS.
+
+use std::fmt;
+
+use log::warn;
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::{error::LoginItemsError, loginitems_plist};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum LaunchdJobType {
+    /// Runs in a user session (LaunchAgents directories)
+    Agent,
+    /// Runs as root or UserName outside of user sessions (LaunchDaemons directories)
+    Daemon,
+}
+
+impl fmt::Display for LaunchdJobType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LaunchdJobType::Agent => write!(f, "LaunchAgent"),
+            LaunchdJobType::Daemon => write!(f, "LaunchDaemon"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LaunchdJob {
+    pub job_type: LaunchdJobType,       // LaunchAgent or LaunchDaemon
+    pub label: String,                  // Label
+    pub program: String,                // Program
+    pub program_arguments: Vec<String>, // ProgramArguments
+    pub run_at_load: bool,              // RunAtLoad
+    pub keep_alive: bool,               // KeepAlive is true or has conditions
+    pub start_interval: u64,            // StartInterval in seconds (0 if not set)
+    pub watch_paths: Vec<String>,       // WatchPaths
+    pub user_name: String,              // UserName the job runs as
+    pub disabled: Option<bool>,         // Disabled (None if not set)
+    pub plist_path: String,             // Job PLIST file
+}
+
+impl LaunchdJob {
+    /// Path of the executable launchd runs. Program takes precedence over the first ProgramArguments value
+    pub fn executable(&self) -> &str {
+        if !self.program.is_empty() {
+            return &self.program;
+        }
+        match self.program_arguments.first() {
+            Some(executable) => executable,
+            None => "",
+        }
+    }
+}
+
+/// Parse a launchd job PLIST file
+pub fn parse_launchd_plist(
+    path: &str,
+    job_type: LaunchdJobType,
+) -> Result<LaunchdJob, LoginItemsError> {
+    let job: Dictionary = loginitems_plist::read_plist(path)?;
+
+    let get_string = |key: &str| match job.get(key) {
+        Some(Value::String(value)) => value.to_string(),
+        _ => String::new(),
+    };
+    let get_strings = |key: &str| match job.get(key) {
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(|value| value.as_string().map(str::to_string))
+            .collect(),
+        // WatchPaths is sometimes a single string
+        Some(Value::String(value)) => vec![value.to_string()],
+        _ => Vec::new(),
+    };
+    let get_bool = |key: &str| match job.get(key) {
+        Some(Value::Boolean(value)) => Some(*value),
+        _ => None,
+    };
+
+    let label = get_string("Label");
+    if label.is_empty() {
+        return Err(LoginItemsError::UnexpectedPlist {
+            path: path.to_string(),
+            message: "Launch job does not have a Label".to_string(),
+        });
+    }
+
+    // KeepAlive can be a boolean or a dictionary of conditions
+    let keep_alive = match job.get("KeepAlive") {
+        Some(Value::Boolean(keep_alive)) => *keep_alive,
+        Some(Value::Dictionary(conditions)) => !conditions.is_empty(),
+        Some(value) => {
+            warn!("Unexpected KeepAlive value in {}: {:?}", path, value);
+            false
+        }
+        None => false,
+    };
+    let start_interval = match job.get("StartInterval") {
+        Some(Value::Integer(interval)) => interval.as_unsigned().unwrap_or_default(),
+        _ => 0,
+    };
+
+    let launchd_job = LaunchdJob {
+        job_type,
+        label,
+        program: get_string("Program"),
+        program_arguments: get_strings("ProgramArguments"),
+        run_at_load: get_bool("RunAtLoad").unwrap_or_default(),
+        keep_alive,
+        start_interval,
+        watch_paths: get_strings("WatchPaths"),
+        user_name: get_string("UserName"),
+        disabled: get_bool("Disabled"),
+        plist_path: path.to_string(),
+    };
+    Ok(launchd_job)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{parse_launchd_plist, LaunchdJobType};
+    use crate::error::LoginItemsError;
+
+    #[test]
+    fn test_parse_launchd_plist_daemon() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root/Library/LaunchDaemons/com.docker.vmnetd.plist");
+        let job = parse_launchd_plist(&test_location.display().to_string(), LaunchdJobType::Daemon)
+            .unwrap();
+
+        assert!(job.label == "com.docker.vmnetd");
+        assert!(job.executable() == "/Library/PrivilegedHelperTools/com.docker.vmnetd");
+        assert!(job.program_arguments.len() == 1);
+        assert!(job.run_at_load);
+        assert!(!job.keep_alive);
+        assert!(job.disabled.is_none());
+        assert!(job.job_type.to_string() == "LaunchDaemon");
+    }
+
+    #[test]
+    fn test_parse_launchd_plist_agent() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location
+            .push("tests/test_data/root/Users/alex/Library/LaunchAgents/com.apple.updates.plist");
+        let job = parse_launchd_plist(&test_location.display().to_string(), LaunchdJobType::Agent)
+            .unwrap();
+
+        assert!(job.label == "com.apple.updates");
+        assert!(job.program.is_empty());
+        assert!(job.executable() == "/Users/alex/.local/updater");
+        assert!(job.program_arguments[1] == "--daemon");
+        assert!(job.run_at_load);
+        assert!(job.keep_alive);
+        assert!(job.start_interval == 3600);
+        assert!(job.watch_paths == ["/Users/alex/Downloads"]);
+        assert!(job.user_name.is_empty());
+        assert!(job.disabled == Some(false));
+    }
+
+    #[test]
+    fn test_parse_launchd_plist_invalid() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/loginitems.plist");
+        let result =
+            parse_launchd_plist(&test_location.display().to_string(), LaunchdJobType::Agent);
+        assert!(matches!(
+            result,
+            Err(LoginItemsError::UnexpectedPlist { .. })
+        ));
+
+        test_location.push("missing");
+        let result =
+            parse_launchd_plist(&test_location.display().to_string(), LaunchdJobType::Agent);
+        assert!(matches!(result, Err(LoginItemsError::Io { .. })));
+    }
+}
@@ -1,3 +1,21 @@
+pub mod app_bundle;
+pub mod bookmark;
+pub mod bookmark_flags;
+pub mod bookmark_writer;
+pub mod btm;
+pub mod consistency;
+pub mod diff;
+pub mod error;
+pub mod launchd;
 pub mod loginitems;
 pub mod loginitems_plist;
+pub mod macho;
+pub mod output;
 pub mod parser;
+pub mod security_extension;
+pub mod siem;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod target;
+pub mod timeline;
+pub mod timestamp;
@@ -3,51 +3,85 @@
 //! Provides a library to parse LoginItems data.
 
 use std::{
-    error,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
     fs::read_dir,
-    io::{Error, ErrorKind},
-    mem::size_of,
     path::Path,
-    str::{from_utf8, Utf8Error},
 };
 
 use log::{info, warn};
-use nom::{
-    bytes::streaming::take,
-    number::streaming::be_u32,
-    number::{complete::be_f64, streaming::le_u64},
-    number::{complete::le_i32, streaming::le_u16},
-    number::{complete::le_i64, streaming::le_u32},
-};
 use serde::Serialize;
 
-use crate::loginitems_plist;
+use crate::{
+    app_bundle::AppBundleInfo,
+    bookmark::{Bookmark, BookmarkRecord, BookmarkValue},
+    bookmark_flags::{TargetFlags, VolumeFlags},
+    btm::{self, BtmItem},
+    consistency::{self, Anomaly},
+    error::LoginItemsError,
+    launchd::{LaunchdJob, LaunchdJobType},
+    loginitems_plist::{self, KeyedArchive},
+    macho::MachOInfo,
+    security_extension::SecurityExtension,
+    target::TargetStatus,
+    timestamp::{CocoaTimestamp, TimestampStatus},
+};
 
 #[derive(Debug, Serialize)]
 pub struct LoginItemsResults {
     pub results: Vec<LoginItemsData>,
     pub path: String,
+    pub owner: String, // User that owns the LoginItems file (per-user files only)
+    pub errors: Vec<LoginItemsError>, // Bookmarks in the file that failed to parse
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoginItemsReport {
+    pub results: Vec<LoginItemsResults>, // LoginItems files that were parsed
+    pub failures: Vec<LoginItemsFailure>, // LoginItems files that failed to parse
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoginItemsFailure {
+    pub path: String,
+    pub error: LoginItemsError,
+}
+
+impl LoginItemsReport {
+    /// Record a source that failed to parse
+    pub fn add_failure(&mut self, path: &Path, error: LoginItemsError) {
+        warn!("Failed to parse {}: {}", path.display(), error);
+        self.failures.push(LoginItemsFailure {
+            path: path.display().to_string(),
+            error,
+        });
+    }
 }
 
 // Bookmark documentation:
 // https://mac-alias.readthedocs.io/en/latest/bookmark_fmt.html
 // http://michaellynn.github.io/2015/10/24/apples-bookmarkdata-exposed/
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct LoginItemsData {
-    pub path: Vec<String>,          // Path to binary to run
-    pub cnid_path: Vec<i64>,        // Path represented as Catalog Node ID
-    pub creation: f64,              // Created timestamp of binary target
-    pub volume_path: String,        // Root
-    pub volume_url: String,         // URL type
-    pub volume_name: String,        // Name of Volume
-    pub volume_uuid: String,        // Volume UUID string
-    pub volume_size: i64,           // Size of Volume
-    pub volume_creation: f64,       // Created timestamp of Volume
-    pub volume_flag: Vec<u64>,      // Volume Property flags
-    pub volume_root: bool,          // If Volume is filesystem root
-    pub localized_name: String,     // Optional localized name of target binary
+    pub path: Vec<String>,                            // Path to binary to run
+    pub cnid_path: Vec<i64>,                          // Path represented as Catalog Node ID
+    pub creation: CocoaTimestamp,                     // Created timestamp of binary target
+    pub volume_path: String,                          // Root
+    pub volume_url: String,                           // URL type
+    pub volume_name: String,                          // Name of Volume
+    pub volume_uuid: String,                          // Volume UUID string
+    pub volume_size: i64,                             // Size of Volume
+    pub volume_creation: CocoaTimestamp,              // Created timestamp of Volume
+    pub volume_flag: Vec<u64>,                        // Volume Property flags
+    pub volume_flag_names: Vec<String>,               // Decoded Volume Property flags
+    pub volume_root: bool,                            // If Volume is filesystem root
+    pub volume_mount_point: String,                   // URL of the Volume mount point
+    pub volume_bookmark: Option<Box<LoginItemsData>>, // Nested Volume bookmark
+    pub localized_name: String,                       // Optional localized name of target binary
     pub security_extension: String, // Optional Security extension of target binary
+    pub security_extension_token: Option<SecurityExtension>, // Decoded Security extension
     pub target_flags: Vec<u64>,     // Resource property flags
+    pub target_flag_names: Vec<String>, // Decoded Resource property flags
     pub username: String,           // Username related to bookmark
     pub folder_index: i32,          // Folder index number
     pub uid: i32,                   // User UID
@@ -55,108 +89,71 @@ pub struct LoginItemsData {
     pub is_bundled: bool,           // Is loginitem in App
     pub app_id: String,             // App ID
     pub app_binary: String,         // App binary
+    pub host_app: String,           // App bundle that contains the helper (bundled items only)
+    pub registered_in: String,      // launchd loginitems PLIST that registers the helper
+    pub disabled: Option<bool>,     // launchd disabled state (None if launchd has no record)
+    pub btm: Option<BtmItem>,       // Ventura+ BTM item record metadata
+    pub launchd: Option<LaunchdJob>, // LaunchAgent or LaunchDaemon job
+    pub bookmark: Option<Bookmark>, // Every record in the bookmark
+    pub anomalies: Vec<Anomaly>,    // Bookmark fields that disagree with each other
+    pub target: Option<TargetStatus>, // Target as found on the filesystem (root scans only)
+    pub bundle: Option<AppBundleInfo>, // Info.plist of an app bundle target (root scans only)
+    pub executable: Option<MachOInfo>, // Mach-O details of the target executable (root scans only)
 }
 
-#[derive(Debug)]
-struct BookmarkHeader {
-    signature: u32,            // Bookmark Signature "book"
-    bookmark_data_length: u32, // Total size of bookmark
-    version: u32,              // Possible version number
-    bookmark_data_offset: u32, // Offset to start of bookmark data (always 0x30 (48)).
-                               // Followed by 32 bytes of empty/reserved space (48 bytes total)
-}
-
-#[derive(Debug)]
-struct BookmarkData {
-    table_of_contents_offset: u32, // Offset to start of Table of Contents (TOC)
-}
-
-#[derive(Debug)]
-struct TableOfContentsHeader {
-    data_length: u32, // Size of TOC
-    record_type: u16, // Unused TOC record/key type (Possible magic number along side flags (0xfffffffe))
-    flags: u16,       // Unused flag (Possible magic number along side record_type (0xfffffffe))
-}
-
-#[derive(Debug)]
-struct TableOfContentsData {
-    level: u32,              // TOC Data level or identifier (always 1?)
-    next_record_offset: u32, // Offset to next TOC record
-    number_of_records: u32,  // Number of records in TOC
-}
-
-#[derive(Debug)]
-struct TableOfContentsDataRecord {
-    record_type: u32, // Record/Key type
-    data_offset: u32, // Offset to record data
-    reserved: u32,    // Reserved (0)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum SourceType {
+    /// Bookmark in a per-user backgrounditems.btm file
+    BackgroundItems,
+    /// Item in a Ventura+ BackgroundItems-v*.btm file
+    BackgroundTaskManagement,
+    /// Helper registered in a launchd loginitems.UID.plist file
+    LaunchdLoginItems,
+    /// Helper app found in an app bundle Contents/Library/LoginItems directory
+    AppHelper,
+    /// LaunchAgent PLIST file
+    LaunchAgent,
+    /// LaunchDaemon PLIST file
+    LaunchDaemon,
 }
 
-#[derive(Debug)]
-struct StandardDataRecord {
-    data_length: u32,     // Length of data
-    data_type: u32,       // Type of data
-    record_data: Vec<u8>, // Data
-    record_type: u32,     // Record type (from TableOfContentsDataRecord)
+impl fmt::Display for SourceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
 }
 
 impl LoginItemsData {
-    // Data types
-    const STRING_TYPE: u32 = 0x0101;
-    const DATA_TYPE: u32 = 0x0201;
-    const _NUMBER_ONE_BYTE: u32 = 0x0301;
-    const _NUMBER_TWO_BYTE: u32 = 0x0302;
-    const NUMBER_FOUR_BYTE: u32 = 0x0303;
-    const NUMBER_EIGHT_BYTE: u32 = 0x0304;
-    const _NUMBER_FLOAT: u32 = 0x0305;
-    const _NUMBERBER_FLOAT64: u32 = 0x0306;
-    const DATE: u32 = 0x0400;
-    const _BOOL_FALSE: u32 = 0x0500;
-    const BOOL_TRUE: u32 = 0x0501;
-    const ARRAY_TYPE: u32 = 0x0601;
-    const _DICTIONARY: u32 = 0x0701;
-    const _UUID: u32 = 0x0801;
-    const URL: u32 = 0x0901;
-    const _URL_RELATIVE: u32 = 0x0902;
-
-    // Table of Contents Key types
-    const _UNKNOWN: u32 = 0x1003;
-    const TARGET_PATH: u32 = 0x1004;
-    const TARGET_CNID_PATH: u32 = 0x1005;
-    const TARGET_FLAGS: u32 = 0x1010;
-    const _TARGET_FILENAME: u32 = 0x1020;
-    const TARGET_CREATION_DATE: u32 = 0x1040;
-    const _UKNOWN2: u32 = 0x1054;
-    const _UNKNOWN3: u32 = 0x1055;
-    const _UNKNOWN4: u32 = 0x1056;
-    const _UNKNOWN5: u32 = 0x1057;
-    const _UNKNOWN6: u32 = 0x1101;
-    const _UNKNOWN7: u32 = 0x1102;
-    const _TOC_PATH: u32 = 0x2000;
-    const VOLUME_PATH: u32 = 0x2002;
-    const VOLUME_URL: u32 = 0x2005;
-    const VOLUME_NAME: u32 = 0x2010;
-    const VOLUME_UUID: u32 = 0x2011;
-    const VOLUME_SIZE: u32 = 0x2012;
-    const VOLUME_CREATION: u32 = 0x2013;
-    const _VOLUME_BOOKMARK: u32 = 0x2040;
-    const VOLUME_FLAGS: u32 = 0x2020;
-    const VOLUME_ROOT: u32 = 0x2030;
-    const _VOLUME_MOUNT_POINT: u32 = 0x2050;
-    const _UNKNOWN8: u32 = 0x2070;
-    const CONTAIN_FOLDER_INDEX: u32 = 0xc001;
-    const CREATOR_USERNAME: u32 = 0xc011;
-    const CREATOR_UID: u32 = 0xc012;
-    const _FILE_REF_FLAG: u32 = 0xd001;
-    const CREATION_OPTIONS: u32 = 0xd010;
-    const _URL_LENGTH_ARRAY: u32 = 0xe003;
-    const LOCALIZED_NAME: u32 = 0xf017;
-    const _UNKNOWN9: u32 = 0xf022;
-    const SECURITY_EXTENSION: u32 = 0xf080;
-    const _UNKNOWN10: u32 = 0xf081;
+    /// Get the kind of file the item was found in
+    pub fn source_type(&self) -> SourceType {
+        if let Some(job) = &self.launchd {
+            return match job.job_type {
+                LaunchdJobType::Agent => SourceType::LaunchAgent,
+                LaunchdJobType::Daemon => SourceType::LaunchDaemon,
+            };
+        }
+        if self.btm.is_some() {
+            return SourceType::BackgroundTaskManagement;
+        }
+        if self.is_bundled {
+            // Helpers found on disk have a path, launchd registrations do not
+            if self.path.is_empty() {
+                return SourceType::LaunchdLoginItems;
+            }
+            return SourceType::AppHelper;
+        }
+        SourceType::BackgroundItems
+    }
 
     /// Parse loginitems from provided input path
-    pub fn parse_loginitems(path: &str) -> Result<LoginItemsResults, Box<dyn error::Error + '_>> {
+    pub fn parse_loginitems(path: &str) -> Result<LoginItemsResults, LoginItemsError> {
+        // Ventura+ BTM files archive a Storage object with typed item records
+        if let Ok(archive) = loginitems_plist::get_keyed_archive(path) {
+            if btm::is_btm_storage(&archive) {
+                return LoginItemsData::parse_btm_loginitems(path, &archive);
+            }
+        }
+
         // Parse PLIST file and get any bookmark data
         let loginitems_data = loginitems_plist::get_bookmarks(path)?;
         if loginitems_data.is_empty() {
@@ -164,586 +161,428 @@ impl LoginItemsData {
             let loginitems_empty = LoginItemsResults {
                 results: Vec::new(),
                 path: String::new(),
+                owner: String::new(),
+                errors: Vec::new(),
             };
             return Ok(loginitems_empty);
         }
 
         let mut loginitems_array: Vec<LoginItemsData> = Vec::new();
-        // Loop through all bookmark data found in PLIST
+        let mut errors: Vec<LoginItemsError> = Vec::new();
+        // Loop through all bookmark data found in PLIST. A malformed bookmark does not stop the others
         for data in loginitems_data {
-            let results = LoginItemsData::bookmark_header(&data);
-            match results {
-                Ok((bookmark_data, bookmark_header)) => {
-                    let book_sig: u32 = 1802465122;
-                    let book_data_offset: u32 = 48;
-
-                    // Check for bookmark signature and expected offset
-                    if bookmark_header.signature != book_sig
-                        || bookmark_header.bookmark_data_offset != book_data_offset
-                    {
-                        warn!("Not a bookmark file: {:?}", path);
-                        continue;
-                    }
-                    let bookmark_results = LoginItemsData::bookmark_data(bookmark_data);
-                    match bookmark_results {
-                        Ok((_, bookmark_loginitems)) => {
-                            loginitems_array.push(bookmark_loginitems);
-                        }
-                        Err(err) => {
-                            return Err(Box::new(Error::new(
-                                ErrorKind::InvalidInput,
-                                format!("Failed to parse bookmark data: {:?}", err),
-                            )))
-                        }
-                    }
-                }
+            match LoginItemsData::parse_bookmark(&data) {
+                Ok(bookmark_loginitems) => loginitems_array.push(bookmark_loginitems),
+                Err(LoginItemsError::NotABookmark) => warn!("Not a bookmark file: {:?}", path),
                 Err(err) => {
-                    return Err(Box::new(Error::new(
-                        ErrorKind::InvalidInput,
-                        format!("Failed to parser bookmark header: {:?}", err),
-                    )))
+                    warn!("Failed to parse bookmark in {}: {}", path, err);
+                    errors.push(err);
                 }
             }
         }
+        LoginItemsData::check_anomalies(&mut loginitems_array, path);
         let loginitems_data = LoginItemsResults {
             results: loginitems_array,
             path: path.to_string(),
+            owner: String::new(),
+            errors,
         };
         Ok(loginitems_data)
     }
 
-    /// Parse bookmark header
-    fn bookmark_header(data: &[u8]) -> nom::IResult<&[u8], BookmarkHeader> {
-        let mut bookmark_header = BookmarkHeader {
-            signature: 0,
-            bookmark_data_length: 0,
-            version: 0,
-            bookmark_data_offset: 0,
-        };
-
-        let (input, sig) = take(size_of::<u32>())(data)?;
-        let (input, data_length) = take(size_of::<u32>())(input)?;
-        let (input, version) = take(size_of::<u32>())(input)?;
-        let (input, data_offset) = take(size_of::<u32>())(input)?;
+    /// Parse the item records in a Ventura+ BTM file. Each item gets its own record metadata
+    fn parse_btm_loginitems(
+        path: &str,
+        archive: &KeyedArchive,
+    ) -> Result<LoginItemsResults, LoginItemsError> {
+        let storage = btm::parse_btm_archive(archive);
 
-        let filler_size: u32 = 32;
-        let (input, _) = take(filler_size)(input)?;
-
-        let (_, bookmark_sig) = le_u32(sig)?;
-        let (_, bookmark_data_length) = le_u32(data_length)?;
-        let (_, bookmark_version) = be_u32(version)?;
-        let (_, bookmark_data_offset) = le_u32(data_offset)?;
+        let mut loginitems_array: Vec<LoginItemsData> = Vec::new();
+        let mut errors: Vec<LoginItemsError> = Vec::new();
+        for item in storage.items {
+            // Item records are still reported if their bookmark is malformed
+            let bookmark = if item.bookmark.is_empty() {
+                None
+            } else {
+                match LoginItemsData::parse_bookmark(&item.bookmark) {
+                    Ok(bookmark_loginitems) => Some(bookmark_loginitems),
+                    Err(err) => {
+                        warn!("Failed to parse bookmark for {}: {}", item.identifier, err);
+                        errors.push(err);
+                        None
+                    }
+                }
+            };
 
-        bookmark_header.signature = bookmark_sig;
-        bookmark_header.bookmark_data_length = bookmark_data_length;
-        bookmark_header.version = bookmark_version;
-        bookmark_header.bookmark_data_offset = bookmark_data_offset;
-        Ok((input, bookmark_header))
-    }
+            let mut loginitems_data = bookmark.unwrap_or_default();
+            loginitems_data.btm = Some(item);
+            loginitems_array.push(loginitems_data);
+        }
 
-    /// Parse the core bookmark data
-    fn bookmark_data(data: &[u8]) -> nom::IResult<&[u8], LoginItemsData> {
-        let mut book_data = BookmarkData {
-            table_of_contents_offset: 0,
+        LoginItemsData::check_anomalies(&mut loginitems_array, path);
+        let loginitems_data = LoginItemsResults {
+            results: loginitems_array,
+            path: path.to_string(),
+            owner: String::new(),
+            errors,
         };
+        Ok(loginitems_data)
+    }
 
-        let (input, offset) = take(size_of::<u32>())(data)?;
-        let (_, toc_offset) = le_u32(offset)?;
-
-        book_data.table_of_contents_offset = toc_offset;
-        let toc_offset_size: u32 = 4;
-        let (input, core_data) = take(book_data.table_of_contents_offset - toc_offset_size)(input)?;
-
-        let (input, toc_header) = LoginItemsData::table_of_contents_header(input)?;
-
-        let (toc_record_data, toc_content_data) =
-            LoginItemsData::table_of_contents_data(input, toc_header.data_length)?;
-
-        let (_, toc_content_data_record) = LoginItemsData::table_of_contents_record(
-            toc_record_data,
-            &toc_content_data.number_of_records,
-        )?;
+    /// Check the bookmark of each loginitem for fields that disagree with each other
+    fn check_anomalies(loginitems: &mut [LoginItemsData], path: &str) {
+        let modified = consistency::file_modified(Path::new(path));
+        for loginitem in loginitems {
+            // Items without a bookmark have nothing to check
+            if loginitem.bookmark.is_none() {
+                continue;
+            }
+            loginitem.anomalies = consistency::check_loginitem(loginitem, modified);
+            for anomaly in &loginitem.anomalies {
+                warn!("Bookmark anomaly in {}: {}", path, anomaly);
+            }
+        }
+    }
 
-        let mut login_items_data = LoginItemsData {
-            path: Vec::new(),
-            cnid_path: Vec::new(),
-            target_flags: Vec::new(),
-            creation: 0.0,
-            volume_path: String::new(),
-            volume_url: String::new(),
-            volume_name: String::new(),
-            volume_uuid: String::new(),
-            volume_size: 0,
-            volume_creation: 0.0,
-            volume_flag: Vec::new(),
-            volume_root: false,
-            localized_name: String::new(),
-            security_extension: String::new(),
-            username: String::new(),
-            uid: 0,
-            creation_options: 0,
-            folder_index: 0,
-            is_bundled: false,
-            app_id: String::new(),
-            app_binary: String::new(),
-        };
+    /// Parse a single bookmark
+    fn parse_bookmark(data: &[u8]) -> Result<LoginItemsData, LoginItemsError> {
+        let bookmark = Bookmark::parse(data)?;
+        Ok(LoginItemsData::from_bookmark(bookmark))
+    }
 
-        for record in toc_content_data_record {
-            let (_, standard_data) = LoginItemsData::bookmark_standard_data(core_data, &record)?;
-            let record_data = standard_data.record_data;
-            let mut standard_data_vec: Vec<StandardDataRecord> = Vec::new();
+    /// Get loginitem data from the bookmark records based on record and data types
+    pub fn from_bookmark(bookmark: Bookmark) -> LoginItemsData {
+        let mut login_items_data = LoginItemsData::default();
 
-            // If data type is ARRAY, standard_data data points to offsets that contain actual loginitem data
-            if standard_data.data_type == LoginItemsData::ARRAY_TYPE {
-                let results_data = LoginItemsData::bookmark_array(&record_data);
-                match results_data {
-                    Ok((_, results)) => {
-                        if results.is_empty() {
-                            continue;
+        // Earlier TOCs take precedence if a record type appears more than once
+        let mut seen: BTreeSet<u32> = BTreeSet::new();
+        for record in &bookmark.records {
+            if !seen.insert(record.record_type) {
+                continue;
+            }
+            match (record.record_type, &record.value) {
+                (Bookmark::TARGET_PATH, BookmarkValue::Array(values)) => {
+                    for value in values {
+                        if let BookmarkValue::String(path) = value {
+                            login_items_data.path.push(path.to_string());
                         }
-
-                        let (_, std_data_vec) =
-                            LoginItemsData::loginitem_data(core_data, results, &record)?;
-
-                        // Now we have data for actual loginitem data
-                        standard_data_vec = std_data_vec;
                     }
-                    Err(err) => warn!("Failed to get bookmark standard data: {:?}", err),
                 }
-            }
-
-            // If we did not have to parse array data, get loginitem data based on record and data types
-            if standard_data_vec.is_empty() {
-                if standard_data.record_type == LoginItemsData::TARGET_FLAGS
-                    && standard_data.data_type == LoginItemsData::DATA_TYPE
-                {
-                    let flag_data = LoginItemsData::bookmark_target_flags(&record_data);
-                    match flag_data {
+                (Bookmark::TARGET_CNID_PATH, BookmarkValue::Array(values)) => {
+                    for value in values {
+                        if let BookmarkValue::Number { value: cnid, .. } = value {
+                            login_items_data.cnid_path.push(*cnid);
+                        }
+                    }
+                }
+                (Bookmark::TARGET_FLAGS, BookmarkValue::Data(data)) => {
+                    match Bookmark::bookmark_target_flags(data) {
                         Ok((_, flags)) => {
-                            if flags.is_empty() {
-                                continue;
-                            }
+                            login_items_data.target_flag_names = TargetFlags::new(&flags).names();
                             login_items_data.target_flags = flags;
                         }
                         Err(err) => warn!("Failed to parse Target Flags: {:?}", err),
                     }
-                } else if standard_data.record_type == LoginItemsData::TARGET_CREATION_DATE
-                    && standard_data.data_type == LoginItemsData::DATE
-                {
-                    let creation_data = LoginItemsData::bookmark_data_type_date(&record_data);
-                    match creation_data {
-                        Ok((_, creation)) => login_items_data.creation = creation,
-                        Err(err) => warn!("Failed to parse Target creation timestamp: {:?}", err),
-                    }
-                } else if standard_data.record_type == LoginItemsData::VOLUME_PATH
-                    && standard_data.data_type == LoginItemsData::STRING_TYPE
-                {
-                    let volume_root = LoginItemsData::bookmark_data_type_string(&record_data);
-                    match volume_root {
-                        Ok(volume_root_data) => login_items_data.volume_path = volume_root_data,
-                        Err(err) => warn!("Failed to parse Volume Path: {:?}", err),
-                    }
-                } else if standard_data.record_type == LoginItemsData::VOLUME_URL
-                    && standard_data.data_type == LoginItemsData::URL
-                {
-                    let volume_url_data = LoginItemsData::bookmark_data_type_string(&record_data);
-                    match volume_url_data {
-                        Ok(volume_url) => login_items_data.volume_url = volume_url,
-                        Err(err) => warn!("Failed to parse Volume URL data: {:?}", err),
-                    }
-                } else if standard_data.record_type == LoginItemsData::VOLUME_NAME
-                    && standard_data.data_type == LoginItemsData::STRING_TYPE
-                {
-                    let volume_name_data = LoginItemsData::bookmark_data_type_string(&record_data);
-                    match volume_name_data {
-                        Ok(volume_name) => login_items_data.volume_name = volume_name,
-                        Err(err) => warn!("Failed to parse Volume Name data: {:?}", err),
-                    }
-                } else if standard_data.record_type == LoginItemsData::VOLUME_UUID
-                    && standard_data.data_type == LoginItemsData::STRING_TYPE
-                {
-                    let volume_uuid_data = LoginItemsData::bookmark_data_type_string(&record_data);
-                    match volume_uuid_data {
-                        Ok(volume_uuid) => login_items_data.volume_uuid = volume_uuid,
-                        Err(err) => warn!("Failed to parse Volume UUID: {:?}", err),
-                    }
-                } else if standard_data.record_type == LoginItemsData::VOLUME_SIZE
-                    && standard_data.data_type == LoginItemsData::NUMBER_EIGHT_BYTE
-                {
-                    let test = LoginItemsData::bookmark_data_type_number_eight(&record_data);
-                    match test {
-                        Ok((_, size)) => login_items_data.volume_size = size,
-                        Err(err) => warn!("Failed to parse Volume size: {:?}", err),
-                    }
-                } else if standard_data.record_type == LoginItemsData::VOLUME_CREATION
-                    && standard_data.data_type == LoginItemsData::DATE
-                {
-                    let creation_data = LoginItemsData::bookmark_data_type_date(&record_data);
-                    match creation_data {
-                        Ok((_, creation)) => login_items_data.volume_creation = creation,
-                        Err(err) => warn!("Failed to parse Volume Creation timestamp: {:?}", err),
-                    }
-                } else if standard_data.record_type == LoginItemsData::VOLUME_FLAGS
-                    && standard_data.data_type == LoginItemsData::DATA_TYPE
-                {
-                    let flags_data = LoginItemsData::bookmark_target_flags(&record_data);
-                    match flags_data {
-                        Ok((_, flags)) => login_items_data.volume_flag = flags,
+                }
+                (Bookmark::TARGET_CREATION_DATE, BookmarkValue::Date(creation)) => {
+                    login_items_data.creation = CocoaTimestamp::new(*creation);
+                }
+                (Bookmark::VOLUME_PATH, BookmarkValue::String(volume_path)) => {
+                    login_items_data.volume_path = volume_path.to_string();
+                }
+                (
+                    Bookmark::VOLUME_URL,
+                    volume_url @ (BookmarkValue::Url(_) | BookmarkValue::RelativeUrl { .. }),
+                ) => {
+                    login_items_data.volume_url = volume_url.url().unwrap_or_default();
+                }
+                (Bookmark::VOLUME_NAME, BookmarkValue::String(volume_name)) => {
+                    login_items_data.volume_name = volume_name.to_string();
+                }
+                (Bookmark::VOLUME_UUID, BookmarkValue::String(volume_uuid)) => {
+                    login_items_data.volume_uuid = volume_uuid.to_string();
+                }
+                (Bookmark::VOLUME_SIZE, BookmarkValue::Number { value: size, .. }) => {
+                    login_items_data.volume_size = *size;
+                }
+                (Bookmark::VOLUME_CREATION, BookmarkValue::Date(creation)) => {
+                    login_items_data.volume_creation = CocoaTimestamp::new(*creation);
+                }
+                (Bookmark::VOLUME_FLAGS, BookmarkValue::Data(data)) => {
+                    match Bookmark::bookmark_target_flags(data) {
+                        Ok((_, flags)) => {
+                            login_items_data.volume_flag_names = VolumeFlags::new(&flags).names();
+                            login_items_data.volume_flag = flags;
+                        }
                         Err(err) => warn!("Failed to parse Volume Flags: {:?}", err),
                     }
-                } else if standard_data.record_type == LoginItemsData::VOLUME_ROOT
-                    && standard_data.data_type == LoginItemsData::BOOL_TRUE
-                {
-                    login_items_data.volume_root = true;
-                } else if standard_data.record_type == LoginItemsData::LOCALIZED_NAME
-                    && standard_data.data_type == LoginItemsData::STRING_TYPE
-                {
-                    let local_name_data = LoginItemsData::bookmark_data_type_string(&record_data);
-                    match local_name_data {
-                        Ok(local_name) => login_items_data.localized_name = local_name,
-                        Err(err) => warn!("Failed to parse Localized Name: {:?}", err),
-                    }
-                } else if standard_data.record_type == LoginItemsData::SECURITY_EXTENSION
-                    && standard_data.data_type == LoginItemsData::DATA_TYPE
-                {
-                    let extension_data = LoginItemsData::bookmark_data_type_string(&record_data);
-                    match extension_data {
-                        Ok(extension) => login_items_data.security_extension = extension,
+                }
+                (Bookmark::VOLUME_ROOT, BookmarkValue::Bool(volume_root)) => {
+                    login_items_data.volume_root = *volume_root;
+                }
+                (
+                    Bookmark::VOLUME_MOUNT_POINT,
+                    mount_point @ (BookmarkValue::Url(_) | BookmarkValue::RelativeUrl { .. }),
+                ) => {
+                    login_items_data.volume_mount_point = mount_point.url().unwrap_or_default();
+                }
+                (Bookmark::VOLUME_MOUNT_POINT, BookmarkValue::String(mount_point)) => {
+                    login_items_data.volume_mount_point = mount_point.to_string();
+                }
+                (Bookmark::VOLUME_BOOKMARK, BookmarkValue::Bookmark(volume_bookmark))
+                | (Bookmark::VOLUME_MOUNT_POINT, BookmarkValue::Bookmark(volume_bookmark)) => {
+                    let mut volume_data = LoginItemsData::from_bookmark(*volume_bookmark.clone());
+                    // Nested bookmark records are already part of the outer bookmark
+                    volume_data.bookmark = None;
+                    login_items_data.volume_bookmark = Some(Box::new(volume_data));
+                }
+                (Bookmark::LOCALIZED_NAME, BookmarkValue::String(local_name)) => {
+                    login_items_data.localized_name = local_name.to_string();
+                }
+                (Bookmark::SECURITY_EXTENSION, BookmarkValue::Data(data)) => {
+                    match Bookmark::bookmark_data_type_string(data) {
+                        Ok(extension) => {
+                            login_items_data.security_extension_token =
+                                SecurityExtension::parse(&extension);
+                            login_items_data.security_extension = extension;
+                        }
                         Err(err) => warn!("Failed to parse Security Extension: {:?}", err),
                     }
-                } else if standard_data.record_type == LoginItemsData::CREATOR_USERNAME
-                    && standard_data.data_type == LoginItemsData::STRING_TYPE
-                {
-                    let username_data = LoginItemsData::bookmark_data_type_string(&record_data);
-                    match username_data {
-                        Ok(username) => login_items_data.username = username,
-                        Err(err) => warn!("Failed to parse bookmark username: {:?}", err),
-                    }
-                } else if standard_data.record_type == LoginItemsData::CONTAIN_FOLDER_INDEX
-                    && standard_data.data_type == LoginItemsData::NUMBER_FOUR_BYTE
-                {
-                    let index_data = LoginItemsData::bookmark_data_type_number_four(&record_data);
-                    match index_data {
-                        Ok((_, index)) => login_items_data.folder_index = index,
-                        Err(err) => warn!("Failed to parse bookmark folder index: {:?}", err),
-                    }
-                } else if standard_data.record_type == LoginItemsData::CREATOR_UID
-                    && standard_data.data_type == LoginItemsData::NUMBER_FOUR_BYTE
-                {
-                    let uid_data = LoginItemsData::bookmark_data_type_number_four(&record_data);
-                    match uid_data {
-                        Ok((_, uid)) => login_items_data.uid = uid,
-                        Err(err) => warn!("Failed to parse bookmark Creator UID: {:?}", err),
-                    }
-                } else if standard_data.record_type == LoginItemsData::CREATION_OPTIONS
-                    && standard_data.data_type == LoginItemsData::NUMBER_FOUR_BYTE
-                {
-                    let creation_options_data =
-                        LoginItemsData::bookmark_data_type_number_four(&record_data);
-                    match creation_options_data {
-                        Ok((_, options)) => login_items_data.creation_options = options,
-                        Err(err) => warn!("Failed to parse bookmark Creation options: {:?}", err),
-                    }
                 }
-                continue;
-            }
-
-            // Get loginitem array data based on data and record types
-            for standard_data in standard_data_vec {
-                if standard_data.data_type == LoginItemsData::STRING_TYPE
-                    && standard_data.record_type == LoginItemsData::TARGET_PATH
-                {
-                    let path_data =
-                        LoginItemsData::bookmark_data_type_string(&standard_data.record_data);
-                    match path_data {
-                        Ok(path) => login_items_data.path.push(path),
-                        Err(_err) => continue,
-                    }
-                } else if standard_data.data_type == LoginItemsData::NUMBER_EIGHT_BYTE
-                    && standard_data.record_type == LoginItemsData::TARGET_CNID_PATH
-                {
-                    let cnid_data = LoginItemsData::bookmark_cnid(&standard_data.record_data);
-                    match cnid_data {
-                        Ok((_, cnid)) => login_items_data.cnid_path.push(cnid),
-                        Err(_err) => continue,
-                    }
+                (Bookmark::CREATOR_USERNAME, BookmarkValue::String(username)) => {
+                    login_items_data.username = username.to_string();
+                }
+                (Bookmark::CONTAIN_FOLDER_INDEX, BookmarkValue::Number { value: index, .. }) => {
+                    login_items_data.folder_index = *index as i32;
+                }
+                (Bookmark::CREATOR_UID, BookmarkValue::Number { value: uid, .. }) => {
+                    login_items_data.uid = *uid as i32;
+                }
+                (Bookmark::CREATION_OPTIONS, BookmarkValue::Number { value: options, .. }) => {
+                    login_items_data.creation_options = *options as i32;
                 }
+                _ => continue,
             }
         }
-        Ok((input, login_items_data))
-    }
-
-    /// Parse the Table of Contents (TOC) header
-    fn table_of_contents_header(data: &[u8]) -> nom::IResult<&[u8], TableOfContentsHeader> {
-        let mut toc_header = TableOfContentsHeader {
-            data_length: 0,
-            record_type: 0,
-            flags: 0,
-        };
-
-        let (input, length) = take(size_of::<u32>())(data)?;
-        let (input, record_type) = take(size_of::<u16>())(input)?;
-        let (input, flags) = take(size_of::<u16>())(input)?;
-
-        let (_, toc_length) = le_u32(length)?;
-        let (_, toc_record_type) = le_u16(record_type)?;
-        let (_, toc_flags) = le_u16(flags)?;
-
-        toc_header.data_length = toc_length;
-        toc_header.record_type = toc_record_type;
-        toc_header.flags = toc_flags;
-
-        Ok((input, toc_header))
+        login_items_data.bookmark = Some(bookmark);
+        login_items_data
     }
 
-    /// Parse the TOC data
-    fn table_of_contents_data(
-        data: &[u8],
-        data_length: u32,
-    ) -> nom::IResult<&[u8], TableOfContentsData> {
-        let mut toc_data = TableOfContentsData {
-            level: 0,
-            next_record_offset: 0,
-            number_of_records: 0,
-        };
-
-        let (input, level) = take(size_of::<u32>())(data)?;
-        let (input, next_record_offset) = take(size_of::<u32>())(input)?;
-        let (input, number_of_records) = take(size_of::<u32>())(input)?;
-
-        let mut final_input = input;
-
-        let (_, toc_level) = le_u32(level)?;
-        let (_, toc_next_record) = le_u32(next_record_offset)?;
-        let (_, toc_number_records) = le_u32(number_of_records)?;
-
-        toc_data.level = toc_level;
-        toc_data.next_record_offset = toc_next_record;
-        toc_data.number_of_records = toc_number_records;
-
-        let record_size = 12;
-        let record_data = record_size * toc_data.number_of_records;
-
-        // Verify TOC data length is equal to number of records (Number of Records * Record Size (12 bytes))
-        // Some TOC headers may give incorrect? data length (they are 8 bytes short, https://mac-alias.readthedocs.io/en/latest/bookmark_fmt.html)
-        if record_data > data_length {
-            let (_, actual_record_data) = take(record_data)(input)?;
-            final_input = actual_record_data;
+    /// Build a bookmark from the loginitem data. The parsed bookmark is used as is if available
+    pub fn to_bookmark(&self) -> Bookmark {
+        if let Some(bookmark) = &self.bookmark {
+            return bookmark.clone();
         }
-        Ok((final_input, toc_data))
-    }
-
-    /// Parse the TOC data record
-    fn table_of_contents_record<'a>(
-        data: &'a [u8],
-        records: &u32,
-    ) -> nom::IResult<&'a [u8], Vec<TableOfContentsDataRecord>> {
-        let mut input_data = data;
-        let mut record: u32 = 0;
-        let mut toc_records_vec: Vec<TableOfContentsDataRecord> = Vec::new();
 
-        // Loop through until all records have been parsed
-        loop {
-            if &record == records {
-                break;
-            }
-            record += 1;
-            let mut toc_data_record = TableOfContentsDataRecord {
-                record_type: 0,
+        let mut records: Vec<BookmarkRecord> = Vec::new();
+        let mut add_record = |record_type: u32, value: BookmarkValue| {
+            records.push(BookmarkRecord {
+                record_type,
+                level: 1,
                 data_offset: 0,
-                reserved: 0,
-            };
-
-            let (input, record_type) = take(size_of::<u32>())(input_data)?;
-            let (input, offset) = take(size_of::<u32>())(input)?;
-            let (input, reserved) = take(size_of::<u32>())(input)?;
-            input_data = input;
-
-            let (_, toc_record) = le_u32(record_type)?;
-            let (_, toc_offset) = le_u32(offset)?;
-            let (_, toc_reserved) = le_u32(reserved)?;
-
-            toc_data_record.record_type = toc_record;
-            toc_data_record.data_offset = toc_offset;
-            toc_data_record.reserved = toc_reserved;
-            toc_records_vec.push(toc_data_record);
-        }
-        Ok((input_data, toc_records_vec))
-    }
-
-    /// Parse the bookmark standard data
-    fn bookmark_standard_data<'a>(
-        bookmark_data: &'a [u8],
-        toc_record: &TableOfContentsDataRecord,
-    ) -> nom::IResult<&'a [u8], StandardDataRecord> {
-        let mut toc_standard_data = StandardDataRecord {
-            data_length: 0,
-            record_data: Vec::new(),
-            data_type: 0,
-            record_type: 0,
+                value,
+            });
+        };
+        let flag_data = |flags: &[u64]| -> Vec<u8> {
+            flags.iter().flat_map(|flag| flag.to_le_bytes()).collect()
         };
-        let toc_offset_value: u32 = 4;
-
-        // Subtract toc offset value from data offset since we already nom'd the value
-        let offset = (toc_record.data_offset - toc_offset_value) as usize;
-
-        // Nom data til standard data info
-        let (input, _) = take(offset)(bookmark_data)?;
-
-        let (input, length) = take(size_of::<u32>())(input)?;
-        let (input, data_type) = take(size_of::<u32>())(input)?;
-
-        let (_, standard_length) = le_u32(length)?;
-        let (_, standard_data_type) = le_u32(data_type)?;
-
-        let (input, record_data) = take(standard_length)(input)?;
-
-        toc_standard_data.data_length = standard_length;
-        toc_standard_data.data_type = standard_data_type;
-        toc_standard_data.record_data = record_data.to_vec();
-        toc_standard_data.record_type = toc_record.record_type;
-
-        Ok((input, toc_standard_data))
-    }
-
-    /// Parse the bookmark array data
-    fn loginitem_data<'a>(
-        data: &'a [u8],
-        array_offsets: Vec<u32>,
-        record: &TableOfContentsDataRecord,
-    ) -> nom::IResult<&'a [u8], Vec<StandardDataRecord>> {
-        let mut standard_data_vec: Vec<StandardDataRecord> = Vec::new();
 
-        for offset in array_offsets {
-            let data_record = TableOfContentsDataRecord {
-                record_type: record.record_type,
-                data_offset: offset,
-                reserved: 0,
-            };
-            let (_, results) = LoginItemsData::bookmark_standard_data(data, &data_record)?;
-            standard_data_vec.push(results);
+        // Only add records that have data
+        if !self.path.is_empty() {
+            let path = self
+                .path
+                .iter()
+                .map(|value| BookmarkValue::String(value.to_string()))
+                .collect();
+            add_record(Bookmark::TARGET_PATH, BookmarkValue::Array(path));
         }
-
-        Ok((data, standard_data_vec))
-    }
-
-    /// Get the offsets for the array data
-    fn bookmark_array(standard_data: &[u8]) -> nom::IResult<&[u8], Vec<u32>> {
-        let mut array_offsets: Vec<u32> = Vec::new();
-        let mut input = standard_data;
-        let offset_size: u32 = 4;
-
-        loop {
-            let (input_data, offset) = take(offset_size)(input)?;
-            let (_, data_offsets) = le_u32(offset)?;
-
-            array_offsets.push(data_offsets);
-            input = input_data;
-            if input_data.is_empty() {
-                break;
-            }
+        if !self.cnid_path.is_empty() {
+            let cnid_path = self
+                .cnid_path
+                .iter()
+                .map(|cnid| BookmarkValue::Number {
+                    value: *cnid,
+                    data_type: Bookmark::NUMBER_EIGHT_BYTE,
+                })
+                .collect();
+            add_record(Bookmark::TARGET_CNID_PATH, BookmarkValue::Array(cnid_path));
         }
-        Ok((input, array_offsets))
-    }
-
-    /// Get the path/strings related to bookmark
-    fn bookmark_data_type_string(standard_data: &[u8]) -> Result<String, Utf8Error> {
-        let path = from_utf8(standard_data)?;
-        Ok(path.to_string())
-    }
-
-    /// Get the CNID path for the target
-    fn bookmark_cnid(standard_data: &[u8]) -> nom::IResult<&[u8], i64> {
-        let (data, cnid) = le_i64(standard_data)?;
-        Ok((data, cnid))
-    }
-
-    /// Get bookmark target flags
-    fn bookmark_target_flags(standard_data: &[u8]) -> nom::IResult<&[u8], Vec<u64>> {
-        let mut input = standard_data;
-        let mut array_flags: Vec<u64> = Vec::new();
-        let max_flag_size = 3;
-
-        // Target flags are composed of three (3) 8 byte values
-        loop {
-            let (data, flag) = take(size_of::<u64>())(input)?;
-            input = data;
-            let (_, flags) = le_u64(flag)?;
-            array_flags.push(flags);
-            if input.is_empty() || array_flags.len() == max_flag_size {
-                break;
-            }
+        if !self.target_flags.is_empty() {
+            let flags = flag_data(&self.target_flags);
+            add_record(Bookmark::TARGET_FLAGS, BookmarkValue::Data(flags));
+        }
+        if self.creation.status() != TimestampStatus::Unset {
+            add_record(
+                Bookmark::TARGET_CREATION_DATE,
+                BookmarkValue::Date(self.creation.raw),
+            );
+        }
+        if !self.volume_path.is_empty() {
+            let volume_path = BookmarkValue::String(self.volume_path.to_string());
+            add_record(Bookmark::VOLUME_PATH, volume_path);
+        }
+        if !self.volume_url.is_empty() {
+            let volume_url = BookmarkValue::Url(self.volume_url.to_string());
+            add_record(Bookmark::VOLUME_URL, volume_url);
+        }
+        if !self.volume_name.is_empty() {
+            let volume_name = BookmarkValue::String(self.volume_name.to_string());
+            add_record(Bookmark::VOLUME_NAME, volume_name);
+        }
+        if !self.volume_uuid.is_empty() {
+            let volume_uuid = BookmarkValue::String(self.volume_uuid.to_string());
+            add_record(Bookmark::VOLUME_UUID, volume_uuid);
+        }
+        if self.volume_size != 0 {
+            add_record(
+                Bookmark::VOLUME_SIZE,
+                BookmarkValue::Number {
+                    value: self.volume_size,
+                    data_type: Bookmark::NUMBER_EIGHT_BYTE,
+                },
+            );
+        }
+        if self.volume_creation.status() != TimestampStatus::Unset {
+            let volume_creation = BookmarkValue::Date(self.volume_creation.raw);
+            add_record(Bookmark::VOLUME_CREATION, volume_creation);
+        }
+        if !self.volume_flag.is_empty() {
+            let flags = flag_data(&self.volume_flag);
+            add_record(Bookmark::VOLUME_FLAGS, BookmarkValue::Data(flags));
+        }
+        if self.volume_root {
+            add_record(Bookmark::VOLUME_ROOT, BookmarkValue::Bool(true));
+        }
+        if !self.volume_mount_point.is_empty() {
+            let mount_point = BookmarkValue::Url(self.volume_mount_point.to_string());
+            add_record(Bookmark::VOLUME_MOUNT_POINT, mount_point);
+        }
+        if let Some(volume_bookmark) = &self.volume_bookmark {
+            let volume_bookmark = BookmarkValue::Bookmark(Box::new(volume_bookmark.to_bookmark()));
+            add_record(Bookmark::VOLUME_BOOKMARK, volume_bookmark);
+        }
+        if !self.localized_name.is_empty() {
+            let localized_name = BookmarkValue::String(self.localized_name.to_string());
+            add_record(Bookmark::LOCALIZED_NAME, localized_name);
+        }
+        if !self.security_extension.is_empty() {
+            let extension = self.security_extension.as_bytes().to_vec();
+            add_record(Bookmark::SECURITY_EXTENSION, BookmarkValue::Data(extension));
+        }
+        if !self.username.is_empty() {
+            let username = BookmarkValue::String(self.username.to_string());
+            add_record(Bookmark::CREATOR_USERNAME, username);
+        }
+        if self.uid != 0 {
+            add_record(
+                Bookmark::CREATOR_UID,
+                BookmarkValue::Number {
+                    value: self.uid as i64,
+                    data_type: Bookmark::NUMBER_FOUR_BYTE,
+                },
+            );
+        }
+        if self.folder_index != 0 {
+            let folder_index = BookmarkValue::Number {
+                value: self.folder_index as i64,
+                data_type: Bookmark::NUMBER_FOUR_BYTE,
+            };
+            add_record(Bookmark::CONTAIN_FOLDER_INDEX, folder_index);
+        }
+        if self.creation_options != 0 {
+            let options = BookmarkValue::Number {
+                value: self.creation_options as i64,
+                data_type: Bookmark::NUMBER_FOUR_BYTE,
+            };
+            add_record(Bookmark::CREATION_OPTIONS, options);
         }
-        Ok((input, array_flags))
-    }
-
-    /// Get bookmark volume size
-    fn bookmark_data_type_number_eight(standard_data: &[u8]) -> nom::IResult<&[u8], i64> {
-        let (data, size) = le_i64(standard_data)?;
-        Ok((data, size))
-    }
 
-    /// Get bookmark folder index
-    fn bookmark_data_type_number_four(standard_data: &[u8]) -> nom::IResult<&[u8], i32> {
-        let (data, index) = le_i32(standard_data)?;
-        Ok((data, index))
+        // TOC records are sorted by record type
+        records.sort_by_key(|record| record.record_type);
+        // Same version as the bookmarks in the Sierra backgrounditems.btm
+        let version = 1040;
+        Bookmark { version, records }
     }
 
-    /// Get bookmark creation timestamps
-    fn bookmark_data_type_date(standard_data: &[u8]) -> nom::IResult<&[u8], f64> {
-        //Apple stores timestamps as Big Endian Float64
-        let (data, creation) = be_f64(standard_data)?;
-        Ok((data, creation))
+    /// Get loginitem data from embedded loginitems in Apps
+    pub fn loginitem_apps() -> Result<Vec<LoginItemsResults>, LoginItemsError> {
+        LoginItemsData::loginitem_apps_root(Path::new("/"))
     }
 
-    /// Get loginitem data from embedded loginitems in Apps
-    pub fn loginitem_apps() -> Result<Vec<LoginItemsResults>, std::io::Error> {
-        const BUNDLED_APP_LOGINITEMS_PATH: &str = "/var/db/com.apple.xpc.launchd/";
+    /// Get loginitem data from embedded loginitems in Apps relative to a root directory
+    pub fn loginitem_apps_root(root: &Path) -> Result<Vec<LoginItemsResults>, LoginItemsError> {
+        const BUNDLED_APP_LOGINITEMS_PATH: &str = "var/db/com.apple.xpc.launchd/";
         let mut loginitems_vec: Vec<LoginItemsResults> = Vec::new();
-        for dir in read_dir(BUNDLED_APP_LOGINITEMS_PATH)? {
+        let loginitems_directory = root.join(BUNDLED_APP_LOGINITEMS_PATH);
+        // Triage collections may not include the launchd directory
+        if root != Path::new("/") && !loginitems_directory.is_dir() {
+            return Ok(loginitems_vec);
+        }
+        let entries = read_dir(&loginitems_directory)
+            .map_err(|err| LoginItemsError::io(&loginitems_directory, err))?;
+        for dir in entries {
             let mut loginitems = LoginItemsResults {
                 results: Vec::new(),
                 path: String::new(),
+                owner: String::new(),
+                errors: Vec::new(),
             };
-            let entry = dir?;
+            let entry = dir.map_err(|err| LoginItemsError::io(&loginitems_directory, err))?;
 
             let path = format!("{}", entry.path().display());
-            let full_path = Path::new(&path);
 
-            if !full_path.display().to_string().contains("loginitems") {
+            // Only check the file name, the root directory may also contain "loginitems"
+            if !entry.file_name().to_string_lossy().contains("loginitems") {
                 continue;
             }
-            let loginitems_plist: Result<plist::Dictionary, plist::Error> = plist::from_file(path);
+            // launchd records whether each label is disabled in the matching disabled.UID.plist
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let disabled_path =
+                loginitems_directory.join(file_name.replacen("loginitems", "disabled", 1));
+            let mut disabled_labels: BTreeMap<String, bool> = BTreeMap::new();
+            match loginitems_plist::get_optional_disabled_labels(&disabled_path) {
+                Ok(labels) => disabled_labels = labels,
+                Err(err) => {
+                    warn!(
+                        "Failed to parse PLIST file: {:?} {:?}",
+                        disabled_path.display(),
+                        err
+                    );
+                    loginitems.errors.push(err);
+                }
+            }
+
+            let loginitems_plist = loginitems_plist::get_app_loginitems(&path);
             match loginitems_plist {
                 Ok(data) => {
                     for (key, value) in data {
                         let mut loginitems_data = LoginItemsData {
-                            path: Vec::new(),
-                            cnid_path: Vec::new(),
-                            creation: 0.0,
-                            volume_path: String::new(),
-                            volume_url: String::new(),
-                            volume_name: String::new(),
-                            volume_uuid: String::new(),
-                            volume_size: 0,
-                            volume_creation: 0.0,
-                            volume_flag: Vec::new(),
-                            volume_root: false,
-                            localized_name: String::new(),
-                            security_extension: String::new(),
-                            target_flags: Vec::new(),
-                            username: String::new(),
-                            folder_index: 0,
-                            uid: 0,
-                            creation_options: 0,
                             is_bundled: true,
-                            app_id: String::new(),
-                            app_binary: String::new(),
+                            ..Default::default()
                         };
                         if key.starts_with("version") {
                             continue;
                         }
-                        loginitems_data.app_id = value.as_string().unwrap().to_string();
+                        loginitems_data.app_id = match value.as_string() {
+                            Some(app_id) => app_id.to_string(),
+                            None => {
+                                warn!("Unexpected loginitems value for {} in {}", key, path);
+                                loginitems.errors.push(LoginItemsError::UnexpectedPlist {
+                                    path: path.to_string(),
+                                    message: format!(
+                                        "Incorrect plist type for {}. Expected string.",
+                                        key
+                                    ),
+                                });
+                                loginitems.path = path.to_string();
+                                continue;
+                            }
+                        };
+                        // Helper bundle ID is the launchd label
+                        loginitems_data.disabled = disabled_labels
+                            .get(&key)
+                            .or_else(|| disabled_labels.get(&loginitems_data.app_id))
+                            .copied();
                         loginitems_data.app_binary = key;
 
                         loginitems.results.push(loginitems_data);
@@ -756,6 +595,8 @@ impl LoginItemsData {
                         entry.path().display(),
                         err
                     );
+                    loginitems.path = entry.path().display().to_string();
+                    loginitems.errors.push(err);
                 }
             }
             loginitems_vec.push(loginitems);
@@ -763,6 +604,47 @@ impl LoginItemsData {
 
         Ok(loginitems_vec)
     }
+
+    /// Get loginitem data for a LaunchAgent or LaunchDaemon job. Path is the job executable
+    pub fn from_launchd_job(job: LaunchdJob) -> LoginItemsData {
+        // Relative executables are looked up in the launchd PATH, they cannot be resolved offline
+        let executable = job.executable();
+        let path: Vec<String> = if executable.starts_with('/') {
+            executable
+                .split('/')
+                .filter(|component| !component.is_empty())
+                .map(str::to_string)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        LoginItemsData {
+            path,
+            username: job.user_name.to_string(),
+            disabled: job.disabled,
+            launchd: Some(job),
+            ..Default::default()
+        }
+    }
+
+    /// Get loginitem data for a helper app found inside an app bundle. Path is relative to the root directory
+    pub fn from_bundled_helper(
+        path: Vec<String>,
+        host_app: &str,
+        host: &AppBundleInfo,
+        helper: AppBundleInfo,
+    ) -> LoginItemsData {
+        LoginItemsData {
+            path,
+            is_bundled: true,
+            app_id: host.bundle_id.to_string(),
+            app_binary: helper.bundle_id.to_string(),
+            host_app: host_app.to_string(),
+            bundle: Some(helper),
+            ..Default::default()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -770,12 +652,63 @@ mod tests {
 
     use std::path::PathBuf;
 
-    use super::{LoginItemsData, TableOfContentsDataRecord};
+    use super::{LoginItemsData, SourceType};
+    use crate::{
+        bookmark::{Bookmark, BookmarkRecord, BookmarkValue},
+        error::LoginItemsError,
+    };
 
     #[test]
+    #[ignore = "Parse loginitems on live system"]
     fn test_loginitem_apps() {
         let results = LoginItemsData::loginitem_apps().unwrap();
-        assert!(results.len() > 0)
+        assert!(!results.is_empty())
+    }
+
+    #[test]
+    fn test_loginitem_apps_root() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root");
+        let results = LoginItemsData::loginitem_apps_root(&test_location).unwrap();
+        assert!(results.len() == 1);
+
+        let app_id = "com.docker.docker";
+        let app_binary = "com.docker.helper";
+        assert!(results[0].results[0].app_id == app_id);
+        assert!(results[0].results[0].app_binary == app_binary);
+        assert!(results[0].results[0].disabled == Some(false));
+        assert!(results[0].results[1].disabled == Some(true));
+        assert!(results[0].results[0].source_type() == SourceType::LaunchdLoginItems);
+    }
+
+    #[test]
+    fn test_loginitem_apps_root_unexpected_value() {
+        let root = std::env::temp_dir().join(format!("loginitems_apps_{}", std::process::id()));
+        let launchd_directory = root.join("var/db/com.apple.xpc.launchd");
+        std::fs::create_dir_all(&launchd_directory).unwrap();
+        let mut loginitems = plist::Dictionary::new();
+        loginitems.insert(
+            "com.docker.helper".to_string(),
+            plist::Value::Integer(1.into()),
+        );
+        loginitems.insert(
+            "com.example.helper".to_string(),
+            plist::Value::String("com.example.app".to_string()),
+        );
+        plist::Value::Dictionary(loginitems)
+            .to_file_xml(launchd_directory.join("loginitems.501.plist"))
+            .unwrap();
+
+        let results = LoginItemsData::loginitem_apps_root(&root).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(results.len() == 1);
+        assert!(results[0].results.len() == 1);
+        assert!(results[0].results[0].app_id == "com.example.app");
+        assert!(matches!(
+            results[0].errors[0],
+            LoginItemsError::UnexpectedPlist { .. }
+        ));
     }
 
     #[test]
@@ -794,32 +727,42 @@ mod tests {
     }
 
     #[test]
-    fn test_bookmark_header() {
-        let test_header = [
-            98, 111, 111, 107, 72, 2, 0, 0, 0, 0, 4, 16, 48, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        ];
-        let (_, header) = LoginItemsData::bookmark_header(test_header.as_slice()).unwrap();
-        let book_sig: u32 = 1802465122;
-        let book_length: u32 = 584;
-        let book_offset: u32 = 48;
-        let book_version: u32 = 1040;
-        assert!(header.signature == book_sig);
-        assert!(header.bookmark_data_length == book_length);
-        assert!(header.bookmark_data_offset == book_offset);
-        assert!(header.version == book_version);
+    fn test_parse_btm_loginitems() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/BackgroundItems-v4.btm");
+        let loginitems_data =
+            LoginItemsData::parse_loginitems(&test_location.display().to_string()).unwrap();
+
+        assert!(loginitems_data.results.len() == 3);
+
+        // Only the login item record carries a bookmark
+        let app_path = ["Applications", "Syncthing.app"];
+        let login_item = &loginitems_data.results[1];
+        assert!(login_item.path == app_path);
+        assert!(login_item.btm.as_ref().unwrap().team_id == "LQE5SYM783");
+
+        let agent = &loginitems_data.results[2];
+        assert!(agent.path.is_empty());
+        assert!(agent.btm.as_ref().unwrap().identifier == "16.com.evil.agent");
+        assert!(agent.source_type() == SourceType::BackgroundTaskManagement);
     }
 
     #[test]
-    fn test_table_of_contents_header() {
-        let test_header = [192, 0, 0, 0, 254, 255, 255, 255];
-        let (_, header) = LoginItemsData::table_of_contents_header(test_header.as_slice()).unwrap();
-        let toc_length: u32 = 192;
-        let toc_record_type: u16 = 65534;
-        let toc_flags: u16 = 65535;
-        assert!(header.data_length == toc_length);
-        assert!(header.record_type == toc_record_type);
-        assert!(header.flags == toc_flags);
+    fn test_parse_loginitems_malformed() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/backgrounditems_malformed.btm");
+        let loginitems_data =
+            LoginItemsData::parse_loginitems(&test_location.display().to_string()).unwrap();
+
+        // Good bookmark is still parsed alongside the truncated copy
+        let app_path = ["Applications", "Syncthing.app"];
+        assert!(loginitems_data.results.len() == 1);
+        assert!(loginitems_data.results[0].path == app_path);
+        assert!(loginitems_data.errors.len() == 1);
+        assert!(matches!(
+            loginitems_data.errors[0],
+            LoginItemsError::TruncatedToc { .. }
+        ));
     }
 
     #[test]
@@ -856,7 +799,12 @@ mod tests {
             0, 0, 16, 1, 0, 0, 0, 0, 0, 0, 48, 32, 0, 0, 60, 1, 0, 0, 0, 0, 0, 0, 23, 240, 0, 0,
             68, 1, 0, 0, 0, 0, 0, 0, 128, 240, 0, 0, 88, 1, 0, 0, 0, 0, 0, 0,
         ];
-        let (_, loginitem) = LoginItemsData::bookmark_data(test_data.as_slice()).unwrap();
+        let records = Bookmark::bookmark_data(test_data.as_slice()).unwrap();
+        let bookmark = Bookmark {
+            version: 0,
+            records,
+        };
+        let loginitem = LoginItemsData::from_bookmark(bookmark);
         let app_path_len = 2;
         let cnid_path_len = 2;
         let target_creation = 665473989.0;
@@ -865,236 +813,75 @@ mod tests {
 
         assert!(loginitem.path.len() == app_path_len);
         assert!(loginitem.cnid_path.len() == cnid_path_len);
-        assert!(loginitem.creation == target_creation);
-        assert!(loginitem.volume_creation == volume_creation);
+        assert!(loginitem.creation.raw == target_creation);
+        assert!(loginitem.volume_creation.raw == volume_creation);
         assert!(loginitem.target_flags.len() == target_flags_len);
     }
 
     #[test]
-    fn test_table_of_contents_data() {
-        let test_data = [
-            1, 0, 0, 0, 0, 0, 0, 0, 15, 0, 0, 0, 4, 16, 0, 0, 52, 0, 0, 0, 0, 0, 0, 0, 5, 16, 0, 0,
-        ];
-        let record_data_size = 192;
-        let (_, toc_data) =
-            LoginItemsData::table_of_contents_data(test_data.as_slice(), record_data_size).unwrap();
-        let level = 1;
-        let next_record_offset = 0;
-        let number_of_records = 15;
-        assert!(toc_data.level == level);
-        assert!(toc_data.next_record_offset == next_record_offset);
-        assert!(toc_data.number_of_records == number_of_records);
-    }
-
-    #[test]
-    fn test_table_of_contents_record() {
-        let test_record = [
-            4, 16, 0, 0, 48, 0, 0, 0, 0, 0, 0, 0, 5, 16, 0, 0, 96, 0, 0, 0, 0, 0, 0, 0, 16, 16, 0,
-            0, 128, 0, 0, 0, 0, 0, 0, 0, 64, 16, 0, 0, 112, 0, 0, 0, 0, 0, 0, 0, 2, 32, 0, 0, 48,
-            1, 0, 0, 0, 0, 0, 0, 5, 32, 0, 0, 160, 0, 0, 0, 0, 0, 0, 0, 16, 32, 0, 0, 176, 0, 0, 0,
-            0, 0, 0, 0, 17, 32, 0, 0, 228, 0, 0, 0, 0, 0, 0, 0, 18, 32, 0, 0, 196, 0, 0, 0, 0, 0,
-            0, 0, 19, 32, 0, 0, 212, 0, 0, 0, 0, 0, 0, 0, 32, 32, 0, 0, 16, 1, 0, 0, 0, 0, 0, 0,
-            48, 32, 0, 0, 60, 1, 0, 0, 0, 0, 0, 0, 23, 240, 0, 0, 68, 1, 0, 0, 0, 0, 0, 0, 128,
-            240, 0, 0, 88, 1, 0, 0, 0, 0, 0, 0,
-        ];
-        let records = 14;
-
-        let (_, record) =
-            LoginItemsData::table_of_contents_record(test_record.as_slice(), &records).unwrap();
-        let record_type = 4100;
-        let record_offset = 48;
-        let record_reserved = 0;
-
-        assert!(record[0].record_type == record_type);
-        assert!(record[0].data_offset == record_offset);
-        assert!(record[0].reserved == record_reserved);
-        assert!(record.len() == records.try_into().unwrap());
-    }
-
-    #[test]
-    fn test_bookmark_standard_data() {
-        let bookmark_data = [
-            12, 0, 0, 0, 1, 1, 0, 0, 65, 112, 112, 108, 105, 99, 97, 116, 105, 111, 110, 115, 13,
-            0, 0, 0, 1, 1, 0, 0, 83, 121, 110, 99, 116, 104, 105, 110, 103, 46, 97, 112, 112, 0, 0,
-            0, 8, 0, 0, 0, 1, 6, 0, 0, 4, 0, 0, 0, 24, 0, 0, 0, 8, 0, 0, 0, 4, 3, 0, 0, 103, 0, 0,
-            0, 0, 0, 0, 0, 8, 0, 0, 0, 4, 3, 0, 0, 42, 198, 10, 0, 0, 0, 0, 0, 8, 0, 0, 0, 1, 6, 0,
-            0, 64, 0, 0, 0, 80, 0, 0, 0, 8, 0, 0, 0, 0, 4, 0, 0, 65, 195, 213, 41, 226, 128, 0, 0,
-            24, 0, 0, 0, 1, 2, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 15, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 8, 0, 0, 0, 1, 9, 0, 0, 102, 105, 108, 101, 58, 47, 47, 47, 12, 0, 0, 0, 1,
-            1, 0, 0, 77, 97, 99, 105, 110, 116, 111, 115, 104, 32, 72, 68, 8, 0, 0, 0, 4, 3, 0, 0,
-            0, 96, 127, 115, 37, 0, 0, 0, 8, 0, 0, 0, 0, 4, 0, 0, 65, 172, 190, 215, 104, 0, 0, 0,
-            36, 0, 0, 0, 1, 1, 0, 0, 48, 65, 56, 49, 70, 51, 66, 49, 45, 53, 49, 68, 57, 45, 51,
-            51, 51, 53, 45, 66, 51, 69, 51, 45, 49, 54, 57, 67, 51, 54, 52, 48, 51, 54, 48, 68, 24,
-            0, 0, 0, 1, 2, 0, 0, 129, 0, 0, 0, 1, 0, 0, 0, 239, 19, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, 0, 47, 0, 0, 0, 0, 0, 0, 0, 1, 5, 0, 0, 9, 0, 0, 0, 1,
-            1, 0, 0, 83, 121, 110, 99, 116, 104, 105, 110, 103, 0, 0, 0, 166, 0, 0, 0, 1, 2, 0, 0,
-            54, 52, 99, 98, 55, 101, 97, 97, 57, 97, 49, 98, 98, 99, 99, 99, 52, 101, 49, 51, 57,
-            55, 99, 57, 102, 50, 97, 52, 49, 49, 101, 98, 101, 53, 51, 57, 99, 100, 50, 57, 59, 48,
-            48, 48, 48, 48, 48, 48, 48, 59, 48, 48, 48, 48, 48, 48, 48, 48, 59, 48, 48, 48, 48, 48,
-            48, 48, 48, 48, 48, 48, 48, 48, 48, 50, 48, 59, 99, 111, 109, 46, 97, 112, 112, 108,
-            101, 46, 97, 112, 112, 45, 115, 97, 110, 100, 98, 111, 120, 46, 114, 101, 97, 100, 45,
-            119, 114, 105, 116, 101, 59, 48, 49, 59, 48, 49, 48, 48, 48, 48, 48, 52, 59, 48, 48,
-            48, 48, 48, 48, 48, 48, 48, 48, 48, 97, 99, 54, 50, 97, 59, 47, 97, 112, 112, 108, 105,
-            99, 97, 116, 105, 111, 110, 115, 47, 115, 121, 110, 99, 116, 104, 105, 110, 103, 46,
-            97, 112, 112, 0, 0, 0,
-        ];
-        let toc_record = TableOfContentsDataRecord {
-            record_type: 8209,
-            data_offset: 228,
-            reserved: 0,
+    fn test_from_bookmark_volume_bookmark() {
+        let record = |record_type: u32, value: BookmarkValue| BookmarkRecord {
+            record_type,
+            level: 1,
+            data_offset: 0,
+            value,
         };
-        let (_, std_data) =
-            LoginItemsData::bookmark_standard_data(bookmark_data.as_slice(), &toc_record).unwrap();
-
-        let data_length = 36;
-        let data_type = 257;
-        let record_data = [
-            48, 65, 56, 49, 70, 51, 66, 49, 45, 53, 49, 68, 57, 45, 51, 51, 51, 53, 45, 66, 51, 69,
-            51, 45, 49, 54, 57, 67, 51, 54, 52, 48, 51, 54, 48, 68,
-        ];
-        let record_type = 8209;
-
-        assert!(std_data.data_length == data_length);
-        assert!(std_data.data_type == data_type);
-        assert!(std_data.record_data == record_data);
-        assert!(std_data.record_type == record_type);
-    }
-
-    #[test]
-    fn test_loginitem_data() {
-        let test_data = [
-            12, 0, 0, 0, 1, 1, 0, 0, 65, 112, 112, 108, 105, 99, 97, 116, 105, 111, 110, 115, 13,
-            0, 0, 0, 1, 1, 0, 0, 83, 121, 110, 99, 116, 104, 105, 110, 103, 46, 97, 112, 112, 0, 0,
-            0, 8, 0, 0, 0, 1, 6, 0, 0, 4, 0, 0, 0, 24, 0, 0, 0, 8, 0, 0, 0, 4, 3, 0, 0, 103, 0, 0,
-            0, 0, 0, 0, 0, 8, 0, 0, 0, 4, 3, 0, 0, 42, 198, 10, 0, 0, 0, 0, 0, 8, 0, 0, 0, 1, 6, 0,
-            0, 64, 0, 0, 0, 80, 0, 0, 0, 8, 0, 0, 0, 0, 4, 0, 0, 65, 195, 213, 41, 226, 128, 0, 0,
-            24, 0, 0, 0, 1, 2, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 15, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 8, 0, 0, 0, 1, 9, 0, 0, 102, 105, 108, 101, 58, 47, 47, 47, 12, 0, 0, 0, 1,
-            1, 0, 0, 77, 97, 99, 105, 110, 116, 111, 115, 104, 32, 72, 68, 8, 0, 0, 0, 4, 3, 0, 0,
-            0, 96, 127, 115, 37, 0, 0, 0, 8, 0, 0, 0, 0, 4, 0, 0, 65, 172, 190, 215, 104, 0, 0, 0,
-            36, 0, 0, 0, 1, 1, 0, 0, 48, 65, 56, 49, 70, 51, 66, 49, 45, 53, 49, 68, 57, 45, 51,
-            51, 51, 53, 45, 66, 51, 69, 51, 45, 49, 54, 57, 67, 51, 54, 52, 48, 51, 54, 48, 68, 24,
-            0, 0, 0, 1, 2, 0, 0, 129, 0, 0, 0, 1, 0, 0, 0, 239, 19, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, 0, 47, 0, 0, 0, 0, 0, 0, 0, 1, 5, 0, 0, 9, 0, 0, 0, 1,
-            1, 0, 0, 83, 121, 110, 99, 116, 104, 105, 110, 103, 0, 0, 0, 166, 0, 0, 0, 1, 2, 0, 0,
-            54, 52, 99, 98, 55, 101, 97, 97, 57, 97, 49, 98, 98, 99, 99, 99, 52, 101, 49, 51, 57,
-            55, 99, 57, 102, 50, 97, 52, 49, 49, 101, 98, 101, 53, 51, 57, 99, 100, 50, 57, 59, 48,
-            48, 48, 48, 48, 48, 48, 48, 59, 48, 48, 48, 48, 48, 48, 48, 48, 59, 48, 48, 48, 48, 48,
-            48, 48, 48, 48, 48, 48, 48, 48, 48, 50, 48, 59, 99, 111, 109, 46, 97, 112, 112, 108,
-            101, 46, 97, 112, 112, 45, 115, 97, 110, 100, 98, 111, 120, 46, 114, 101, 97, 100, 45,
-            119, 114, 105, 116, 101, 59, 48, 49, 59, 48, 49, 48, 48, 48, 48, 48, 52, 59, 48, 48,
-            48, 48, 48, 48, 48, 48, 48, 48, 48, 97, 99, 54, 50, 97, 59, 47, 97, 112, 112, 108, 105,
-            99, 97, 116, 105, 111, 110, 115, 47, 115, 121, 110, 99, 116, 104, 105, 110, 103, 46,
-            97, 112, 112, 0, 0, 0,
-        ];
-        let test_array_offsets = [4, 24];
-        let toc_record = TableOfContentsDataRecord {
-            record_type: 4100,
-            data_offset: 48,
-            reserved: 0,
+        let volume_bookmark = Bookmark {
+            version: 0,
+            records: vec![record(
+                Bookmark::VOLUME_URL,
+                BookmarkValue::Url("smb://server/share".to_string()),
+            )],
         };
-        let records = 2;
-
-        let (_, std_record) = LoginItemsData::loginitem_data(
-            test_data.as_slice(),
-            (&test_array_offsets).to_vec(),
-            &toc_record,
-        )
-        .unwrap();
-        let record_type = 4100;
-        let data_type = 257;
-        let record_data = [65, 112, 112, 108, 105, 99, 97, 116, 105, 111, 110, 115];
-        let data_length = 12;
 
-        assert!(std_record[0].record_type == record_type);
-        assert!(std_record[0].data_type == data_type);
-        assert!(std_record[0].record_data == record_data);
-        assert!(std_record[0].data_length == data_length);
-
-        assert!(std_record.len() == records.try_into().unwrap());
-    }
-
-    #[test]
-    fn test_bookmark_array() {
-        let test_array = [4, 0, 0, 0, 24, 0, 0, 0];
-
-        let (_, book_array) = LoginItemsData::bookmark_array(test_array.as_slice()).unwrap();
-        let offset = 4;
-        let offset_2 = 24;
-
-        let offsets = 2;
-        assert!(book_array.len() == offsets);
-
-        assert!(book_array[0] == offset);
-        assert!(book_array[1] == offset_2);
-    }
-
-    #[test]
-    fn test_bookmark_data_type_string() {
-        let test_path = [83, 121, 110, 99, 116, 104, 105, 110, 103];
-
-        let book_path = LoginItemsData::bookmark_data_type_string(test_path.as_slice()).unwrap();
-        let path = "Syncthing";
-        assert!(book_path == path);
-    }
-
-    #[test]
-    fn test_bookmark_cnid() {
-        let test_cnid = [42, 198, 10, 0, 0, 0, 0, 0];
-
-        let (_, book_cnid) = LoginItemsData::bookmark_cnid(test_cnid.as_slice()).unwrap();
-        let cnid = 706090;
-        assert!(book_cnid == cnid);
-    }
-
-    #[test]
-    fn test_bookmark_target_flags() {
-        let test_flags = [
-            129, 0, 0, 0, 1, 0, 0, 0, 239, 19, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        let records = vec![
+            record(
+                Bookmark::VOLUME_BOOKMARK,
+                BookmarkValue::Bookmark(Box::new(volume_bookmark)),
+            ),
+            record(
+                Bookmark::VOLUME_MOUNT_POINT,
+                BookmarkValue::Url("file:///Volumes/share/".to_string()),
+            ),
         ];
+        let bookmark = Bookmark {
+            version: 0,
+            records,
+        };
 
-        let (_, book_flags) = LoginItemsData::bookmark_target_flags(test_flags.as_slice()).unwrap();
-        let flag = 4294967425;
-        let flag_2 = 4294972399;
-        let flag_3 = 0;
-
-        let flags = 3;
-
-        assert!(book_flags.len() == flags);
-        assert!(book_flags[0] == flag);
-        assert!(book_flags[1] == flag_2);
-        assert!(book_flags[2] == flag_3);
-    }
-
-    #[test]
-    fn test_bookmark_data_type_number_eight() {
-        let test_volume_size = [0, 96, 127, 115, 37, 0, 0, 0];
-
-        let (_, book_size) =
-            LoginItemsData::bookmark_data_type_number_eight(test_volume_size.as_slice()).unwrap();
-        let size = 160851517440;
-
-        assert!(book_size == size);
-    }
-
-    #[test]
-    fn test_bookmark_data_type_date() {
-        let test_creation = [65, 172, 190, 215, 104, 0, 0, 0];
-
-        let (_, book_creation) =
-            LoginItemsData::bookmark_data_type_date(test_creation.as_slice()).unwrap();
-        let creation = 241134516.0;
-
-        assert!(book_creation == creation);
+        let loginitem = LoginItemsData::from_bookmark(bookmark);
+        assert!(loginitem.volume_mount_point == "file:///Volumes/share/");
+        let volume = loginitem.volume_bookmark.unwrap();
+        assert!(volume.volume_url == "smb://server/share");
+        assert!(volume.bookmark.is_none());
     }
 
     #[test]
-    fn test_bookmark_data_type_number_four() {
-        let test_creation = [0, 0, 0, 32];
-
-        let (_, creation_options) =
-            LoginItemsData::bookmark_data_type_number_four(test_creation.as_slice()).unwrap();
-        let options = 536870912;
-        assert!(creation_options == options);
+    fn test_to_bookmark() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/backgrounditems_sierra.btm");
+        let results = LoginItemsData::parse_loginitems(&test_location.display().to_string())
+            .unwrap()
+            .results;
+        let mut loginitem = results.into_iter().next().unwrap();
+
+        // Rebuild the bookmark from the loginitem fields only
+        loginitem.bookmark = None;
+        let data = loginitem.to_bookmark().to_bytes();
+        let rebuilt = LoginItemsData::from_bookmark(Bookmark::parse(&data).unwrap());
+
+        assert!(rebuilt.path == loginitem.path);
+        assert!(rebuilt.cnid_path == loginitem.cnid_path);
+        assert!(rebuilt.target_flags == loginitem.target_flags);
+        assert!(rebuilt.creation == loginitem.creation);
+        assert!(rebuilt.volume_creation == loginitem.volume_creation);
+        assert!(rebuilt.volume_url == loginitem.volume_url);
+        assert!(rebuilt.volume_name == loginitem.volume_name);
+        assert!(rebuilt.volume_uuid == loginitem.volume_uuid);
+        assert!(rebuilt.volume_size == loginitem.volume_size);
+        assert!(rebuilt.volume_flag == loginitem.volume_flag);
+        assert!(rebuilt.volume_root == loginitem.volume_root);
+        assert!(rebuilt.security_extension == loginitem.security_extension);
+        assert!(rebuilt.creation_options == loginitem.creation_options);
     }
 }
@@ -2,31 +2,40 @@
 //!
 //! Provides a library to parse LoginItems data.
 
-use std::{
-    error,
-    io::{Error, ErrorKind},
-};
+use std::{collections::BTreeMap, fs::File, io::BufReader, path::Path};
 
 use log::warn;
 use plist::{Dictionary, Value};
+use serde::de::DeserializeOwned;
+
+use crate::error::LoginItemsError;
+
+/// Read a PLIST file. Missing or unreadable files are reported separately from corrupt PLISTs
+pub fn read_plist<T: DeserializeOwned>(path: &str) -> Result<T, LoginItemsError> {
+    let file = File::open(path).map_err(|err| LoginItemsError::io(Path::new(path), err))?;
+    plist::from_reader(BufReader::new(file)).map_err(|err| LoginItemsError::PlistDecode {
+        path: path.to_string(),
+        source: err,
+    })
+}
 
 /// Parse PLIST file and get Vec of bookmark data
-pub fn get_bookmarks(path: &str) -> Result<Vec<Vec<u8>>, Box<dyn error::Error + '_>> {
-    let login_items: Dictionary = plist::from_file(path)?;
+pub fn get_bookmarks(path: &str) -> Result<Vec<Vec<u8>>, LoginItemsError> {
+    let login_items: Dictionary = read_plist(path)?;
     for (key, value) in login_items {
         if key.as_str() != "$objects" {
             continue;
         }
         match value {
             Value::Array(_) => {
-                let results = get_array_values(value)?;
+                let results = get_array_values(value);
                 return Ok(results);
             }
             _ => {
-                return Err(Box::new(Error::new(
-                    ErrorKind::InvalidInput,
-                    "Incorrect plist type. Expected array.".to_string(),
-                )));
+                return Err(LoginItemsError::UnexpectedPlist {
+                    path: path.to_string(),
+                    message: "Incorrect plist type. Expected array.".to_string(),
+                });
             }
         }
     }
@@ -35,7 +44,7 @@ pub fn get_bookmarks(path: &str) -> Result<Vec<Vec<u8>>, Box<dyn error::Error +
 }
 
 /// Loop through Array values and identify bookmark data (should be at least 48 bytes in size (header is 48 bytes))
-fn get_array_values(value: Value) -> Result<Vec<Vec<u8>>, Box<dyn error::Error + 'static>> {
+fn get_array_values(value: Value) -> Vec<Vec<u8>> {
     let mut bookmark_data: Vec<Vec<u8>> = Vec::new();
     let results = value.as_array();
     match results {
@@ -83,20 +92,245 @@ fn get_array_values(value: Value) -> Result<Vec<Vec<u8>>, Box<dyn error::Error +
                 }
             }
         }
-        None => return Ok(bookmark_data),
+        None => return bookmark_data,
     }
-    Ok(bookmark_data)
+    bookmark_data
 }
 
 /// Try to get LoginItems in App bundles. Should be in files: loginitems.UID.plist
-pub fn get_app_loginitems(path: &str) -> Result<Dictionary, plist::Error> {
-    let login_items: Dictionary = plist::from_file(path)?;
+pub fn get_app_loginitems(path: &str) -> Result<Dictionary, LoginItemsError> {
+    let login_items: Dictionary = read_plist(path)?;
     Ok(login_items)
 }
 
+/// Get the launchd disabled state of each label. Should be in files: disabled.plist and disabled.UID.plist
+pub fn get_disabled_labels(path: &str) -> Result<BTreeMap<String, bool>, LoginItemsError> {
+    let labels: Dictionary = read_plist(path)?;
+    let mut disabled_labels: BTreeMap<String, bool> = BTreeMap::new();
+    for (label, value) in labels {
+        // Older overrides.plist files store a dictionary with a Disabled key
+        let disabled = match &value {
+            Value::Boolean(disabled) => *disabled,
+            Value::Dictionary(overrides) => match overrides.get("Disabled") {
+                Some(Value::Boolean(disabled)) => *disabled,
+                _ => continue,
+            },
+            _ => {
+                warn!(
+                    "Unexpected launchd disabled value for {}: {:?}",
+                    label, value
+                );
+                continue;
+            }
+        };
+        disabled_labels.insert(label, disabled);
+    }
+    Ok(disabled_labels)
+}
+
+/// Get the launchd disabled state of each label. A missing disabled PLIST file has no labels
+pub fn get_optional_disabled_labels(
+    path: &Path,
+) -> Result<BTreeMap<String, bool>, LoginItemsError> {
+    if !path.is_file() {
+        return Ok(BTreeMap::new());
+    }
+    get_disabled_labels(&path.display().to_string())
+}
+
+/// NSKeyedArchiver object graph. Objects reference each other by `CF$UID` index into `$objects`
+#[derive(Debug)]
+pub struct KeyedArchive {
+    objects: Vec<Value>,
+    top: Dictionary,
+}
+
+/// Parse PLIST file as an NSKeyedArchiver archive
+pub fn get_keyed_archive(path: &str) -> Result<KeyedArchive, LoginItemsError> {
+    let archive: Dictionary = read_plist(path)?;
+    let objects = match archive.get("$objects") {
+        Some(Value::Array(objects)) => objects.to_vec(),
+        _ => {
+            return Err(LoginItemsError::UnexpectedPlist {
+                path: path.to_string(),
+                message: "Not a keyed archive. Expected $objects array.".to_string(),
+            });
+        }
+    };
+    let top = match archive.get("$top") {
+        Some(Value::Dictionary(top)) => top.clone(),
+        _ => {
+            return Err(LoginItemsError::UnexpectedPlist {
+                path: path.to_string(),
+                message: "Not a keyed archive. Expected $top dictionary.".to_string(),
+            });
+        }
+    };
+    Ok(KeyedArchive { objects, top })
+}
+
+impl KeyedArchive {
+    /// Get the root object referenced by `$top`
+    pub fn root(&self) -> Option<&Value> {
+        let root = self.top.get("root")?;
+        self.resolve(root)
+    }
+
+    /// Follow a `CF$UID` reference into `$objects`. Values that are not references are returned as is.
+    /// `$null` references resolve to None
+    pub fn resolve<'a>(&'a self, value: &'a Value) -> Option<&'a Value> {
+        let mut current = value;
+        // Objects should never point directly at other references, but guard against hostile archives
+        for _ in 0..=self.objects.len() {
+            let uid = match KeyedArchive::uid(current) {
+                Some(uid) => uid,
+                None => return Some(current),
+            };
+            current = self.objects.get(uid as usize)?;
+            if current.as_string() == Some("$null") {
+                return None;
+            }
+        }
+        warn!("Keyed archive reference loop detected");
+        None
+    }
+
+    /// Get the `CF$UID` value. Binary PLISTs use a UID type, XML PLISTs use a dictionary
+    fn uid(value: &Value) -> Option<u64> {
+        match value {
+            Value::Uid(uid) => Some(uid.get()),
+            Value::Dictionary(dict) if dict.len() == 1 => {
+                dict.get("CF$UID").and_then(Value::as_unsigned_integer)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get value of a key in an archived object, following references
+    pub fn get<'a>(&'a self, object: &'a Value, key: &str) -> Option<&'a Value> {
+        let value = object.as_dictionary()?.get(key)?;
+        self.resolve(value)
+    }
+
+    /// Get the class name of an archived object
+    pub fn class_name<'a>(&'a self, object: &'a Value) -> Option<&'a str> {
+        let class = self.get(object, "$class")?;
+        class.as_dictionary()?.get("$classname")?.as_string()
+    }
+
+    /// Get a string value, empty if the key is missing or not a string
+    pub fn get_string(&self, object: &Value, key: &str) -> String {
+        match self.get(object, key) {
+            Some(Value::String(value)) => value.to_string(),
+            Some(value) if self.class_name(value) == Some("NSURL") => self.url(value),
+            _ => String::new(),
+        }
+    }
+
+    /// Get an integer value, zero if the key is missing or not an integer
+    pub fn get_integer(&self, object: &Value, key: &str) -> u64 {
+        let value = self.get(object, key);
+        value.and_then(Value::as_unsigned_integer).unwrap_or(0)
+    }
+
+    /// Get a data value, empty if the key is missing or not data
+    pub fn get_data(&self, object: &Value, key: &str) -> Vec<u8> {
+        match self.get(object, key) {
+            Some(Value::Data(data)) => data.to_vec(),
+            Some(value) => match self.get(value, "NS.data") {
+                Some(Value::Data(data)) => data.to_vec(),
+                _ => Vec::new(),
+            },
+            None => Vec::new(),
+        }
+    }
+
+    /// Get the members of an archived NSArray or NSSet
+    pub fn collection<'a>(&'a self, object: &'a Value) -> Vec<&'a Value> {
+        let members = object
+            .as_dictionary()
+            .and_then(|dict| dict.get("NS.objects"));
+        match members {
+            Some(Value::Array(members)) => members
+                .iter()
+                .filter_map(|member| self.resolve(member))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get the key/value pairs of an archived NSDictionary
+    pub fn dictionary<'a>(&'a self, object: &'a Value) -> Vec<(&'a Value, &'a Value)> {
+        let dict = match object.as_dictionary() {
+            Some(dict) => dict,
+            None => return Vec::new(),
+        };
+        let (keys, values) = match (dict.get("NS.keys"), dict.get("NS.objects")) {
+            (Some(Value::Array(keys)), Some(Value::Array(values))) => (keys, values),
+            _ => return Vec::new(),
+        };
+
+        let mut entries: Vec<(&Value, &Value)> = Vec::new();
+        for (key, value) in keys.iter().zip(values) {
+            match (self.resolve(key), self.resolve(value)) {
+                (Some(key), Some(value)) => entries.push((key, value)),
+                _ => continue,
+            }
+        }
+        entries
+    }
+
+    /// Get the absolute string of an archived NSURL
+    pub fn url(&self, object: &Value) -> String {
+        let max_depth = 16;
+        self.url_depth(object, max_depth)
+    }
+
+    fn url_depth(&self, object: &Value, depth: u8) -> String {
+        let relative = match self.get(object, "NS.relative") {
+            Some(Value::String(relative)) => relative.to_string(),
+            _ => String::new(),
+        };
+        if depth == 0 {
+            return relative;
+        }
+        match self.get(object, "NS.base") {
+            Some(base) => {
+                let base_url = self.url_depth(base, depth - 1);
+                join_url(&base_url, &relative)
+            }
+            None => relative,
+        }
+    }
+}
+
+/// Join a relative URL onto its base URL
+pub fn join_url(base: &str, relative: &str) -> String {
+    if base.is_empty() || relative.contains("://") {
+        return relative.to_string();
+    }
+    if let Some(absolute) = relative.strip_prefix('/') {
+        // Absolute path replaces the whole path of the base
+        let scheme_end = base.find("://").map(|index| index + 3).unwrap_or(0);
+        let host_end = base[scheme_end..]
+            .find('/')
+            .map(|index| index + scheme_end)
+            .unwrap_or(base.len());
+        return format!("{}/{}", &base[..host_end], absolute);
+    }
+    match base.rfind('/') {
+        Some(index) => format!("{}{}", &base[..=index], relative),
+        None => format!("{}/{}", base, relative),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{get_app_loginitems, get_array_values, get_bookmarks};
+    use super::{
+        get_app_loginitems, get_array_values, get_bookmarks, get_disabled_labels,
+        get_keyed_archive, get_optional_disabled_labels, join_url,
+    };
+    use crate::error::LoginItemsError;
     use plist::{Dictionary, Value};
     use std::path::PathBuf;
 
@@ -117,6 +351,29 @@ mod tests {
         assert!(results.len() > 1)
     }
 
+    #[test]
+    fn test_get_disabled_labels() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root/var/db/com.apple.xpc.launchd/disabled.501.plist");
+        let results = get_disabled_labels(&test_location.display().to_string()).unwrap();
+        assert!(results.len() == 4);
+        assert!(!results["com.docker.helper"]);
+        assert!(results["com.csaba.fitzl.shield.ShieldHelper"]);
+    }
+
+    #[test]
+    fn test_get_optional_disabled_labels() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root/var/db/com.apple.xpc.launchd");
+        let results = get_optional_disabled_labels(&test_location.join("disabled.plist")).unwrap();
+        assert!(results.len() == 3);
+        assert!(results["com.apple.ftpd"]);
+        assert!(results["com.docker.vmnetd"]);
+
+        let results = get_optional_disabled_labels(&test_location.join("disabled.502.plist"));
+        assert!(results.unwrap().is_empty());
+    }
+
     #[test]
     fn test_get_array_values() {
         let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -132,7 +389,7 @@ mod tests {
             }
             match value {
                 Value::Array(_) => {
-                    results = get_array_values(value).unwrap();
+                    results = get_array_values(value);
                 }
                 _ => {
                     panic!("Unsupported Value type, expected array. Got: {:?}", value)
@@ -141,4 +398,54 @@ mod tests {
         }
         assert!(results.len() == 1);
     }
+
+    #[test]
+    fn test_get_keyed_archive() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/BackgroundItems-v4.btm");
+
+        let archive = get_keyed_archive(&test_location.display().to_string()).unwrap();
+        let root = archive.root().unwrap();
+        assert!(archive.class_name(root) == Some("Storage"));
+        assert!(archive.get_integer(root, "version") == 4);
+
+        let users = archive.get(root, "itemsByUserIdentifier").unwrap();
+        let entries = archive.dictionary(users);
+        assert!(entries.len() == 1);
+        assert!(entries[0].0.as_string() == Some("A1B2C3D4-0000-4000-8000-0000000001F5"));
+
+        let items = archive.collection(entries[0].1);
+        assert!(items.len() == 3);
+        assert!(archive.get_string(items[0], "name") == "Syncthing");
+        assert!(archive.get_string(items[0], "url") == "file:///Applications/Syncthing.app/");
+        assert!(archive.get(items[0], "executablePath").is_none());
+        assert!(archive.get_data(items[1], "bookmark").starts_with(b"book"));
+    }
+
+    #[test]
+    fn test_get_bookmarks_missing() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/missing.btm");
+        let result = get_bookmarks(&test_location.display().to_string());
+        assert!(matches!(result, Err(LoginItemsError::Io { .. })));
+    }
+
+    #[test]
+    fn test_get_bookmarks_corrupt() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/backgrounditems_truncated.btm");
+        let result = get_bookmarks(&test_location.display().to_string());
+        assert!(matches!(result, Err(LoginItemsError::PlistDecode { .. })));
+    }
+
+    #[test]
+    fn test_join_url() {
+        assert!(join_url("", "file:///Applications/") == "file:///Applications/");
+        assert!(
+            join_url("file:///Applications/", "Syncthing.app")
+                == "file:///Applications/Syncthing.app"
+        );
+        assert!(join_url("file:///Applications/Other.app", "/Users/") == "file:///Users/");
+        assert!(join_url("file:///", "smb://server/share") == "smb://server/share");
+    }
 }
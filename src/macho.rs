@@ -0,0 +1,821 @@
+//! Parse macOS Mach-O executables
+//!
+//! Provides a library to get the architectures, code signature and entitlements of the executable a login item
+//! launches. Fat (universal) binaries are parsed per architecture.
+
+use std::{fmt, fs::read, mem::size_of, path::Path};
+
+use log::warn;
+use nom::{
+    bytes::complete::take,
+    number::complete::{be_u32, be_u64, u32 as endian_u32},
+    number::Endianness,
+};
+use plist::Dictionary;
+use serde::Serialize;
+
+use crate::error::LoginItemsError;
+
+// Mach-O documentation:
+// https://github.com/apple-oss-distributions/xnu/blob/main/EXTERNAL_HEADERS/mach-o/loader.h
+// https://github.com/apple-oss-distributions/xnu/blob/main/osfmk/kern/cs_blobs.h
+#[derive(Debug, Clone, Serialize)]
+pub struct MachOInfo {
+    pub path: String,            // Path to the executable
+    pub slices: Vec<MachOSlice>, // One per architecture
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MachOSlice {
+    pub architecture: String,                  // Architecture name (ex: arm64)
+    pub cpu_type: u32,                         // Mach-O CPU type
+    pub cpu_subtype: u32,                      // Mach-O CPU subtype
+    pub file_type: u32,                        // Mach-O file type (2 = executable)
+    pub has_code_signature: bool,              // Slice has a LC_CODE_SIGNATURE load command
+    pub code_signature: Option<CodeSignature>, // Decoded code signature
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CodeSignature {
+    pub identifier: String, // Signing identifier, usually the bundle ID
+    pub team_id: String,    // Team ID from the signing certificate or CodeDirectory
+    pub flags: u32,         // CodeDirectory flags
+    pub cms_signed: bool,   // Signature has a CMS blob with certificates
+    pub entitlements: Option<Dictionary>, // Embedded entitlements PLIST
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum SigningStatus {
+    /// No LC_CODE_SIGNATURE load command
+    Unsigned,
+    /// Signed without a certificate
+    AdHoc,
+    /// Signed with a certificate
+    Signed,
+    /// LC_CODE_SIGNATURE data could not be decoded
+    Malformed,
+}
+
+impl fmt::Display for SigningStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+const FAT_MAGIC: u32 = 0xcafebabe;
+const FAT_MAGIC_64: u32 = 0xcafebabf;
+const MH_MAGIC: u32 = 0xfeedface;
+const MH_MAGIC_64: u32 = 0xfeedfacf;
+const MH_CIGAM: u32 = 0xcefaedfe;
+const MH_CIGAM_64: u32 = 0xcffaedfe;
+const LC_CODE_SIGNATURE: u32 = 0x1d;
+
+const CSMAGIC_EMBEDDED_SIGNATURE: u32 = 0xfade0cc0;
+const CSMAGIC_CODEDIRECTORY: u32 = 0xfade0c02;
+const CSMAGIC_EMBEDDED_ENTITLEMENTS: u32 = 0xfade7171;
+const CSMAGIC_BLOBWRAPPER: u32 = 0xfade0b01;
+const CSSLOT_CODEDIRECTORY: u32 = 0;
+const CSSLOT_ENTITLEMENTS: u32 = 5;
+const CSSLOT_SIGNATURESLOT: u32 = 0x10000;
+const CS_SUPPORTSTEAMID: u32 = 0x20200;
+
+impl MachOInfo {
+    /// Architecture names of every slice
+    pub fn architectures(&self) -> Vec<String> {
+        self.slices
+            .iter()
+            .map(|slice| slice.architecture.to_string())
+            .collect()
+    }
+
+    /// Code signature of the first slice that has one
+    pub fn code_signature(&self) -> Option<&CodeSignature> {
+        self.slices
+            .iter()
+            .find_map(|slice| slice.code_signature.as_ref())
+    }
+
+    /// Weakest signing status of all slices. A single unsigned slice makes the binary unsigned
+    pub fn signing_status(&self) -> SigningStatus {
+        let statuses: Vec<SigningStatus> = self
+            .slices
+            .iter()
+            .map(|slice| slice.signing_status())
+            .collect();
+        let order = [
+            SigningStatus::Unsigned,
+            SigningStatus::Malformed,
+            SigningStatus::AdHoc,
+        ];
+        for status in order {
+            if statuses.is_empty() || statuses.contains(&status) {
+                return status;
+            }
+        }
+        SigningStatus::Signed
+    }
+}
+
+impl MachOSlice {
+    /// Check how the slice is signed
+    pub fn signing_status(&self) -> SigningStatus {
+        match &self.code_signature {
+            _ if !self.has_code_signature => SigningStatus::Unsigned,
+            None => SigningStatus::Malformed,
+            Some(signature) if signature.is_ad_hoc() => SigningStatus::AdHoc,
+            Some(_) => SigningStatus::Signed,
+        }
+    }
+}
+
+impl CodeSignature {
+    /// CodeDirectory flag set for ad-hoc signatures
+    pub const ADHOC: u32 = 0x2;
+    /// CodeDirectory flag set for signatures added by the linker
+    pub const LINKER_SIGNED: u32 = 0x20000;
+    /// CodeDirectory flag set for the hardened runtime
+    pub const RUNTIME: u32 = 0x10000;
+
+    /// Check if the signature has no signing certificate
+    pub fn is_ad_hoc(&self) -> bool {
+        self.flags & CodeSignature::ADHOC != 0 || !self.cms_signed
+    }
+}
+
+/// Parse the Mach-O executable at a path
+pub fn parse_macho_file(path: &Path) -> Result<MachOInfo, LoginItemsError> {
+    let data = read(path).map_err(|err| LoginItemsError::io(path, err))?;
+    let macho_info = MachOInfo {
+        path: path.display().to_string(),
+        slices: parse_macho(&data)?,
+    };
+    Ok(macho_info)
+}
+
+/// Parse a thin or fat Mach-O binary
+pub fn parse_macho(data: &[u8]) -> Result<Vec<MachOSlice>, LoginItemsError> {
+    let (_, magic) = be_u32::<_, ()>(data).map_err(|_| LoginItemsError::NotMachO)?;
+    match magic {
+        FAT_MAGIC | FAT_MAGIC_64 => parse_fat(data, magic == FAT_MAGIC_64),
+        _ => Ok(vec![parse_slice(data, 0)?]),
+    }
+}
+
+/// Parse every architecture in a fat binary. Fat headers are always big endian
+fn parse_fat(data: &[u8], is_64: bool) -> Result<Vec<MachOSlice>, LoginItemsError> {
+    let (mut input, arch_count) =
+        fat_header(data).map_err(|_| LoginItemsError::TruncatedMachO { offset: 0 })?;
+
+    // Java class files share the fat magic, their version is much larger than any architecture count
+    let max_archs = 16;
+    if arch_count > max_archs {
+        return Err(LoginItemsError::NotMachO);
+    }
+
+    let header_size = 8;
+    let arch_size = if is_64 { 32 } else { 20 };
+    let mut slices: Vec<MachOSlice> = Vec::new();
+    for index in 0..arch_count as usize {
+        let arch_offset = header_size + index * arch_size;
+        let (remaining, (offset, size)) =
+            fat_arch(input, is_64).map_err(|_| LoginItemsError::TruncatedMachO {
+                offset: arch_offset,
+            })?;
+        input = remaining;
+
+        let slice_data = usize::try_from(offset).ok().and_then(|start| {
+            let end = start.checked_add(usize::try_from(size).ok()?)?;
+            data.get(start..end)
+        });
+        match slice_data {
+            Some(slice_data) => slices.push(parse_slice(slice_data, offset as usize)?),
+            None => {
+                return Err(LoginItemsError::TruncatedMachO {
+                    offset: arch_offset,
+                })
+            }
+        }
+    }
+    Ok(slices)
+}
+
+/// Parse the fat header and return the number of architectures
+fn fat_header(data: &[u8]) -> nom::IResult<&[u8], u32> {
+    let (input, _magic) = be_u32(data)?;
+    let (input, arch_count) = be_u32(input)?;
+    Ok((input, arch_count))
+}
+
+/// Parse a fat architecture entry and return the offset and size of the slice
+fn fat_arch(data: &[u8], is_64: bool) -> nom::IResult<&[u8], (u64, u64)> {
+    let (input, _cpu_type) = be_u32(data)?;
+    let (input, _cpu_subtype) = be_u32(input)?;
+    if is_64 {
+        let (input, offset) = be_u64(input)?;
+        let (input, size) = be_u64(input)?;
+        let (input, _align) = be_u32(input)?;
+        let (input, _reserved) = be_u32(input)?;
+        return Ok((input, (offset, size)));
+    }
+    let (input, offset) = be_u32(input)?;
+    let (input, size) = be_u32(input)?;
+    let (input, _align) = be_u32(input)?;
+    Ok((input, (offset as u64, size as u64)))
+}
+
+struct MachHeader {
+    cpu_type: u32,
+    cpu_subtype: u32,
+    file_type: u32,
+    command_count: u32,
+}
+
+/// Parse a single architecture Mach-O. Base is the offset of the slice in the file, used for errors
+fn parse_slice(data: &[u8], base: usize) -> Result<MachOSlice, LoginItemsError> {
+    let truncated = |offset: usize| LoginItemsError::TruncatedMachO {
+        offset: base + offset,
+    };
+    let (_, magic) = be_u32::<_, ()>(data).map_err(|_| LoginItemsError::NotMachO)?;
+    let (endian, is_64) = match magic {
+        MH_MAGIC => (Endianness::Big, false),
+        MH_MAGIC_64 => (Endianness::Big, true),
+        MH_CIGAM => (Endianness::Little, false),
+        MH_CIGAM_64 => (Endianness::Little, true),
+        _ => return Err(LoginItemsError::NotMachO),
+    };
+    let (_, header) = mach_header(data, endian).map_err(|_| truncated(0))?;
+
+    let mut slice = MachOSlice {
+        architecture: architecture_name(header.cpu_type, header.cpu_subtype),
+        cpu_type: header.cpu_type,
+        cpu_subtype: header.cpu_subtype,
+        file_type: header.file_type,
+        has_code_signature: false,
+        code_signature: None,
+    };
+
+    // 64-bit headers have an extra reserved field
+    let mut command_offset = if is_64 { 32 } else { 28 };
+    for _ in 0..header.command_count {
+        let (input, _) =
+            take::<_, _, ()>(command_offset)(data).map_err(|_| truncated(command_offset))?;
+        let (input, (command, command_size)) =
+            load_command(input, endian).map_err(|_| truncated(command_offset))?;
+        let min_command_size = 8;
+        if (command_size as usize) < min_command_size {
+            return Err(truncated(command_offset));
+        }
+
+        if command == LC_CODE_SIGNATURE {
+            slice.has_code_signature = true;
+            let (_, (data_offset, data_size)) =
+                linkedit_data(input, endian).map_err(|_| truncated(command_offset))?;
+            let start = data_offset as usize;
+            let signature_data = data
+                .get(start..start.saturating_add(data_size as usize))
+                .ok_or_else(|| truncated(start))?;
+            slice.code_signature = code_signature(signature_data);
+        }
+        command_offset += command_size as usize;
+    }
+    Ok(slice)
+}
+
+/// Parse the Mach-O header after the magic
+fn mach_header(data: &[u8], endian: Endianness) -> nom::IResult<&[u8], MachHeader> {
+    let (input, _magic) = take(size_of::<u32>())(data)?;
+    let (input, cpu_type) = endian_u32(endian)(input)?;
+    let (input, cpu_subtype) = endian_u32(endian)(input)?;
+    let (input, file_type) = endian_u32(endian)(input)?;
+    let (input, command_count) = endian_u32(endian)(input)?;
+    let (input, _commands_size) = endian_u32(endian)(input)?;
+    let (input, _flags) = endian_u32(endian)(input)?;
+
+    let header = MachHeader {
+        cpu_type,
+        cpu_subtype,
+        file_type,
+        command_count,
+    };
+    Ok((input, header))
+}
+
+/// Parse the load command type and size
+fn load_command(data: &[u8], endian: Endianness) -> nom::IResult<&[u8], (u32, u32)> {
+    let (input, command) = endian_u32(endian)(data)?;
+    let (input, command_size) = endian_u32(endian)(input)?;
+    Ok((input, (command, command_size)))
+}
+
+/// Parse a linkedit data command and return the offset and size of the data
+fn linkedit_data(data: &[u8], endian: Endianness) -> nom::IResult<&[u8], (u32, u32)> {
+    let (input, data_offset) = endian_u32(endian)(data)?;
+    let (input, data_size) = endian_u32(endian)(input)?;
+    Ok((input, (data_offset, data_size)))
+}
+
+/// Get the architecture name for a CPU type
+fn architecture_name(cpu_type: u32, cpu_subtype: u32) -> String {
+    let cpu_arch_abi64 = 0x01000000;
+    let cpu_arch_abi64_32 = 0x02000000;
+    let cpu_type_x86 = 7;
+    let cpu_type_arm = 12;
+    let cpu_type_powerpc = 18;
+    let cpu_subtype_arm64e = 2;
+
+    let name = match cpu_type {
+        _ if cpu_type == cpu_type_x86 => "i386",
+        _ if cpu_type == cpu_type_x86 | cpu_arch_abi64 => "x86_64",
+        _ if cpu_type == cpu_type_arm => "arm",
+        _ if cpu_type == cpu_type_arm | cpu_arch_abi64 => {
+            // Upper bits of the subtype are capability flags
+            if cpu_subtype & 0xff == cpu_subtype_arm64e {
+                "arm64e"
+            } else {
+                "arm64"
+            }
+        }
+        _ if cpu_type == cpu_type_arm | cpu_arch_abi64_32 => "arm64_32",
+        _ if cpu_type == cpu_type_powerpc => "ppc",
+        _ if cpu_type == cpu_type_powerpc | cpu_arch_abi64 => "ppc64",
+        _ => return format!("unknown ({:#x})", cpu_type),
+    };
+    name.to_string()
+}
+
+/// Decode the code signature SuperBlob. Code signature data is always big endian
+fn code_signature(data: &[u8]) -> Option<CodeSignature> {
+    let (mut input, (magic, blob_count)) = match super_blob_header(data) {
+        Ok(result) => result,
+        Err(err) => {
+            warn!("Failed to parse code signature header: {:?}", err);
+            return None;
+        }
+    };
+    if magic != CSMAGIC_EMBEDDED_SIGNATURE {
+        warn!("Unexpected code signature magic {:#x}", magic);
+        return None;
+    }
+
+    let mut signature = CodeSignature {
+        identifier: String::new(),
+        team_id: String::new(),
+        flags: 0,
+        cms_signed: false,
+        entitlements: None,
+    };
+    let mut has_code_directory = false;
+    let mut code_directory_team_id = String::new();
+    for _ in 0..blob_count {
+        let (remaining, (slot, blob_offset)) = match blob_index(input) {
+            Ok(result) => result,
+            Err(err) => {
+                warn!("Failed to parse code signature blob index: {:?}", err);
+                return None;
+            }
+        };
+        input = remaining;
+
+        let (blob_magic, blob) = match code_signature_blob(data, blob_offset as usize) {
+            Some(result) => result,
+            None => {
+                warn!(
+                    "Code signature blob {:#x} at offset {:#x} is truncated",
+                    slot, blob_offset
+                );
+                continue;
+            }
+        };
+        match (slot, blob_magic) {
+            (CSSLOT_CODEDIRECTORY, CSMAGIC_CODEDIRECTORY) => {
+                let (_, (flags, identifier, team_id)) = match code_directory(blob) {
+                    Ok(result) => result,
+                    Err(err) => {
+                        warn!("Failed to parse code directory: {:?}", err);
+                        return None;
+                    }
+                };
+                has_code_directory = true;
+                signature.flags = flags;
+                signature.identifier = identifier;
+                code_directory_team_id = team_id;
+            }
+            (CSSLOT_ENTITLEMENTS, CSMAGIC_EMBEDDED_ENTITLEMENTS) => {
+                let blob_header_size = 8;
+                match plist::from_bytes::<Dictionary>(&blob[blob_header_size..]) {
+                    Ok(entitlements) => signature.entitlements = Some(entitlements),
+                    Err(err) => warn!("Failed to parse entitlements: {:?}", err),
+                }
+            }
+            (CSSLOT_SIGNATURESLOT, CSMAGIC_BLOBWRAPPER) => {
+                // Ad-hoc signatures have an empty CMS blob
+                let blob_header_size = 8;
+                let cms = &blob[blob_header_size..];
+                signature.cms_signed = !cms.is_empty();
+                signature.team_id = cms_team_id(cms);
+            }
+            _ => {}
+        }
+    }
+
+    if !has_code_directory {
+        warn!("Code signature does not have a code directory");
+        return None;
+    }
+    if signature.team_id.is_empty() {
+        signature.team_id = code_directory_team_id;
+    }
+    Some(signature)
+}
+
+/// Parse the SuperBlob header and return the magic and number of blobs
+fn super_blob_header(data: &[u8]) -> nom::IResult<&[u8], (u32, u32)> {
+    let (input, magic) = be_u32(data)?;
+    let (input, _length) = be_u32(input)?;
+    let (input, blob_count) = be_u32(input)?;
+    Ok((input, (magic, blob_count)))
+}
+
+/// Parse a SuperBlob index entry and return the slot type and blob offset
+fn blob_index(data: &[u8]) -> nom::IResult<&[u8], (u32, u32)> {
+    let (input, slot) = be_u32(data)?;
+    let (input, offset) = be_u32(input)?;
+    Ok((input, (slot, offset)))
+}
+
+/// Get a blob in the SuperBlob. The returned blob includes its magic and length
+fn code_signature_blob(data: &[u8], offset: usize) -> Option<(u32, &[u8])> {
+    let blob = data.get(offset..)?;
+    let (input, magic) = be_u32::<_, ()>(blob).ok()?;
+    let (_, length) = be_u32::<_, ()>(input).ok()?;
+    let blob_header_size = 8;
+    if (length as usize) < blob_header_size {
+        return None;
+    }
+    Some((magic, blob.get(..length as usize)?))
+}
+
+/// Parse the CodeDirectory and return the flags, identifier and team ID
+fn code_directory(data: &[u8]) -> nom::IResult<&[u8], (u32, String, String)> {
+    let (input, _magic) = be_u32(data)?;
+    let (input, _length) = be_u32(input)?;
+    let (input, version) = be_u32(input)?;
+    let (input, flags) = be_u32(input)?;
+    let (input, _hash_offset) = be_u32(input)?;
+    let (input, identifier_offset) = be_u32(input)?;
+
+    let mut team_id = String::new();
+    if version >= CS_SUPPORTSTEAMID {
+        // Skip the slot counts, code limit, hash info and scatter offset
+        let team_offset_position: usize = 24;
+        let (input, _) = take(team_offset_position)(input)?;
+        let (_, team_offset) = be_u32(input)?;
+        if team_offset != 0 {
+            team_id = c_string(data, team_offset as usize);
+        }
+    }
+    Ok((
+        input,
+        (flags, c_string(data, identifier_offset as usize), team_id),
+    ))
+}
+
+/// Read a NUL terminated string at an offset
+fn c_string(data: &[u8], offset: usize) -> String {
+    let value = data.get(offset..).unwrap_or_default();
+    let end = value
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(value.len());
+    String::from_utf8_lossy(&value[..end]).to_string()
+}
+
+/// Get the team ID from the CMS signature. Developer certificates store it in the subject organizational unit
+fn cms_team_id(cms: &[u8]) -> String {
+    let mut units: Vec<String> = Vec::new();
+    der_organizational_units(cms, 0, &mut units);
+
+    // Apple CA certificates in the chain also have organizational units
+    let is_team_id = |unit: &&String| {
+        let team_id_length = 10;
+        unit.len() == team_id_length
+            && unit
+                .chars()
+                .all(|char| char.is_ascii_uppercase() || char.is_ascii_digit())
+    };
+    units.iter().find(is_team_id).cloned().unwrap_or_default()
+}
+
+/// Walk BER/DER values and collect the organizational unit (OID 2.5.4.11) values.
+/// Returns the data after an end-of-contents marker, or None if the data is malformed
+fn der_organizational_units<'a>(
+    data: &'a [u8],
+    depth: usize,
+    units: &mut Vec<String>,
+) -> Option<&'a [u8]> {
+    let max_depth = 32;
+    if depth > max_depth {
+        return None;
+    }
+    let organizational_unit_oid = [0x55, 0x04, 0x0b];
+    let oid_tag = 0x06;
+    let string_tags = [0x0c, 0x13, 0x16]; // UTF8String, PrintableString, IA5String
+    let constructed = 0x20;
+
+    let mut input = data;
+    let mut previous_is_unit = false;
+    while !input.is_empty() {
+        let (tag, length, remaining) = der_header(input)?;
+        // End-of-contents closes an indefinite length value
+        if tag == 0 && length == Some(0) {
+            return Some(remaining);
+        }
+
+        match length {
+            Some(length) => {
+                let (content, remaining) = remaining.split_at_checked(length)?;
+                if tag & constructed != 0 {
+                    der_organizational_units(content, depth + 1, units)?;
+                } else if previous_is_unit && string_tags.contains(&tag) {
+                    units.push(String::from_utf8_lossy(content).to_string());
+                }
+                previous_is_unit = tag == oid_tag && content == organizational_unit_oid;
+                input = remaining;
+            }
+            // Indefinite length values end at an end-of-contents marker
+            None if tag & constructed != 0 => {
+                input = der_organizational_units(remaining, depth + 1, units)?;
+                previous_is_unit = false;
+            }
+            None => return None,
+        }
+    }
+    Some(input)
+}
+
+/// Parse a BER/DER tag and length. Indefinite lengths are returned as None
+fn der_header(data: &[u8]) -> Option<(u8, Option<usize>, &[u8])> {
+    let (&tag, input) = data.split_first()?;
+    // Multi-byte tags are not used in certificates
+    let multi_byte_tag = 0x1f;
+    if tag & multi_byte_tag == multi_byte_tag {
+        return None;
+    }
+
+    let (&first, input) = input.split_first()?;
+    let long_form = 0x80;
+    if first < long_form {
+        return Some((tag, Some(first as usize), input));
+    }
+    let length_size = (first & !long_form) as usize;
+    if length_size == 0 {
+        return Some((tag, None, input));
+    }
+    if length_size > size_of::<usize>() {
+        return None;
+    }
+    let (length_bytes, input) = input.split_at_checked(length_size)?;
+    let length = length_bytes
+        .iter()
+        .fold(0, |length, byte| (length << 8) | *byte as usize);
+    Some((tag, Some(length), input))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{
+        cms_team_id, parse_macho, parse_macho_file, CodeSignature, SigningStatus,
+        CSMAGIC_BLOBWRAPPER, CSMAGIC_CODEDIRECTORY, CSMAGIC_EMBEDDED_ENTITLEMENTS,
+        CSSLOT_CODEDIRECTORY, CSSLOT_ENTITLEMENTS, CSSLOT_SIGNATURESLOT, LC_CODE_SIGNATURE,
+    };
+    use crate::error::LoginItemsError;
+
+    const CPU_TYPE_X86_64: u32 = 0x01000007;
+    const CPU_TYPE_ARM64: u32 = 0x0100000c;
+
+    fn test_blob(magic: u32, payload: &[u8]) -> Vec<u8> {
+        let mut blob = magic.to_be_bytes().to_vec();
+        blob.extend(((payload.len() + 8) as u32).to_be_bytes());
+        blob.extend(payload);
+        blob
+    }
+
+    fn test_code_directory(identifier: &str, team_id: &str, flags: u32) -> Vec<u8> {
+        let header_size = 52;
+        let identifier_offset = header_size + 8;
+        let team_offset = identifier_offset + identifier.len() + 1;
+        let mut payload: Vec<u8> = Vec::new();
+        payload.extend(0x20400u32.to_be_bytes()); // version
+        payload.extend(flags.to_be_bytes());
+        payload.extend(0u32.to_be_bytes()); // hash offset
+        payload.extend((identifier_offset as u32).to_be_bytes());
+        payload.extend([0; 24]); // slot counts, code limit, hash info, scatter offset
+        payload.extend((team_offset as u32).to_be_bytes());
+        payload.extend([0; 8]);
+        payload.extend(identifier.as_bytes());
+        payload.push(0);
+        payload.extend(team_id.as_bytes());
+        payload.push(0);
+        test_blob(CSMAGIC_CODEDIRECTORY, &payload)
+    }
+
+    /// Minimal CMS structure with indefinite lengths like codesign writes, holding Apple and team OUs
+    fn test_cms(team_id: &str) -> Vec<u8> {
+        let name = |unit: &str| {
+            let mut attribute = vec![0x06, 0x03, 0x55, 0x04, 0x0b, 0x0c, unit.len() as u8];
+            attribute.extend(unit.as_bytes());
+            let mut sequence = vec![0x30, attribute.len() as u8];
+            sequence.extend(attribute);
+            let mut set = vec![0x31, sequence.len() as u8];
+            set.extend(sequence);
+            set
+        };
+        let mut cms = vec![0x30, 0x80, 0xa0, 0x80];
+        cms.extend(name("Apple Certification Authority"));
+        cms.extend(name(team_id));
+        cms.extend([0, 0, 0, 0]);
+        cms
+    }
+
+    fn test_signature(blobs: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let header_size = 12 + blobs.len() * 8;
+        let mut index: Vec<u8> = Vec::new();
+        let mut data: Vec<u8> = Vec::new();
+        for (slot, blob) in blobs {
+            index.extend(slot.to_be_bytes());
+            index.extend(((header_size + data.len()) as u32).to_be_bytes());
+            data.extend(blob);
+        }
+        let mut signature = 0xfade0cc0u32.to_be_bytes().to_vec();
+        signature.extend(((header_size + data.len()) as u32).to_be_bytes());
+        signature.extend((blobs.len() as u32).to_be_bytes());
+        signature.extend(index);
+        signature.extend(data);
+        signature
+    }
+
+    fn test_macho(cpu_type: u32, signature: Option<&[u8]>) -> Vec<u8> {
+        let header_size = 32;
+        let command_size = 16;
+        let mut macho = 0xfeedfacfu32.to_le_bytes().to_vec();
+        macho.extend(cpu_type.to_le_bytes());
+        macho.extend(0u32.to_le_bytes()); // cpu subtype
+        macho.extend(2u32.to_le_bytes()); // executable
+        macho.extend((signature.is_some() as u32).to_le_bytes());
+        macho.extend((command_size * signature.is_some() as u32).to_le_bytes());
+        macho.extend([0; 8]); // flags, reserved
+        if let Some(signature) = signature {
+            macho.extend(LC_CODE_SIGNATURE.to_le_bytes());
+            macho.extend(command_size.to_le_bytes());
+            macho.extend((header_size + command_size).to_le_bytes());
+            macho.extend((signature.len() as u32).to_le_bytes());
+            macho.extend(signature);
+        }
+        macho
+    }
+
+    fn test_fat(slices: &[Vec<u8>]) -> Vec<u8> {
+        let align = 0x1000;
+        let mut fat = 0xcafebabeu32.to_be_bytes().to_vec();
+        fat.extend((slices.len() as u32).to_be_bytes());
+        let mut offset = align;
+        for slice in slices {
+            fat.extend(
+                slice[4..12]
+                    .chunks(4)
+                    .flat_map(|value| u32::from_le_bytes(value.try_into().unwrap()).to_be_bytes()),
+            );
+            fat.extend((offset as u32).to_be_bytes());
+            fat.extend((slice.len() as u32).to_be_bytes());
+            fat.extend(12u32.to_be_bytes());
+            offset += slice.len().next_multiple_of(align);
+        }
+        for slice in slices {
+            fat.resize(fat.len().next_multiple_of(align), 0);
+            fat.extend(slice);
+        }
+        fat
+    }
+
+    fn test_signed_macho(cpu_type: u32) -> Vec<u8> {
+        let entitlements = b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<plist version=\"1.0\"><dict><key>com.apple.security.automation.apple-events</key><true/></dict></plist>\n";
+        let signature = test_signature(&[
+            (
+                CSSLOT_CODEDIRECTORY,
+                test_code_directory(
+                    "com.github.xor-gate.syncthing-macosx",
+                    "LQE5SYM783",
+                    CodeSignature::RUNTIME,
+                ),
+            ),
+            (
+                CSSLOT_ENTITLEMENTS,
+                test_blob(CSMAGIC_EMBEDDED_ENTITLEMENTS, entitlements),
+            ),
+            (
+                CSSLOT_SIGNATURESLOT,
+                test_blob(CSMAGIC_BLOBWRAPPER, &test_cms("LQE5SYM783")),
+            ),
+        ]);
+        test_macho(cpu_type, Some(&signature))
+    }
+
+    #[test]
+    fn test_parse_macho_file() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location
+            .push("tests/test_data/root/Applications/Syncthing.app/Contents/MacOS/Syncthing");
+        let info = parse_macho_file(&test_location).unwrap();
+
+        assert!(info.architectures() == ["x86_64", "arm64"]);
+        assert!(info.signing_status() == SigningStatus::Signed);
+        let signature = info.code_signature().unwrap();
+        assert!(signature.identifier == "com.github.xor-gate.syncthing-macosx");
+        assert!(signature.team_id == "LQE5SYM783");
+        assert!(signature.flags & CodeSignature::RUNTIME != 0);
+        let entitlements = signature.entitlements.as_ref().unwrap();
+        assert!(entitlements.contains_key("com.apple.security.automation.apple-events"));
+    }
+
+    #[test]
+    fn test_parse_macho_fat() {
+        let fat = test_fat(&[
+            test_signed_macho(CPU_TYPE_X86_64),
+            test_macho(CPU_TYPE_ARM64, None),
+        ]);
+        let slices = parse_macho(&fat).unwrap();
+        assert!(slices.len() == 2);
+        assert!(slices[0].signing_status() == SigningStatus::Signed);
+        assert!(slices[1].architecture == "arm64");
+        assert!(slices[1].signing_status() == SigningStatus::Unsigned);
+    }
+
+    #[test]
+    fn test_parse_macho_ad_hoc() {
+        // Linker signatures have no CMS blob
+        let code_directory = test_code_directory(
+            "a.out",
+            "",
+            CodeSignature::ADHOC | CodeSignature::LINKER_SIGNED,
+        );
+        let signature = test_signature(&[(CSSLOT_CODEDIRECTORY, code_directory)]);
+        let slices = parse_macho(&test_macho(CPU_TYPE_ARM64, Some(&signature))).unwrap();
+        assert!(slices[0].signing_status() == SigningStatus::AdHoc);
+        let code_signature = slices[0].code_signature.as_ref().unwrap();
+        assert!(code_signature.identifier == "a.out");
+        assert!(code_signature.team_id.is_empty());
+        assert!(code_signature.entitlements.is_none());
+
+        // Ad-hoc signatures from codesign have an empty CMS blob
+        let code_directory = test_code_directory("evil", "", CodeSignature::ADHOC);
+        let signature = test_signature(&[
+            (CSSLOT_CODEDIRECTORY, code_directory),
+            (CSSLOT_SIGNATURESLOT, test_blob(CSMAGIC_BLOBWRAPPER, &[])),
+        ]);
+        let slices = parse_macho(&test_macho(CPU_TYPE_X86_64, Some(&signature))).unwrap();
+        assert!(slices[0].signing_status() == SigningStatus::AdHoc);
+    }
+
+    #[test]
+    fn test_parse_macho_malformed_signature() {
+        let slices = parse_macho(&test_macho(CPU_TYPE_ARM64, Some(&[0; 16]))).unwrap();
+        assert!(slices[0].has_code_signature);
+        assert!(slices[0].signing_status() == SigningStatus::Malformed);
+    }
+
+    #[test]
+    fn test_parse_macho_invalid() {
+        let script = b"#!/bin/sh\necho \"Syncthing placeholder\"\n";
+        assert!(matches!(
+            parse_macho(script),
+            Err(LoginItemsError::NotMachO)
+        ));
+        assert!(matches!(parse_macho(&[]), Err(LoginItemsError::NotMachO)));
+
+        // Java class files share the fat magic
+        let class = [0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 0x3d];
+        assert!(matches!(
+            parse_macho(&class),
+            Err(LoginItemsError::NotMachO)
+        ));
+
+        let macho = test_signed_macho(CPU_TYPE_ARM64);
+        assert!(matches!(
+            parse_macho(&macho[..40]),
+            Err(LoginItemsError::TruncatedMachO { offset: 0x20 })
+        ));
+        assert!(matches!(
+            parse_macho(&macho[..60]),
+            Err(LoginItemsError::TruncatedMachO { offset: 0x30 })
+        ));
+    }
+
+    #[test]
+    fn test_cms_team_id() {
+        assert!(cms_team_id(&test_cms("ABCDE12345")) == "ABCDE12345");
+        assert!(cms_team_id(&test_cms("Apple Software")).is_empty());
+        assert!(cms_team_id(&[0x30, 0x82, 0xff]).is_empty());
+    }
+}
@@ -0,0 +1,246 @@
+//! Command line tool to parse macOS LoginItems, LaunchAgents and LaunchDaemons
+//!
+//! Exit status is 0 on success, 1 on failure and 2 if some files failed to parse but results were written.
+
+use std::{
+    error::Error,
+    fs::{read, File},
+    io::{stdout, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand};
+use macos_loginitems::{
+    bookmark::Bookmark,
+    diff::diff_results,
+    loginitems::LoginItemsResults,
+    output::{
+        bookmark_records, write_changes_csv, write_csv, write_json, write_json_lines, write_ndjson,
+        write_records_csv, OutputFormat, RecordContext,
+    },
+    parser::{
+        host_name_root, parse_loginitems_path, parse_loginitems_root, parse_loginitems_system,
+    },
+    siem::{write_events, EventSchema},
+    timeline::{timeline_events, write_bodyfile, write_l2t_csv},
+};
+
+#[derive(Parser)]
+#[command(
+    name = "loginitems",
+    version,
+    about = "Parse macOS LoginItems, LaunchAgents and LaunchDaemons",
+    after_help = "Exit status is 0 on success, 1 on failure and 2 if some files failed to parse"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Output format (json, csv, ndjson, bodyfile, l2tcsv, sqlite, ecs or ocsf)
+    #[arg(long, short, global = true, default_value = "json")]
+    format: OutputFormat,
+    /// Output file, - writes to stdout
+    #[arg(long, short, global = true, default_value = "-")]
+    output: String,
+    /// Host name written to ndjson, l2tcsv, ecs and ocsf records. Defaults to the host name of the scanned system
+    #[arg(long, global = true)]
+    host: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Scan the live system, or a root directory such as a mounted disk image
+    Scan {
+        /// Root directory to scan instead of the live system
+        #[arg(long)]
+        root: Option<PathBuf>,
+    },
+    /// Parse a single LoginItems file
+    Parse {
+        /// backgrounditems.btm, BackgroundItems-v*.btm or loginitems PLIST file
+        path: PathBuf,
+    },
+    /// Dump the raw bookmark records in a LoginItems or bookmark file
+    Dump {
+        /// LoginItems file or a raw bookmark
+        path: PathBuf,
+    },
+    /// Compare two LoginItems files or root directories
+    Diff {
+        /// Older file or root directory
+        old: PathBuf,
+        /// Newer file or root directory
+        new: PathBuf,
+    },
+}
+
+const EXIT_PARTIAL: u8 = 2;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(status) => status,
+        Err(err) => {
+            eprintln!("loginitems: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: &Cli) -> Result<ExitCode, Box<dyn Error>> {
+    let mut status = ExitCode::SUCCESS;
+    match &cli.command {
+        Command::Scan { root } => {
+            let report = match root {
+                Some(root) => parse_loginitems_root(root)?,
+                None => parse_loginitems_system()?,
+            };
+            for failure in &report.failures {
+                eprintln!("Failed to parse {}: {}", failure.path, failure.error);
+                status = ExitCode::from(EXIT_PARTIAL);
+            }
+            if cli.format == OutputFormat::Sqlite {
+                write_database(&cli.output, &report.results)?;
+                return Ok(status);
+            }
+            let writer = output_writer(&cli.output)?;
+            match cli.format {
+                OutputFormat::Json => write_json(writer, &report)?,
+                OutputFormat::Csv => write_csv(writer, &report.results)?,
+                format => {
+                    let host = match &cli.host {
+                        Some(host) => host.to_string(),
+                        None => host_name_root(root.as_deref().unwrap_or(Path::new("/"))),
+                    };
+                    write_records(writer, format, &report.results, &host)?
+                }
+            }
+        }
+        Command::Parse { path } => {
+            let results = parse_loginitems_path(&path.display().to_string())?;
+            for err in &results.errors {
+                eprintln!("Failed to parse item in {}: {}", results.path, err);
+                status = ExitCode::from(EXIT_PARTIAL);
+            }
+            if cli.format == OutputFormat::Sqlite {
+                write_database(&cli.output, &[results])?;
+                return Ok(status);
+            }
+            let writer = output_writer(&cli.output)?;
+            match cli.format {
+                OutputFormat::Json => write_json(writer, &results)?,
+                OutputFormat::Csv => write_csv(writer, &[results])?,
+                format => {
+                    let host = cli.host.as_deref().unwrap_or_default();
+                    write_records(writer, format, &[results], host)?
+                }
+            }
+        }
+        Command::Dump { path } => {
+            let bookmarks = read_bookmarks(path)?;
+            let records = bookmark_records(&bookmarks);
+            let writer = output_writer(&cli.output)?;
+            match cli.format {
+                OutputFormat::Json => write_json(writer, &records)?,
+                OutputFormat::Ndjson => write_json_lines(writer, &records)?,
+                OutputFormat::Csv => write_records_csv(writer, &records)?,
+                format => return Err(format!("dump does not support {} output", format).into()),
+            }
+        }
+        Command::Diff { old, new } => {
+            let old_results = read_results(old, &mut status)?;
+            let new_results = read_results(new, &mut status)?;
+            let changes = diff_results(&old_results, &new_results);
+            let writer = output_writer(&cli.output)?;
+            match cli.format {
+                OutputFormat::Json => write_json(writer, &changes)?,
+                OutputFormat::Ndjson => write_json_lines(writer, &changes)?,
+                OutputFormat::Csv => write_changes_csv(writer, &changes)?,
+                format => return Err(format!("diff does not support {} output", format).into()),
+            }
+        }
+    }
+    Ok(status)
+}
+
+/// Write login items as one record per item or per timestamp
+fn write_records(
+    writer: Box<dyn Write>,
+    format: OutputFormat,
+    results: &[LoginItemsResults],
+    host: &str,
+) -> Result<(), Box<dyn Error>> {
+    let context = RecordContext::new(host);
+    match format {
+        OutputFormat::Bodyfile => write_bodyfile(writer, &timeline_events(results))?,
+        OutputFormat::L2tCsv => write_l2t_csv(writer, &timeline_events(results), host)?,
+        OutputFormat::Ecs => write_events(writer, results, &context, EventSchema::Ecs)?,
+        OutputFormat::Ocsf => write_events(writer, results, &context, EventSchema::Ocsf)?,
+        _ => write_ndjson(writer, results, &context)?,
+    }
+    Ok(())
+}
+
+/// Export login items to a new SQLite database file
+fn write_database(output: &str, results: &[LoginItemsResults]) -> Result<(), Box<dyn Error>> {
+    if output == "-" {
+        return Err("sqlite output needs an output file".into());
+    }
+    #[cfg(feature = "sqlite")]
+    {
+        macos_loginitems::sqlite::write_sqlite(Path::new(output), results)
+            .map_err(|err| format!("Failed to write {}: {}", output, err))?;
+        Ok(())
+    }
+    #[cfg(not(feature = "sqlite"))]
+    {
+        let _ = results;
+        Err("loginitems was built without the sqlite feature".into())
+    }
+}
+
+/// Open the output file, or stdout for -
+fn output_writer(output: &str) -> Result<Box<dyn Write>, Box<dyn Error>> {
+    if output == "-" {
+        return Ok(Box::new(stdout().lock()));
+    }
+    let file =
+        File::create(output).map_err(|err| format!("Failed to create {}: {}", output, err))?;
+    Ok(Box::new(BufWriter::new(file)))
+}
+
+/// Get the bookmarks in a raw bookmark or LoginItems file
+fn read_bookmarks(path: &Path) -> Result<Vec<Bookmark>, Box<dyn Error>> {
+    let data = read(path).map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+    if data.starts_with(b"book") {
+        return Ok(vec![Bookmark::parse(&data)?]);
+    }
+    let results = parse_loginitems_path(&path.display().to_string())?;
+    let bookmarks = results
+        .results
+        .into_iter()
+        .filter_map(|loginitem| loginitem.bookmark)
+        .collect();
+    Ok(bookmarks)
+}
+
+/// Parse a LoginItems file, or scan a root directory. Failures are reported on stderr and set a partial status
+fn read_results(
+    path: &Path,
+    status: &mut ExitCode,
+) -> Result<Vec<LoginItemsResults>, Box<dyn Error>> {
+    if path.is_dir() {
+        let report = parse_loginitems_root(path)?;
+        for failure in &report.failures {
+            eprintln!("Failed to parse {}: {}", failure.path, failure.error);
+            *status = ExitCode::from(EXIT_PARTIAL);
+        }
+        return Ok(report.results);
+    }
+    let results = parse_loginitems_path(&path.display().to_string())?;
+    for err in &results.errors {
+        eprintln!("Failed to parse item in {}: {}", results.path, err);
+        *status = ExitCode::from(EXIT_PARTIAL);
+    }
+    Ok(vec![results])
+}
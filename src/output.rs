@@ -0,0 +1,467 @@
+//! Write LoginItems results
+//!
+//! Provides the output formats used by the `loginitems` command line tool.
+
+use std::{
+    fmt,
+    io::Write,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use crate::{
+    bookmark::{Bookmark, BookmarkValue},
+    diff::LoginItemsChange,
+    loginitems::{LoginItemsData, LoginItemsResults, SourceType},
+    timestamp::CocoaTimestamp,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// Pretty printed JSON document
+    Json,
+    /// One row per login item
+    Csv,
+    /// One JSON object per line per login item
+    Ndjson,
+    /// mactime bodyfile with one line per timestamp
+    Bodyfile,
+    /// L2T CSV with one row per timestamp
+    L2tCsv,
+    /// SQLite database (requires the sqlite feature and an output file)
+    Sqlite,
+    /// One Elastic Common Schema event per line per login item
+    Ecs,
+    /// One OCSF event per line per login item
+    Ocsf,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_lowercase().as_str() {
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
+            "bodyfile" => Ok(OutputFormat::Bodyfile),
+            "l2tcsv" | "l2t" => Ok(OutputFormat::L2tCsv),
+            "sqlite" | "db" => Ok(OutputFormat::Sqlite),
+            "ecs" => Ok(OutputFormat::Ecs),
+            "ocsf" => Ok(OutputFormat::Ocsf),
+            _ => Err(format!("Unsupported output format: {}", format)),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::Ndjson => write!(f, "ndjson"),
+            OutputFormat::Bodyfile => write!(f, "bodyfile"),
+            OutputFormat::L2tCsv => write!(f, "l2tcsv"),
+            OutputFormat::Sqlite => write!(f, "sqlite"),
+            OutputFormat::Ecs => write!(f, "ecs"),
+            OutputFormat::Ocsf => write!(f, "ocsf"),
+        }
+    }
+}
+
+/// CSV columns written by `write_csv`
+pub const CSV_HEADER: [&str; 43] = [
+    "Path",
+    "CNID Path",
+    "Target Creation Timestamp",
+    "Volume Path",
+    "Volume URL",
+    "Volume Name",
+    "Volume UUID",
+    "Volume Size",
+    "Volume Creation",
+    "Volume Flags",
+    "Volume Flag Names",
+    "Volume Root",
+    "Volume Mount Point",
+    "Volume Bookmark URL",
+    "Localized Name",
+    "Security Extension",
+    "Target Flags",
+    "Target Flag Names",
+    "Creator Username",
+    "Creator UID",
+    "Folder Index",
+    "Creation Options",
+    "Is App Bundled",
+    "APP ID",
+    "APP Binary",
+    "Host App",
+    "Registered In",
+    "Disabled",
+    "Launch Job Type",
+    "Launch Job Label",
+    "Launch Job Arguments",
+    "Run At Load",
+    "Keep Alive",
+    "Anomalies",
+    "Target Exists",
+    "Target SHA-256",
+    "App Bundle ID",
+    "Architectures",
+    "Signing Status",
+    "Signing Identifier",
+    "Team ID",
+    "Source",
+    "Source Owner",
+];
+
+#[derive(Debug, Clone)]
+pub struct RecordContext {
+    pub host: String,               // Host name of the system the sources came from
+    pub parse_time: CocoaTimestamp, // When the sources were parsed
+}
+
+impl RecordContext {
+    /// Get context for sources parsed now
+    pub fn new(host: &str) -> RecordContext {
+        let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(now) => now.as_secs_f64(),
+            Err(_) => 0.0,
+        };
+        RecordContext {
+            host: host.to_string(),
+            parse_time: CocoaTimestamp::from_unix(now),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoginItemsRecord<'a> {
+    pub source_path: &'a str,       // File the login item was found in
+    pub source_type: SourceType,    // Kind of source the login item came from
+    pub source_owner: &'a str,      // User that owns the source file
+    pub host: &'a str,              // Host name of the system
+    pub parse_time: CocoaTimestamp, // When the source was parsed
+    #[serde(flatten)]
+    pub item: &'a LoginItemsData,
+}
+
+/// Write any result as pretty printed JSON
+pub fn write_json<W: Write, T: Serialize>(mut writer: W, value: &T) -> std::io::Result<()> {
+    serde_json::to_writer_pretty(&mut writer, value)?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+/// Write every value as compact JSON on its own line
+pub fn write_json_lines<W: Write, T: Serialize>(
+    mut writer: W,
+    values: &[T],
+) -> std::io::Result<()> {
+    for value in values {
+        serde_json::to_writer(&mut writer, value)?;
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Write every login item as a CSV row
+pub fn write_csv<W: Write>(writer: W, results: &[LoginItemsResults]) -> std::io::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(CSV_HEADER)?;
+    for result in results {
+        for loginitem in &result.results {
+            writer.write_record(csv_row(loginitem, &result.path, &result.owner))?;
+        }
+    }
+    writer.flush()
+}
+
+/// Write every login item as a self-contained JSON object per line
+pub fn write_ndjson<W: Write>(
+    mut writer: W,
+    results: &[LoginItemsResults],
+    context: &RecordContext,
+) -> std::io::Result<()> {
+    for result in results {
+        for loginitem in &result.results {
+            let record = LoginItemsRecord {
+                source_path: &result.path,
+                source_type: loginitem.source_type(),
+                source_owner: &result.owner,
+                host: &context.host,
+                parse_time: context.parse_time,
+                item: loginitem,
+            };
+            serde_json::to_writer(&mut writer, &record)?;
+            writeln!(writer)?;
+        }
+    }
+    writer.flush()
+}
+
+/// Get the CSV columns for a login item found in a source file
+pub fn csv_row(loginitem: &LoginItemsData, source: &str, owner: &str) -> Vec<String> {
+    let signature = loginitem
+        .executable
+        .as_ref()
+        .and_then(|executable| executable.code_signature());
+    vec![
+        loginitem.path.join("/"),
+        format!("{:?}", loginitem.cnid_path),
+        loginitem.creation.to_string(),
+        loginitem.volume_path.to_string(),
+        loginitem.volume_url.to_string(),
+        loginitem.volume_name.to_string(),
+        loginitem.volume_uuid.to_string(),
+        loginitem.volume_size.to_string(),
+        loginitem.volume_creation.to_string(),
+        format!("{:?}", loginitem.volume_flag),
+        loginitem.volume_flag_names.join(", "),
+        loginitem.volume_root.to_string(),
+        loginitem.volume_mount_point.to_string(),
+        match &loginitem.volume_bookmark {
+            Some(volume) => volume.volume_url.to_string(),
+            None => String::new(),
+        },
+        loginitem.localized_name.to_string(),
+        loginitem.security_extension.to_string(),
+        format!("{:?}", loginitem.target_flags),
+        loginitem.target_flag_names.join(", "),
+        loginitem.username.to_string(),
+        loginitem.uid.to_string(),
+        loginitem.folder_index.to_string(),
+        loginitem.creation_options.to_string(),
+        loginitem.is_bundled.to_string(),
+        loginitem.app_id.to_string(),
+        loginitem.app_binary.to_string(),
+        loginitem.host_app.to_string(),
+        loginitem.registered_in.to_string(),
+        match loginitem.disabled {
+            Some(disabled) => disabled.to_string(),
+            None => String::new(),
+        },
+        match &loginitem.launchd {
+            Some(job) => job.job_type.to_string(),
+            None => String::new(),
+        },
+        match &loginitem.launchd {
+            Some(job) => job.label.to_string(),
+            None => String::new(),
+        },
+        match &loginitem.launchd {
+            Some(job) => job.program_arguments.join(" "),
+            None => String::new(),
+        },
+        match &loginitem.launchd {
+            Some(job) => job.run_at_load.to_string(),
+            None => String::new(),
+        },
+        match &loginitem.launchd {
+            Some(job) => job.keep_alive.to_string(),
+            None => String::new(),
+        },
+        loginitem
+            .anomalies
+            .iter()
+            .map(|anomaly| anomaly.to_string())
+            .collect::<Vec<String>>()
+            .join("; "),
+        match &loginitem.target {
+            Some(target) => target.exists.to_string(),
+            None => String::new(),
+        },
+        match &loginitem.target {
+            Some(target) => target.sha256.to_string(),
+            None => String::new(),
+        },
+        match &loginitem.bundle {
+            Some(bundle) => bundle.bundle_id.to_string(),
+            None => String::new(),
+        },
+        match &loginitem.executable {
+            Some(executable) => executable.architectures().join(", "),
+            None => String::new(),
+        },
+        match &loginitem.executable {
+            Some(executable) => executable.signing_status().to_string(),
+            None => String::new(),
+        },
+        match signature {
+            Some(signature) => signature.identifier.to_string(),
+            None => String::new(),
+        },
+        match signature {
+            Some(signature) => signature.team_id.to_string(),
+            None => String::new(),
+        },
+        source.to_string(),
+        owner.to_string(),
+    ]
+}
+
+#[derive(Debug, Serialize)]
+pub struct BookmarkRecordDump<'a> {
+    pub bookmark: usize,          // Index of the bookmark in the file
+    pub record_type: u32,         // TOC record type
+    pub record_name: String,      // TOC record type name
+    pub level: u32,               // TOC level the record was found in
+    pub value: &'a BookmarkValue, // Decoded record value
+}
+
+/// Flatten the records of every bookmark
+pub fn bookmark_records(bookmarks: &[Bookmark]) -> Vec<BookmarkRecordDump<'_>> {
+    let mut records: Vec<BookmarkRecordDump> = Vec::new();
+    for (index, bookmark) in bookmarks.iter().enumerate() {
+        for record in &bookmark.records {
+            records.push(BookmarkRecordDump {
+                bookmark: index,
+                record_type: record.record_type,
+                record_name: Bookmark::record_name(record.record_type),
+                level: record.level,
+                value: &record.value,
+            });
+        }
+    }
+    records
+}
+
+/// Write login item changes as CSV rows
+pub fn write_changes_csv<W: Write>(
+    writer: W,
+    changes: &[LoginItemsChange<'_>],
+) -> std::io::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    let mut header = vec!["Change", "Key"];
+    header.extend(CSV_HEADER);
+    writer.write_record(header)?;
+    for change in changes {
+        let mut row = vec![change.change.to_string(), change.key.to_string()];
+        row.extend(csv_row(change.item, change.source, change.owner));
+        writer.write_record(row)?;
+    }
+    writer.flush()
+}
+
+/// Write bookmark records as CSV rows. Values are written as JSON
+pub fn write_records_csv<W: Write>(
+    writer: W,
+    records: &[BookmarkRecordDump<'_>],
+) -> std::io::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(["Bookmark", "Record Type", "Record Name", "Level", "Value"])?;
+    for record in records {
+        writer.write_record([
+            record.bookmark.to_string(),
+            format!("{:#x}", record.record_type),
+            record.record_name.to_string(),
+            record.level.to_string(),
+            serde_json::to_string(record.value)?,
+        ])?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{
+        bookmark_records, write_csv, write_json, write_json_lines, write_ndjson, write_records_csv,
+        OutputFormat, RecordContext, CSV_HEADER,
+    };
+    use crate::parser::parse_loginitems_path;
+
+    fn sierra_results() -> crate::loginitems::LoginItemsResults {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/backgrounditems_sierra.btm");
+        parse_loginitems_path(&test_location.display().to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let results = sierra_results();
+        let mut output: Vec<u8> = Vec::new();
+        write_csv(&mut output, &[results]).unwrap();
+
+        let mut reader = csv::Reader::from_reader(output.as_slice());
+        assert!(reader.headers().unwrap().len() == CSV_HEADER.len());
+        let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert!(rows.len() == 1);
+        assert!(rows[0][0] == *"Applications/Syncthing.app");
+        assert!(rows[0][2] == *"2022-02-02T05:53:09Z");
+    }
+
+    #[test]
+    fn test_write_json() {
+        let results = sierra_results();
+        let mut output: Vec<u8> = Vec::new();
+        write_json(&mut output, &results).unwrap();
+
+        let value: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert!(value["results"][0]["localized_name"] == "Syncthing");
+    }
+
+    #[test]
+    fn test_write_ndjson() {
+        let results = sierra_results();
+        let context = RecordContext::new("Sams-MacBook-Pro");
+        let mut output: Vec<u8> = Vec::new();
+        write_ndjson(&mut output, &[results], &context).unwrap();
+
+        let lines: Vec<&str> = std::str::from_utf8(&output).unwrap().lines().collect();
+        assert!(lines.len() == 1);
+        let value: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert!(value["source_path"]
+            .as_str()
+            .unwrap()
+            .ends_with("backgrounditems_sierra.btm"));
+        assert!(value["source_type"] == "BackgroundItems");
+        assert!(value["host"] == "Sams-MacBook-Pro");
+        assert!(value["parse_time"]["rfc3339"].is_string());
+        assert!(value["localized_name"] == "Syncthing");
+    }
+
+    #[test]
+    fn test_write_json_lines() {
+        let results = sierra_results();
+        let bookmark = results.results[0].bookmark.clone().unwrap();
+        let bookmarks = [bookmark];
+        let records = bookmark_records(&bookmarks);
+        let mut output: Vec<u8> = Vec::new();
+        write_json_lines(&mut output, &records).unwrap();
+
+        let lines: Vec<&str> = std::str::from_utf8(&output).unwrap().lines().collect();
+        assert!(lines.len() == records.len());
+        let value: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert!(value.is_object());
+    }
+
+    #[test]
+    fn test_write_records_csv() {
+        let results = sierra_results();
+        let bookmark = results.results[0].bookmark.clone().unwrap();
+        let bookmarks = [bookmark];
+        let records = bookmark_records(&bookmarks);
+        assert!(records
+            .iter()
+            .any(|record| record.record_name == "TARGET_PATH"));
+
+        let mut output: Vec<u8> = Vec::new();
+        write_records_csv(&mut output, &records).unwrap();
+        let reader = csv::Reader::from_reader(output.as_slice());
+        assert!(reader.into_records().count() == records.len());
+    }
+
+    #[test]
+    fn test_output_format() {
+        assert!("JSON".parse::<OutputFormat>().unwrap() == OutputFormat::Json);
+        assert!(OutputFormat::Csv.to_string() == "csv");
+        assert!("jsonl".parse::<OutputFormat>().unwrap() == OutputFormat::Ndjson);
+        assert!("L2T".parse::<OutputFormat>().unwrap() == OutputFormat::L2tCsv);
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+}
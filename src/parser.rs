@@ -1,50 +1,525 @@
 use std::{
-    error,
-    fs::read_dir,
-    io::{Error, ErrorKind},
-    path::Path,
+    collections::BTreeMap,
+    fs::{read_dir, symlink_metadata},
+    path::{Path, PathBuf},
 };
 
-use crate::loginitems::{LoginItemsData, LoginItemsResults};
+use log::warn;
+use plist::Value;
 
-pub fn parse_loginitems_system() -> Result<Vec<LoginItemsResults>, Box<dyn error::Error + 'static>>
-{
-    let base_directory = "/Users/Shared/BTM";
-    let loginitems_path =
-        "/2/BackgroundItems-v3.btm";
+use crate::{
+    app_bundle,
+    error::LoginItemsError,
+    launchd::{self, LaunchdJobType},
+    loginitems::{LoginItemsData, LoginItemsReport, LoginItemsResults},
+    loginitems_plist, macho, target,
+};
+
+pub fn parse_loginitems_system() -> Result<LoginItemsReport, LoginItemsError> {
+    parse_loginitems_root(Path::new("/"))
+}
+
+/// Parse all LoginItems, LaunchAgents and LaunchDaemons relative to a root directory (ex: a mounted disk image or
+/// triage collection). Results and failures are sorted by source path.
+/// Files that fail to parse are recorded in the report instead of stopping the scan
+pub fn parse_loginitems_root(root: &Path) -> Result<LoginItemsReport, LoginItemsError> {
+    let mut report = LoginItemsReport {
+        results: Vec::new(),
+        failures: Vec::new(),
+    };
+
+    parse_loginitems_btm(root, &mut report);
+    parse_loginitems_users(root, &mut report);
+
+    match LoginItemsData::loginitem_apps_root(root) {
+        Ok(mut app_loginitems) => report.results.append(&mut app_loginitems),
+        Err(err) => report.add_failure(&root.join("var/db/com.apple.xpc.launchd"), err),
+    }
+    parse_bundled_helpers(root, &mut report);
+    parse_launchd_jobs(root, &mut report);
+
+    // Check what the bookmarks point to now
+    for results in &mut report.results {
+        for loginitem in &mut results.results {
+            inspect_target(root, loginitem, &mut results.errors);
+        }
+    }
+
+    if report.results.is_empty() && report.failures.is_empty() {
+        return Err(LoginItemsError::NoLoginItems);
+    }
+    // Sources are listed by path, directory entries are not returned in a fixed order
+    report
+        .results
+        .sort_by(|first, second| first.path.cmp(&second.path));
+    report
+        .failures
+        .sort_by(|first, second| first.path.cmp(&second.path));
+    Ok(report)
+}
+
+/// Resolve the loginitem target, then read its app bundle Info.plist and Mach-O executable
+fn inspect_target(root: &Path, loginitem: &mut LoginItemsData, errors: &mut Vec<LoginItemsError>) {
+    loginitem.target = target::resolve_target(root, loginitem);
+    let status = match &loginitem.target {
+        Some(status) if status.exists && status.symlink_target.is_empty() => status,
+        _ => return,
+    };
+    let target_path = PathBuf::from(&status.resolved_path);
+
+    let executable_path = if status.is_directory {
+        if !app_bundle::is_app_bundle(&loginitem.path) {
+            return;
+        }
+        // Read the Info.plist of app bundles so bookmarks can be matched to app IDs
+        let info = match app_bundle::app_bundle_info(root, &target_path) {
+            Ok(info) => info,
+            Err(err) => {
+                warn!(
+                    "Failed to read app bundle {}: {}",
+                    target_path.display(),
+                    err
+                );
+                errors.push(err);
+                return;
+            }
+        };
+        // Executable name comes from the Info.plist, it must not leave the bundle
+        let executable = ["Contents", "MacOS", &info.executable].map(String::from);
+        loginitem.bundle = Some(info);
+        match target::target_path(&target_path, &executable) {
+            Some(executable_path) => executable_path,
+            None => return,
+        }
+    } else {
+        target_path
+    };
+
+    if let Some(link) = target::symlink_below_root(root, &executable_path) {
+        warn!("Not following symlink {}", link.display());
+        return;
+    }
+    match symlink_metadata(&executable_path) {
+        Ok(metadata) if metadata.is_file() => {}
+        _ => {
+            warn!("Executable {} not found", executable_path.display());
+            return;
+        }
+    }
+    match macho::parse_macho_file(&executable_path) {
+        Ok(info) => loginitem.executable = Some(info),
+        // Scripts can be login items too
+        Err(LoginItemsError::NotMachO) => {
+            warn!("{} is not a Mach-O binary", executable_path.display())
+        }
+        Err(err) => {
+            warn!(
+                "Failed to parse executable {}: {}",
+                executable_path.display(),
+                err
+            );
+            errors.push(err);
+        }
+    }
+}
+
+/// Split a path under the root directory into components, like a bookmark path
+fn root_components(root: &Path, path: &Path) -> Vec<String> {
+    let relative_path = path.strip_prefix(root).unwrap_or(path);
+    relative_path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect()
+}
+
+/// Get the entries of a directory, recording any failures in the report
+fn read_directory(directory: &Path, report: &mut LoginItemsReport) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = Vec::new();
+    let dir_entries = match read_dir(directory) {
+        Ok(dir_entries) => dir_entries,
+        Err(err) => {
+            report.add_failure(directory, LoginItemsError::io(directory, err));
+            return entries;
+        }
+    };
+    for dir in dir_entries {
+        match dir {
+            Ok(entry) => entries.push(entry.path()),
+            Err(err) => report.add_failure(directory, LoginItemsError::io(directory, err)),
+        }
+    }
+    entries
+}
+
+/// Parse the Ventura+ BackgroundItems-v*.btm files
+fn parse_loginitems_btm(root: &Path, report: &mut LoginItemsReport) {
+    let base_directory = root.join("Users/Shared/BTM");
+    let loginitems_path = "2";
+
+    // BTM directory only exists on Ventura+
+    if !base_directory.is_dir() {
+        return;
+    }
+    for entry in read_directory(&base_directory, report) {
+        let btm_directory = entry.join(loginitems_path);
+        if !btm_directory.is_dir() {
+            continue;
+        }
 
-    let mut loginitems_data: Vec<LoginItemsResults> = Vec::new();
-    for dir in read_dir(base_directory)? {
-        let entry = dir?;
-        let path = format!("{}{}", entry.path().display(), loginitems_path);
-        let full_path = Path::new(&path);
+        // BTM file name contains the storage version (BackgroundItems-v3.btm, BackgroundItems-v4.btm, ...)
+        for full_path in read_directory(&btm_directory, report) {
+            let file_name = match full_path.file_name() {
+                Some(file_name) => file_name.to_string_lossy().to_string(),
+                None => continue,
+            };
+            if !file_name.starts_with("BackgroundItems-v") || !file_name.ends_with(".btm") {
+                continue;
+            }
+            if !full_path.is_file() {
+                continue;
+            }
 
-        if full_path.is_file() {
             let plist_path = full_path.display().to_string();
-            let results = LoginItemsData::parse_loginitems(&plist_path);
-            match results {
-                Ok(data) => loginitems_data.push(data),
+            match LoginItemsData::parse_loginitems(&plist_path) {
+                Ok(results) => report.results.push(results),
+                Err(err) => report.add_failure(&full_path, err),
+            }
+        }
+    }
+}
+
+/// Parse the backgrounditems.btm file in each user home directory
+fn parse_loginitems_users(root: &Path, report: &mut LoginItemsReport) {
+    let base_directory = root.join("Users");
+    let loginitems_path =
+        "Library/Application Support/com.apple.backgroundtaskmanagementagent/backgrounditems.btm";
+
+    // root user home is not under /Users
+    let mut home_directories: Vec<PathBuf> = vec![root.join("var/root")];
+    if base_directory.is_dir() {
+        home_directories.append(&mut read_directory(&base_directory, report));
+    }
+
+    for home in home_directories {
+        let full_path = home.join(loginitems_path);
+        if !full_path.is_file() {
+            continue;
+        }
+
+        let path = full_path.display().to_string();
+        match LoginItemsData::parse_loginitems(&path) {
+            Ok(mut results) => {
+                results.owner = match home.file_name() {
+                    Some(username) => username.to_string_lossy().to_string(),
+                    None => String::new(),
+                };
+                report.results.push(results);
+            }
+            Err(err) => report.add_failure(&full_path, err),
+        }
+    }
+}
+
+/// Find the login item helper apps inside the app bundles in /Applications and ~/Applications. Helpers
+/// are linked to the launchd loginitems PLIST entries that register them
+fn parse_bundled_helpers(root: &Path, report: &mut LoginItemsReport) {
+    // Helpers in /Applications can be registered by any user
+    let mut applications_directories: Vec<(PathBuf, String, Option<u32>)> =
+        vec![(root.join("Applications"), String::new(), None)];
+    let base_directory = root.join("Users");
+    if base_directory.is_dir() {
+        for home in read_directory(&base_directory, report) {
+            let owner = match home.file_name() {
+                Some(username) => username.to_string_lossy().to_string(),
+                None => String::new(),
+            };
+            let uid = user_uid(root, &home);
+            applications_directories.push((home.join("Applications"), owner, uid));
+        }
+    }
+
+    for (directory, owner, uid) in applications_directories {
+        if !directory.is_dir() {
+            continue;
+        }
+        let mut results = LoginItemsResults {
+            results: Vec::new(),
+            path: directory.display().to_string(),
+            owner,
+            errors: Vec::new(),
+        };
+
+        for app in read_directory(&directory, report) {
+            let helpers = app_bundle::login_item_helpers(root, &app);
+            if helpers.is_empty() {
+                continue;
+            }
+            let host = match app_bundle::app_bundle_info(root, &app) {
+                Ok(host) => host,
+                Err(err) => {
+                    warn!("Failed to read app bundle {}: {}", app.display(), err);
+                    results.errors.push(err);
+                    continue;
+                }
+            };
+
+            for helper in helpers {
+                let helper_info = match app_bundle::app_bundle_info(root, &helper) {
+                    Ok(helper_info) => helper_info,
+                    Err(err) => {
+                        warn!("Failed to read app bundle {}: {}", helper.display(), err);
+                        results.errors.push(err);
+                        continue;
+                    }
+                };
+                results.results.push(LoginItemsData::from_bundled_helper(
+                    root_components(root, &helper),
+                    &format!("/{}", root_components(root, &app).join("/")),
+                    &host,
+                    helper_info,
+                ));
+            }
+        }
+
+        results.results = link_helpers(results.results, uid, report);
+        if !results.results.is_empty() || !results.errors.is_empty() {
+            report.results.push(results);
+        }
+    }
+}
+
+/// Link helpers to the launchd loginitems PLIST entries that register them. Helpers in a home directory are only
+/// linked to the loginitems PLIST of the user (UID). Shared helpers get an entry for every registration
+fn link_helpers(
+    helpers: Vec<LoginItemsData>,
+    uid: Option<u32>,
+    report: &mut LoginItemsReport,
+) -> Vec<LoginItemsData> {
+    let mut linked_helpers: Vec<LoginItemsData> = Vec::new();
+    for helper in helpers {
+        let mut registrations: Vec<LoginItemsData> = Vec::new();
+        for registered in &mut report.results {
+            if uid.is_some() && loginitems_uid(&registered.path) != uid {
+                continue;
+            }
+            for loginitem in &mut registered.results {
+                if !loginitem.is_bundled
+                    || !loginitem.path.is_empty()
+                    || loginitem.app_binary != helper.app_binary
+                {
+                    continue;
+                }
+                let mut registration = helper.clone();
+                registration.registered_in = registered.path.to_string();
+                registration.disabled = loginitem.disabled;
+                loginitem.host_app = helper.host_app.to_string();
+                registrations.push(registration);
+            }
+        }
+        if registrations.is_empty() {
+            linked_helpers.push(helper);
+        } else {
+            linked_helpers.append(&mut registrations);
+        }
+    }
+    linked_helpers
+}
+
+/// Get the UID from a launchd loginitems.UID.plist path
+fn loginitems_uid(path: &str) -> Option<u32> {
+    let file_name = Path::new(path).file_name()?.to_str()?;
+    file_name
+        .strip_prefix("loginitems.")?
+        .strip_suffix(".plist")?
+        .parse()
+        .ok()
+}
+
+/// Get the UID of a user from the local directory records under a root directory. Falls back to the owner of
+/// the home directory if the records are not available
+fn user_uid(root: &Path, home: &Path) -> Option<u32> {
+    let username = home.file_name()?.to_string_lossy().to_string();
+    let user_path = root
+        .join("var/db/dslocal/nodes/Default/users")
+        .join(format!("{}.plist", username));
+    if user_path.is_file() {
+        match loginitems_plist::read_plist::<Value>(&user_path.display().to_string()) {
+            Ok(user) => {
+                // Directory records store every attribute as an array of strings
+                let uid = user
+                    .as_dictionary()
+                    .and_then(|user| user.get("uid"))
+                    .and_then(Value::as_array)
+                    .and_then(|values| values.first())
+                    .and_then(Value::as_string)
+                    .and_then(|uid| uid.parse().ok());
+                if uid.is_some() {
+                    return uid;
+                }
+                warn!("No UID in user record {}", user_path.display());
+            }
+            Err(err) => warn!("Failed to read user record: {}", err),
+        }
+    }
+    match symlink_metadata(home) {
+        Ok(metadata) => Some(target::owner_ids(&metadata).0),
+        Err(err) => {
+            warn!("Failed to get owner of {}: {}", home.display(), err);
+            None
+        }
+    }
+}
+
+/// Parse the LaunchAgents and LaunchDaemons PLIST files in the system and user Library directories. The launchd
+/// disabled overrides take precedence over the Disabled key in the job PLIST
+fn parse_launchd_jobs(root: &Path, report: &mut LoginItemsReport) {
+    // System domain overrides only apply to LaunchDaemons. System LaunchAgents load in the gui domain of each
+    // user, so they keep the disabled state of the job PLIST
+    let launchd_db = root.join("var/db/com.apple.xpc.launchd");
+    let system_overrides = disabled_overrides(&launchd_db.join("disabled.plist"), report);
+    let mut launchd_directories: Vec<(PathBuf, LaunchdJobType, String, BTreeMap<String, bool>)> = vec![
+        (
+            root.join("Library/LaunchAgents"),
+            LaunchdJobType::Agent,
+            String::new(),
+            BTreeMap::new(),
+        ),
+        (
+            root.join("Library/LaunchDaemons"),
+            LaunchdJobType::Daemon,
+            String::new(),
+            system_overrides.clone(),
+        ),
+        (
+            root.join("System/Library/LaunchAgents"),
+            LaunchdJobType::Agent,
+            String::new(),
+            BTreeMap::new(),
+        ),
+        (
+            root.join("System/Library/LaunchDaemons"),
+            LaunchdJobType::Daemon,
+            String::new(),
+            system_overrides,
+        ),
+    ];
+
+    // root user home is not under /Users
+    let mut home_directories: Vec<PathBuf> = vec![root.join("var/root")];
+    let base_directory = root.join("Users");
+    if base_directory.is_dir() {
+        home_directories.append(&mut read_directory(&base_directory, report));
+    }
+    for home in home_directories {
+        let directory = home.join("Library/LaunchAgents");
+        if !directory.is_dir() {
+            continue;
+        }
+        let owner = match home.file_name() {
+            Some(username) => username.to_string_lossy().to_string(),
+            None => String::new(),
+        };
+        // Per-user LaunchAgents use the overrides of the user domain
+        let user_overrides = match user_uid(root, &home) {
+            Some(uid) => {
+                disabled_overrides(&launchd_db.join(format!("disabled.{}.plist", uid)), report)
+            }
+            None => BTreeMap::new(),
+        };
+        launchd_directories.push((directory, LaunchdJobType::Agent, owner, user_overrides));
+    }
+
+    for (directory, job_type, owner, overrides) in launchd_directories {
+        if !directory.is_dir() {
+            continue;
+        }
+        let mut results = LoginItemsResults {
+            results: Vec::new(),
+            path: directory.display().to_string(),
+            owner,
+            errors: Vec::new(),
+        };
+
+        for full_path in read_directory(&directory, report) {
+            let is_plist = match full_path.extension() {
+                Some(extension) => extension == "plist",
+                None => false,
+            };
+            if !is_plist || !full_path.is_file() {
+                continue;
+            }
+
+            let path = full_path.display().to_string();
+            match launchd::parse_launchd_plist(&path, job_type) {
+                Ok(job) => {
+                    let mut loginitem = LoginItemsData::from_launchd_job(job);
+                    if let Some(job) = &loginitem.launchd {
+                        if let Some(disabled) = overrides.get(&job.label) {
+                            loginitem.disabled = Some(*disabled);
+                        }
+                    }
+                    results.results.push(loginitem);
+                }
                 Err(err) => {
-                    return Err(Box::new(Error::new(
-                        ErrorKind::InvalidInput,
-                        format!("{:?}", err),
-                    )))
+                    warn!("Failed to parse launch job {}: {}", path, err);
+                    results.errors.push(err);
                 }
             }
         }
+        report.results.push(results);
     }
-    let mut app_loginitems = LoginItemsData::loginitem_apps()?;
-    loginitems_data.append(&mut app_loginitems);
-    if !loginitems_data.is_empty() {
-        return Ok(loginitems_data);
+}
+
+/// Get the launchd disabled overrides from a disabled PLIST file. Failures are added to the report
+fn disabled_overrides(path: &Path, report: &mut LoginItemsReport) -> BTreeMap<String, bool> {
+    match loginitems_plist::get_optional_disabled_labels(path) {
+        Ok(labels) => labels,
+        Err(err) => {
+            report.add_failure(path, err);
+            BTreeMap::new()
+        }
     }
-    Err(Box::new(Error::new(
-        ErrorKind::InvalidInput,
-        "No bookmark files on system".to_string(),
-    )))
 }
 
-pub fn parse_loginitems_path(path: &str) -> Result<LoginItemsResults, Box<dyn error::Error + '_>> {
+/// Get the host name of the system under a root directory from the SystemConfiguration preferences.
+/// Returns an empty string if the preferences are not available
+pub fn host_name_root(root: &Path) -> String {
+    let preferences_path = root.join("Library/Preferences/SystemConfiguration/preferences.plist");
+    if !preferences_path.is_file() {
+        return String::new();
+    }
+    let preferences: Value =
+        match loginitems_plist::read_plist(&preferences_path.display().to_string()) {
+            Ok(preferences) => preferences,
+            Err(err) => {
+                warn!("Failed to read host name: {}", err);
+                return String::new();
+            }
+        };
+
+    // HostName is only set when configured with scutil, LocalHostName is the Bonjour name
+    let names = [
+        ["System", "System", "HostName"].as_slice(),
+        ["System", "Network", "HostNames", "LocalHostName"].as_slice(),
+        ["System", "System", "ComputerName"].as_slice(),
+    ];
+    for keys in names {
+        let mut value = Some(&preferences);
+        for key in keys {
+            value = value
+                .and_then(Value::as_dictionary)
+                .and_then(|dictionary| dictionary.get(key));
+        }
+        if let Some(name) = value.and_then(Value::as_string) {
+            if !name.is_empty() {
+                return name.to_string();
+            }
+        }
+    }
+    String::new()
+}
+
+pub fn parse_loginitems_path(path: &str) -> Result<LoginItemsResults, LoginItemsError> {
     let results = LoginItemsData::parse_loginitems(path)?;
     Ok(results)
 }
@@ -53,14 +528,89 @@ pub fn parse_loginitems_path(path: &str) -> Result<LoginItemsResults, Box<dyn er
 mod tests {
     use std::path::PathBuf;
 
+    use super::host_name_root;
     use super::parse_loginitems_path;
+    use super::parse_loginitems_root;
     use super::parse_loginitems_system;
+    use super::{inspect_target, link_helpers, loginitems_uid, parse_launchd_jobs, user_uid};
+    use crate::{
+        error::LoginItemsError,
+        loginitems::{LoginItemsData, LoginItemsReport},
+        macho::SigningStatus,
+    };
 
     #[test]
     #[ignore = "Parse loginitems on live system"]
     fn test_parse_loginitems_system() {
-        let results = parse_loginitems_system().unwrap();
-        assert!(results.len() > 0);
+        let report = parse_loginitems_system().unwrap();
+        assert!(!report.results.is_empty());
+    }
+
+    #[test]
+    fn test_host_name_root() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root");
+        assert!(host_name_root(&test_location) == "Sams-MacBook-Pro");
+        assert!(host_name_root(&test_location.join("Users")).is_empty());
+    }
+
+    #[test]
+    fn test_user_uid() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root");
+        assert!(user_uid(&test_location, &test_location.join("Users/alex")) == Some(501));
+        assert!(loginitems_uid("/var/db/com.apple.xpc.launchd/loginitems.501.plist") == Some(501));
+        assert!(loginitems_uid("/var/db/com.apple.xpc.launchd/disabled.501.plist").is_none());
+    }
+
+    #[test]
+    fn test_link_helpers() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root");
+        let report = parse_loginitems_root(&test_location).unwrap();
+        let mut helper = report
+            .results
+            .iter()
+            .find(|results| results.path.ends_with("alex/Applications"))
+            .unwrap()
+            .results[0]
+            .clone();
+        helper.registered_in = String::new();
+        helper.disabled = None;
+
+        // Second user that registers the same helper
+        let mut registered = LoginItemsReport {
+            results: LoginItemsData::loginitem_apps_root(&test_location).unwrap(),
+            failures: Vec::new(),
+        };
+        let mut other_user = LoginItemsData::loginitem_apps_root(&test_location)
+            .unwrap()
+            .remove(0);
+        other_user.path = other_user.path.replace("loginitems.501", "loginitems.502");
+        for loginitem in &mut other_user.results {
+            if loginitem.app_binary == helper.app_binary {
+                loginitem.disabled = Some(true);
+            }
+        }
+        registered.results.push(other_user);
+
+        // Shared helpers keep every registration
+        let shared = link_helpers(vec![helper.clone()], None, &mut registered);
+        assert!(shared.len() == 2);
+        assert!(shared[0].registered_in.ends_with("loginitems.501.plist"));
+        assert!(shared[0].disabled == Some(false));
+        assert!(shared[1].registered_in.ends_with("loginitems.502.plist"));
+        assert!(shared[1].disabled == Some(true));
+
+        // Helpers in a home directory are only registered by the owner
+        let owned = link_helpers(vec![helper.clone()], Some(502), &mut registered);
+        assert!(owned.len() == 1);
+        assert!(owned[0].registered_in.ends_with("loginitems.502.plist"));
+        assert!(owned[0].disabled == Some(true));
+        let unregistered = link_helpers(vec![helper], Some(503), &mut registered);
+        assert!(unregistered.len() == 1);
+        assert!(unregistered[0].registered_in.is_empty());
+        assert!(unregistered[0].disabled.is_none());
     }
 
     #[test]
@@ -70,4 +620,163 @@ mod tests {
         let results = parse_loginitems_path(&test_location.display().to_string()).unwrap();
         assert!(results.results.len() == 1);
     }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_inspect_target_symlink() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data");
+        let mut loginitem = LoginItemsData::parse_loginitems(
+            &test_location
+                .join("backgrounditems_sierra.btm")
+                .display()
+                .to_string(),
+        )
+        .unwrap()
+        .results
+        .remove(0);
+
+        let bundle = test_location.join("root/Applications/Syncthing.app/Contents");
+        let root = std::env::temp_dir().join(format!("loginitems_inspect_{}", std::process::id()));
+        let contents = root.join("Applications/Syncthing.app/Contents");
+        std::fs::create_dir_all(&contents).unwrap();
+        std::fs::copy(bundle.join("Info.plist"), contents.join("Info.plist")).unwrap();
+        // Absolute link to an executable outside of the root directory
+        std::os::unix::fs::symlink(bundle.join("MacOS"), contents.join("MacOS")).unwrap();
+
+        let mut errors: Vec<LoginItemsError> = Vec::new();
+        inspect_target(&root, &mut loginitem, &mut errors);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(loginitem.bundle.is_some());
+        assert!(loginitem.executable.is_none());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_parse_launchd_jobs_system_agents() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root");
+        let root = std::env::temp_dir().join(format!("loginitems_launchd_{}", std::process::id()));
+        let job = test_location.join("Library/LaunchDaemons/com.docker.vmnetd.plist");
+        for directory in ["Library/LaunchAgents", "Library/LaunchDaemons"] {
+            std::fs::create_dir_all(root.join(directory)).unwrap();
+            std::fs::copy(&job, root.join(directory).join("com.docker.vmnetd.plist")).unwrap();
+        }
+        let launchd_db = "var/db/com.apple.xpc.launchd";
+        std::fs::create_dir_all(root.join(launchd_db)).unwrap();
+        std::fs::copy(
+            test_location.join(launchd_db).join("disabled.plist"),
+            root.join(launchd_db).join("disabled.plist"),
+        )
+        .unwrap();
+
+        let mut report = LoginItemsReport {
+            results: Vec::new(),
+            failures: Vec::new(),
+        };
+        parse_launchd_jobs(&root, &mut report);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        // disabled.plist has the label, but only the daemon is in the system domain
+        let agent = report
+            .results
+            .iter()
+            .find(|results| results.path.ends_with("Library/LaunchAgents"))
+            .unwrap();
+        assert!(agent.results[0].disabled.is_none());
+        let daemon = report
+            .results
+            .iter()
+            .find(|results| results.path.ends_with("Library/LaunchDaemons"))
+            .unwrap();
+        assert!(daemon.results[0].disabled == Some(true));
+    }
+
+    #[test]
+    fn test_parse_loginitems_root() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root");
+        let report = parse_loginitems_root(&test_location).unwrap();
+        let source = |suffix: &str| {
+            report
+                .results
+                .iter()
+                .find(|results| results.path.ends_with(suffix))
+                .unwrap()
+        };
+
+        // Ventura BTM, per-user backgrounditems.btm, bundled app loginitems, app helpers and launch jobs
+        assert!(report.results.len() == 7);
+        assert!(report
+            .results
+            .windows(2)
+            .all(|pair| pair[0].path <= pair[1].path));
+        assert!(source("BackgroundItems-v4.btm").results.len() == 3);
+        let background_items = source("backgrounditems.btm");
+        assert!(background_items.owner == "sam");
+        assert!(background_items.results.len() == 1);
+        let target = background_items.results[0].target.as_ref().unwrap();
+        assert!(target.exists);
+        assert!(target
+            .resolved_path
+            .ends_with("root/Applications/Syncthing.app"));
+        let bundle = background_items.results[0].bundle.as_ref().unwrap();
+        assert!(bundle.bundle_id == "com.github.xor-gate.syncthing-macosx");
+        assert!(bundle.ui_element);
+        let executable = background_items.results[0].executable.as_ref().unwrap();
+        assert!(executable.architectures() == ["x86_64", "arm64"]);
+        assert!(executable.signing_status() == SigningStatus::Signed);
+        let registered = source("loginitems.501.plist");
+        assert!(registered.results.len() == 2);
+        let docker = registered
+            .results
+            .iter()
+            .find(|loginitem| loginitem.app_binary == "com.docker.helper")
+            .unwrap();
+        assert!(docker.is_bundled);
+        assert!(docker.host_app == "/Users/alex/Applications/Docker.app");
+
+        // Helpers found in app bundles, only the Docker helper is registered with launchd
+        let syncthing_helper = &source("root/Applications").results[0];
+        assert!(syncthing_helper.app_id == "com.github.xor-gate.syncthing-macosx");
+        assert!(syncthing_helper.app_binary == "com.github.xor-gate.syncthing-macosx.helper");
+        assert!(syncthing_helper.registered_in.is_empty());
+        assert!(syncthing_helper.target.as_ref().unwrap().exists);
+        let user_helpers = source("alex/Applications");
+        assert!(user_helpers.owner == "alex");
+        let docker_helper = &user_helpers.results[0];
+        assert!(docker_helper.app_id == "com.docker.docker");
+        assert!(docker_helper.host_app == "/Users/alex/Applications/Docker.app");
+        assert!(docker_helper.registered_in == registered.path);
+        assert!(docker_helper.disabled == Some(false));
+        assert!(syncthing_helper.disabled.is_none());
+
+        // LaunchDaemons and per-user LaunchAgents
+        let daemons = source("root/Library/LaunchDaemons");
+        let daemon = daemons.results[0].launchd.as_ref().unwrap();
+        assert!(daemon.label == "com.docker.vmnetd");
+        assert!(!daemons.results[0].target.as_ref().unwrap().exists);
+        // Disabled in the system disabled.plist
+        assert!(daemons.results[0].disabled == Some(true));
+        let agents = source("alex/Library/LaunchAgents");
+        assert!(agents.owner == "alex");
+        assert!(agents.results[0].path == ["Users", "alex", ".local", "updater"]);
+        // The job PLIST has Disabled false, disabled.501.plist overrides it
+        assert!(!agents.results[0]
+            .launchd
+            .as_ref()
+            .unwrap()
+            .disabled
+            .unwrap());
+        assert!(agents.results[0].disabled == Some(true));
+
+        // Corrupt backgrounditems.btm does not stop the scan
+        assert!(report.failures.len() == 1);
+        assert!(report.failures[0].path.contains("/Users/alex/"));
+        assert!(matches!(
+            report.failures[0].error,
+            LoginItemsError::PlistDecode { .. }
+        ));
+    }
 }
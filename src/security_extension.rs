@@ -0,0 +1,102 @@
+//! Parse macOS Bookmark security extensions
+//!
+//! Provides a library to parse the sandbox extension token stored in the bookmark security extension record.
+//! Tokens are `;` separated fields ending with the path and a NUL byte:
+//! `hmac;unknown;flags;unknown;class;unknown;device;inode;path`
+
+use log::warn;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SecurityExtension {
+    pub hmac: String,            // Token HMAC (hex)
+    pub unknown1: u64,           // Unknown (hex)
+    pub flags: u64,              // Extension flags (hex)
+    pub unknown2: u64,           // Unknown (hex)
+    pub extension_class: String, // Extension class (ex: com.apple.app-sandbox.read-write)
+    pub unknown3: u64,           // Unknown (hex)
+    pub device: u64,             // Volume device number (hex)
+    pub inode: u64,              // Target inode (hex), should match the last CNID
+    pub path: String,            // Target path, lower-cased by macOS
+}
+
+impl SecurityExtension {
+    /// Parse the security extension token. Returns None if the token does not have the expected fields
+    pub fn parse(token: &str) -> Option<SecurityExtension> {
+        let token = token.trim_end_matches('\0');
+        // Path is last and may contain the separator
+        let fields: Vec<&str> = token.splitn(9, ';').collect();
+        let expected_fields = 9;
+        if fields.len() != expected_fields {
+            warn!(
+                "Security extension has {} fields, expected {}",
+                fields.len(),
+                expected_fields
+            );
+            return None;
+        }
+
+        let hex = |value: &str| match u64::from_str_radix(value, 16) {
+            Ok(number) => Some(number),
+            Err(err) => {
+                warn!(
+                    "Failed to parse security extension field {}: {:?}",
+                    value, err
+                );
+                None
+            }
+        };
+        let extension = SecurityExtension {
+            hmac: fields[0].to_string(),
+            unknown1: hex(fields[1])?,
+            flags: hex(fields[2])?,
+            unknown2: hex(fields[3])?,
+            extension_class: fields[4].to_string(),
+            unknown3: hex(fields[5])?,
+            device: hex(fields[6])?,
+            inode: hex(fields[7])?,
+            path: fields[8].to_lowercase(),
+        };
+        Some(extension)
+    }
+
+    /// Check if the extension grants write access
+    pub fn is_read_write(&self) -> bool {
+        self.extension_class.ends_with(".read-write")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SecurityExtension;
+
+    #[test]
+    fn test_parse() {
+        let token = "64cb7eaa9a1bbccc4e1397c9f2a411ebe539cd29;00000000;00000000;0000000000000020;com.apple.app-sandbox.read-write;01;01000004;00000000000ac62a;/applications/syncthing.app\u{0}";
+        let extension = SecurityExtension::parse(token).unwrap();
+
+        assert!(extension.hmac == "64cb7eaa9a1bbccc4e1397c9f2a411ebe539cd29");
+        assert!(extension.flags == 0);
+        assert!(extension.unknown2 == 0x20);
+        assert!(extension.extension_class == "com.apple.app-sandbox.read-write");
+        assert!(extension.unknown3 == 1);
+        assert!(extension.device == 0x1000004);
+        assert!(extension.inode == 706090);
+        assert!(extension.path == "/applications/syncthing.app");
+        assert!(extension.is_read_write());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(SecurityExtension::parse("").is_none());
+        assert!(SecurityExtension::parse("abc;00;00").is_none());
+
+        let token = "abc;00000000;00000000;0000000000000020;com.apple.app-sandbox.read-only;01;01000004;not-hex;/tmp";
+        assert!(SecurityExtension::parse(token).is_none());
+
+        let token = "abc;00000000;00000000;0000000000000020;com.apple.app-sandbox.read-only;01;01000004;2a;/Tmp/a;b";
+        let extension = SecurityExtension::parse(token).unwrap();
+        assert!(extension.path == "/tmp/a;b");
+        assert!(!extension.is_read_write());
+    }
+}
@@ -0,0 +1,423 @@
+//! Map LoginItems results to SIEM event schemas
+//!
+//! Provides Elastic Common Schema (ECS) and Open Cybersecurity Schema Framework (OCSF) events for login items.
+//! Fields without a standard mapping are kept under a `loginitems` vendor namespace.
+
+use std::io::Write;
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    loginitems::{LoginItemsData, LoginItemsResults, SourceType},
+    output::RecordContext,
+    timestamp::{CocoaTimestamp, TimestampStatus},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventSchema {
+    /// Elastic Common Schema 8.11
+    Ecs,
+    /// Open Cybersecurity Schema Framework 1.1 File Query events
+    Ocsf,
+}
+
+const ECS_VERSION: &str = "8.11.0";
+const OCSF_VERSION: &str = "1.1.0";
+// OCSF Discovery category, File Query class and Query activity
+const OCSF_CATEGORY_UID: u32 = 5;
+const OCSF_CLASS_UID: u32 = 5007;
+const OCSF_ACTIVITY_ID: u32 = 1;
+
+/// Get the MITRE ATT&CK persistence technique ID and name for a source type
+pub fn persistence_technique(source_type: SourceType) -> (&'static str, &'static str) {
+    match source_type {
+        SourceType::LaunchAgent => ("T1543.001", "Launch Agent"),
+        SourceType::LaunchDaemon => ("T1543.004", "Launch Daemon"),
+        SourceType::BackgroundItems
+        | SourceType::BackgroundTaskManagement
+        | SourceType::LaunchdLoginItems
+        | SourceType::AppHelper => ("T1547.015", "Login Items"),
+    }
+}
+
+/// Write every login item as a JSON event per line
+pub fn write_events<W: Write>(
+    mut writer: W,
+    results: &[LoginItemsResults],
+    context: &RecordContext,
+    schema: EventSchema,
+) -> std::io::Result<()> {
+    for result in results {
+        for loginitem in &result.results {
+            let event = match schema {
+                EventSchema::Ecs => ecs_event(loginitem, &result.path, &result.owner, context),
+                EventSchema::Ocsf => ocsf_event(loginitem, &result.path, &result.owner, context),
+            };
+            serde_json::to_writer(&mut writer, &event)?;
+            writeln!(writer)?;
+        }
+    }
+    writer.flush()
+}
+
+/// Map a login item to an ECS event
+pub fn ecs_event(
+    loginitem: &LoginItemsData,
+    source: &str,
+    owner: &str,
+    context: &RecordContext,
+) -> Value {
+    let source_type = loginitem.source_type();
+    let (technique_id, technique_name) = persistence_technique(source_type);
+    let signature = loginitem
+        .executable
+        .as_ref()
+        .and_then(|executable| executable.code_signature());
+
+    let event = json!({
+        "@timestamp": context.parse_time.rfc3339(),
+        "ecs": { "version": ECS_VERSION },
+        "event": {
+            "kind": "event",
+            "category": ["configuration"],
+            "type": ["info"],
+            "module": "loginitems",
+            "dataset": format!("loginitems.{}", source_type),
+            "created": context.parse_time.rfc3339(),
+        },
+        "host": { "name": context.host },
+        "log": { "file": { "path": source } },
+        "file": {
+            "path": file_path(loginitem),
+            "name": loginitem.path.last(),
+            "inode": inode(loginitem).map(|inode| inode.to_string()),
+            "created": loginitem.creation.rfc3339(),
+            "size": loginitem.target.as_ref().map(|target| target.size),
+            "hash": { "sha256": loginitem.target.as_ref().map(|target| &target.sha256) },
+            "code_signature": {
+                "exists": loginitem.executable.as_ref().map(|_| signature.is_some()),
+                "status": loginitem.executable.as_ref().map(|executable| executable.signing_status().to_string()),
+                "signing_id": signature.map(|signature| &signature.identifier),
+                "team_id": signature.map(|signature| &signature.team_id),
+            },
+        },
+        "user": {
+            "name": loginitem.username,
+            "id": user_id(loginitem),
+        },
+        "process": {
+            "executable": process_executable(loginitem),
+            "args": loginitem.launchd.as_ref().map(|job| &job.program_arguments),
+        },
+        "threat": {
+            "framework": "MITRE ATT&CK",
+            "tactic": { "id": ["TA0003"], "name": ["Persistence"] },
+            "technique": { "id": [technique_id], "name": [technique_name] },
+        },
+        "loginitems": vendor_fields(loginitem, source_type, owner),
+    });
+    prune(event).unwrap_or_default()
+}
+
+/// Map a login item to an OCSF File Query event
+pub fn ocsf_event(
+    loginitem: &LoginItemsData,
+    source: &str,
+    owner: &str,
+    context: &RecordContext,
+) -> Value {
+    let source_type = loginitem.source_type();
+    let (technique_id, technique_name) = persistence_technique(source_type);
+    let signature = loginitem
+        .executable
+        .as_ref()
+        .and_then(|executable| executable.code_signature());
+
+    let event = json!({
+        "category_uid": OCSF_CATEGORY_UID,
+        "category_name": "Discovery",
+        "class_uid": OCSF_CLASS_UID,
+        "class_name": "File Query",
+        "activity_id": OCSF_ACTIVITY_ID,
+        "activity_name": "Query",
+        "type_uid": OCSF_CLASS_UID * 100 + OCSF_ACTIVITY_ID,
+        "type_name": "File Query: Query",
+        "severity_id": 1,
+        "severity": "Informational",
+        "time": epoch_millis(context),
+        "metadata": {
+            "version": OCSF_VERSION,
+            "log_name": source,
+            "product": {
+                "name": "macos-loginitems",
+                "vendor_name": "macos-loginitems",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+        },
+        "device": { "hostname": context.host, "os": { "name": "macOS", "type": "macOS", "type_id": 300 } },
+        "file": {
+            "path": file_path(loginitem),
+            "name": loginitem.path.last(),
+            "uid": inode(loginitem).map(|inode| inode.to_string()),
+            "created_time_dt": loginitem.creation.rfc3339(),
+            "size": loginitem.target.as_ref().map(|target| target.size),
+            "hashes": loginitem.target.as_ref().filter(|target| !target.sha256.is_empty()).map(|target| {
+                json!([{ "algorithm_id": 3, "algorithm": "SHA-256", "value": target.sha256 }])
+            }),
+            "signature": signature.map(|signature| json!({
+                "algorithm_id": 99,
+                "algorithm": "Apple Code Signature",
+                "certificate": { "subject": signature.identifier },
+                "developer_uid": signature.team_id,
+            })),
+        },
+        "actor": {
+            "user": {
+                "name": loginitem.username,
+                "uid": user_id(loginitem),
+            },
+            "process": {
+                "file": { "path": process_executable(loginitem) },
+                "cmd_line": loginitem.launchd.as_ref().map(|job| job.program_arguments.join(" ")),
+            },
+        },
+        "attacks": [{
+            "version": "v14",
+            "tactic": { "uid": "TA0003", "name": "Persistence" },
+            "technique": { "uid": technique_id, "name": technique_name },
+        }],
+        "unmapped": { "loginitems": vendor_fields(loginitem, source_type, owner) },
+    });
+    prune(event).unwrap_or_default()
+}
+
+/// Fields without a standard mapping
+fn vendor_fields(loginitem: &LoginItemsData, source_type: SourceType, owner: &str) -> Value {
+    json!({
+        "source_type": source_type.to_string(),
+        "source_owner": owner,
+        "cnid_path": loginitem.cnid_path,
+        "creation": timestamp_fields(&loginitem.creation),
+        "localized_name": loginitem.localized_name,
+        "app_id": loginitem.app_id,
+        "is_bundled": loginitem.is_bundled,
+        "host_app": loginitem.host_app,
+        "registered_in": loginitem.registered_in,
+        "disabled": loginitem.disabled,
+        "volume": {
+            "path": loginitem.volume_path,
+            "name": loginitem.volume_name,
+            "uuid": loginitem.volume_uuid,
+            "created": loginitem.volume_creation.rfc3339(),
+            "creation": timestamp_fields(&loginitem.volume_creation),
+        },
+        "target_flags": loginitem.target_flag_names,
+        "launchd": loginitem.launchd.as_ref().map(|job| json!({
+            "type": job.job_type.to_string(),
+            "label": job.label,
+            "run_at_load": job.run_at_load,
+            "keep_alive": job.keep_alive,
+            "start_interval": job.start_interval,
+            "watch_paths": job.watch_paths,
+            "user_name": job.user_name,
+        })),
+        "btm": loginitem.btm.as_ref().map(|btm| json!({
+            "identifier": btm.identifier,
+        })),
+        "bundle_id": loginitem.bundle.as_ref().map(|bundle| &bundle.bundle_id),
+        "architectures": loginitem.executable.as_ref().map(|executable| executable.architectures()),
+        "anomalies": loginitem.anomalies.iter().map(|anomaly| anomaly.to_string()).collect::<Vec<String>>(),
+    })
+}
+
+/// Raw value and status of a timestamp. Unset timestamps are left out
+fn timestamp_fields(timestamp: &CocoaTimestamp) -> Option<&CocoaTimestamp> {
+    if timestamp.status() == TimestampStatus::Unset {
+        return None;
+    }
+    Some(timestamp)
+}
+
+/// Absolute path of the login item target
+fn file_path(loginitem: &LoginItemsData) -> Option<String> {
+    if loginitem.path.is_empty() {
+        return None;
+    }
+    Some(format!("/{}", loginitem.path.join("/")))
+}
+
+/// Last CNID of the login item, the inode of the target
+fn inode(loginitem: &LoginItemsData) -> Option<i64> {
+    loginitem.cnid_path.last().copied()
+}
+
+/// UID as a string. Launch jobs and helpers do not record a UID
+fn user_id(loginitem: &LoginItemsData) -> Option<String> {
+    if loginitem.username.is_empty() && loginitem.uid == 0 {
+        return None;
+    }
+    Some(loginitem.uid.to_string())
+}
+
+/// Executable the login item runs: the bundled app binary, or the launch job executable
+fn process_executable(loginitem: &LoginItemsData) -> Option<String> {
+    if !loginitem.app_binary.is_empty() {
+        return Some(loginitem.app_binary.to_string());
+    }
+    loginitem
+        .launchd
+        .as_ref()
+        .map(|job| job.executable().to_string())
+}
+
+/// OCSF timestamps are milliseconds since 1970-01-01 UTC
+fn epoch_millis(context: &RecordContext) -> Option<i64> {
+    context
+        .parse_time
+        .unix()
+        .map(|unix| (unix * 1000.0).round() as i64)
+}
+
+/// Remove null values, empty strings and empty objects or arrays so events only contain fields that are set
+fn prune(value: Value) -> Option<Value> {
+    match value {
+        Value::Null => None,
+        Value::String(text) if text.is_empty() => None,
+        Value::Array(values) => {
+            let values: Vec<Value> = values.into_iter().filter_map(prune).collect();
+            if values.is_empty() {
+                return None;
+            }
+            Some(Value::Array(values))
+        }
+        Value::Object(fields) => {
+            let fields: Map<String, Value> = fields
+                .into_iter()
+                .filter_map(|(key, value)| prune(value).map(|value| (key, value)))
+                .collect();
+            if fields.is_empty() {
+                return None;
+            }
+            Some(Value::Object(fields))
+        }
+        value => Some(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::read_to_string, path::PathBuf};
+
+    use serde_json::Value;
+
+    use super::{write_events, EventSchema};
+    use crate::{
+        bookmark::{Bookmark, BookmarkRecord, BookmarkValue},
+        launchd::{parse_launchd_plist, LaunchdJobType},
+        loginitems::{LoginItemsData, LoginItemsResults},
+        output::RecordContext,
+        parser::{parse_loginitems_path, parse_loginitems_root},
+        timestamp::CocoaTimestamp,
+    };
+
+    fn golden_results() -> Vec<LoginItemsResults> {
+        let test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/test_data");
+        let mut sierra = parse_loginitems_path(
+            &test_location
+                .join("backgrounditems_sierra.btm")
+                .display()
+                .to_string(),
+        )
+        .unwrap();
+        // Keep the golden files independent of the checkout location
+        sierra.path = "tests/test_data/backgrounditems_sierra.btm".to_string();
+        sierra.owner = "sam".to_string();
+        // The fixture does not record its creator
+        let mut bookmark = sierra.results[0].bookmark.clone().unwrap();
+        let creator = [
+            (
+                Bookmark::CREATOR_USERNAME,
+                BookmarkValue::String("sam".to_string()),
+            ),
+            (
+                Bookmark::CREATOR_UID,
+                BookmarkValue::Number {
+                    value: 501,
+                    data_type: Bookmark::NUMBER_FOUR_BYTE,
+                },
+            ),
+        ];
+        for (record_type, value) in creator {
+            bookmark.records.push(BookmarkRecord {
+                record_type,
+                level: 1,
+                data_offset: 0,
+                value,
+            });
+        }
+        sierra.results[0] = LoginItemsData::from_bookmark(bookmark);
+
+        let mut report = parse_loginitems_root(&test_location.join("root")).unwrap();
+        let helpers_index = report
+            .results
+            .iter()
+            .position(|results| results.path.ends_with("loginitems.501.plist"))
+            .unwrap();
+        let mut helpers = report.results.remove(helpers_index);
+        helpers.path = "/var/db/com.apple.xpc.launchd/loginitems.501.plist".to_string();
+
+        let agent_path = "root/Users/alex/Library/LaunchAgents/com.apple.updates.plist";
+        let job = parse_launchd_plist(
+            &test_location.join(agent_path).display().to_string(),
+            LaunchdJobType::Agent,
+        )
+        .unwrap();
+        let agent = LoginItemsResults {
+            results: vec![LoginItemsData::from_launchd_job(job)],
+            path: "/Users/alex/Library/LaunchAgents/com.apple.updates.plist".to_string(),
+            owner: "alex".to_string(),
+            errors: Vec::new(),
+        };
+        vec![sierra, helpers, agent]
+    }
+
+    fn golden_test(schema: EventSchema, golden: &str) {
+        let context = RecordContext {
+            host: "Sams-MacBook-Pro".to_string(),
+            parse_time: CocoaTimestamp::new(700000000.0),
+        };
+        let mut output: Vec<u8> = Vec::new();
+        write_events(&mut output, &golden_results(), &context, schema).unwrap();
+
+        let mut golden_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        golden_location.push("tests/test_data/golden");
+        golden_location.push(golden);
+        let mut expected: Vec<Value> = read_to_string(golden_location)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        // Golden files are not regenerated on every release
+        for event in &mut expected {
+            if let Some(version) = event.pointer_mut("/metadata/product/version") {
+                *version = Value::from(env!("CARGO_PKG_VERSION"));
+            }
+        }
+        let events: Vec<Value> = std::str::from_utf8(&output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(events == expected);
+    }
+
+    #[test]
+    fn test_ecs_golden() {
+        golden_test(EventSchema::Ecs, "loginitems.ecs.ndjson");
+    }
+
+    #[test]
+    fn test_ocsf_golden() {
+        golden_test(EventSchema::Ocsf, "loginitems.ocsf.ndjson");
+    }
+}
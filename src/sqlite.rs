@@ -0,0 +1,302 @@
+//! Export LoginItems results to SQLite
+//!
+//! Provides a normalized SQLite schema for loading results from large investigations. Every table is keyed by the
+//! source path and the index of the login item in that source. SQLite is bundled, nothing is needed at runtime.
+
+use std::path::Path;
+
+use rusqlite::{params, Connection, Result};
+
+use crate::{
+    bookmark::Bookmark,
+    loginitems::{LoginItemsData, LoginItemsResults},
+};
+
+const SCHEMA: &str = "
+CREATE TABLE sources (
+    source_path TEXT PRIMARY KEY,
+    owner TEXT NOT NULL,
+    item_count INTEGER NOT NULL,
+    error_count INTEGER NOT NULL
+);
+CREATE TABLE items (
+    source_path TEXT NOT NULL REFERENCES sources (source_path),
+    item_index INTEGER NOT NULL,
+    source_type TEXT NOT NULL,
+    path TEXT NOT NULL,
+    creation TEXT,
+    localized_name TEXT NOT NULL,
+    username TEXT NOT NULL,
+    uid INTEGER NOT NULL,
+    volume_path TEXT NOT NULL,
+    volume_name TEXT NOT NULL,
+    volume_uuid TEXT NOT NULL,
+    volume_creation TEXT,
+    security_extension TEXT NOT NULL,
+    is_bundled INTEGER NOT NULL,
+    app_id TEXT NOT NULL,
+    app_binary TEXT NOT NULL,
+    host_app TEXT NOT NULL,
+    registered_in TEXT NOT NULL,
+    disabled INTEGER,
+    launch_label TEXT,
+    launch_program TEXT,
+    run_at_load INTEGER,
+    keep_alive INTEGER,
+    anomalies TEXT NOT NULL,
+    target_exists INTEGER,
+    target_sha256 TEXT,
+    bundle_id TEXT,
+    signing_status TEXT,
+    signing_identifier TEXT,
+    team_id TEXT,
+    PRIMARY KEY (source_path, item_index)
+);
+CREATE TABLE path_components (
+    source_path TEXT NOT NULL,
+    item_index INTEGER NOT NULL,
+    component_index INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    cnid INTEGER,
+    PRIMARY KEY (source_path, item_index, component_index),
+    FOREIGN KEY (source_path, item_index) REFERENCES items (source_path, item_index)
+);
+CREATE TABLE flags (
+    source_path TEXT NOT NULL,
+    item_index INTEGER NOT NULL,
+    flag_type TEXT NOT NULL,
+    name TEXT NOT NULL,
+    FOREIGN KEY (source_path, item_index) REFERENCES items (source_path, item_index)
+);
+CREATE TABLE toc_records (
+    source_path TEXT NOT NULL,
+    item_index INTEGER NOT NULL,
+    record_index INTEGER NOT NULL,
+    record_type INTEGER NOT NULL,
+    record_name TEXT NOT NULL,
+    level INTEGER NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (source_path, item_index, record_index),
+    FOREIGN KEY (source_path, item_index) REFERENCES items (source_path, item_index)
+);
+
+CREATE VIEW items_outside_applications AS
+    SELECT * FROM items
+    WHERE path != '' AND path NOT LIKE '/Applications/%' AND path NOT LIKE '/System/Applications/%';
+CREATE VIEW unsigned_items AS
+    SELECT * FROM items WHERE signing_status IN ('Unsigned', 'AdHoc', 'Malformed');
+CREATE VIEW missing_targets AS
+    SELECT * FROM items WHERE target_exists = 0;
+CREATE VIEW items_with_anomalies AS
+    SELECT * FROM items WHERE anomalies != '';
+";
+
+/// Write results to a new SQLite database. Fails if the database already has the tables
+pub fn write_sqlite(path: &Path, results: &[LoginItemsResults]) -> Result<()> {
+    let mut connection = Connection::open(path)?;
+    export_results(&mut connection, results)
+}
+
+/// Create the schema and insert every result in a single transaction
+pub fn export_results(connection: &mut Connection, results: &[LoginItemsResults]) -> Result<()> {
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(SCHEMA)?;
+    for result in results {
+        transaction.execute(
+            "INSERT INTO sources VALUES (?1, ?2, ?3, ?4)",
+            params![
+                result.path,
+                result.owner,
+                result.results.len(),
+                result.errors.len()
+            ],
+        )?;
+        for (index, loginitem) in result.results.iter().enumerate() {
+            insert_item(&transaction, &result.path, index, loginitem)?;
+        }
+    }
+    transaction.commit()
+}
+
+/// Insert a login item and its path components, flags and TOC records
+fn insert_item(
+    connection: &Connection,
+    source: &str,
+    index: usize,
+    loginitem: &LoginItemsData,
+) -> Result<()> {
+    let signature = loginitem
+        .executable
+        .as_ref()
+        .and_then(|executable| executable.code_signature());
+    let anomalies: Vec<String> = loginitem
+        .anomalies
+        .iter()
+        .map(|anomaly| anomaly.to_string())
+        .collect();
+    let path = if loginitem.path.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", loginitem.path.join("/"))
+    };
+
+    connection.execute(
+        "INSERT INTO items VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15,
+            ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30)",
+        params![
+            source,
+            index,
+            loginitem.source_type().to_string(),
+            path,
+            loginitem.creation.rfc3339(),
+            loginitem.localized_name,
+            loginitem.username,
+            loginitem.uid,
+            loginitem.volume_path,
+            loginitem.volume_name,
+            loginitem.volume_uuid,
+            loginitem.volume_creation.rfc3339(),
+            loginitem.security_extension,
+            loginitem.is_bundled,
+            loginitem.app_id,
+            loginitem.app_binary,
+            loginitem.host_app,
+            loginitem.registered_in,
+            loginitem.disabled,
+            loginitem.launchd.as_ref().map(|job| job.label.to_string()),
+            loginitem
+                .launchd
+                .as_ref()
+                .map(|job| job.executable().to_string()),
+            loginitem.launchd.as_ref().map(|job| job.run_at_load),
+            loginitem.launchd.as_ref().map(|job| job.keep_alive),
+            anomalies.join("; "),
+            loginitem.target.as_ref().map(|target| target.exists),
+            loginitem
+                .target
+                .as_ref()
+                .map(|target| target.sha256.to_string()),
+            loginitem
+                .bundle
+                .as_ref()
+                .map(|bundle| bundle.bundle_id.to_string()),
+            loginitem
+                .executable
+                .as_ref()
+                .map(|executable| executable.signing_status().to_string()),
+            signature.map(|signature| signature.identifier.to_string()),
+            signature.map(|signature| signature.team_id.to_string()),
+        ],
+    )?;
+
+    for (component_index, name) in loginitem.path.iter().enumerate() {
+        connection.execute(
+            "INSERT INTO path_components VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                source,
+                index,
+                component_index,
+                name,
+                loginitem.cnid_path.get(component_index)
+            ],
+        )?;
+    }
+
+    let flags = [
+        ("target", &loginitem.target_flag_names),
+        ("volume", &loginitem.volume_flag_names),
+    ];
+    for (flag_type, names) in flags {
+        for name in names {
+            connection.execute(
+                "INSERT INTO flags VALUES (?1, ?2, ?3, ?4)",
+                params![source, index, flag_type, name],
+            )?;
+        }
+    }
+
+    if let Some(bookmark) = &loginitem.bookmark {
+        for (record_index, record) in bookmark.records.iter().enumerate() {
+            let value = serde_json::to_string(&record.value)
+                .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+            connection.execute(
+                "INSERT INTO toc_records VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    source,
+                    index,
+                    record_index,
+                    record.record_type,
+                    Bookmark::record_name(record.record_type),
+                    record.level,
+                    value
+                ],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use rusqlite::Connection;
+
+    use super::export_results;
+    use crate::parser::{parse_loginitems_path, parse_loginitems_root};
+
+    #[test]
+    fn test_export_results() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/backgrounditems_sierra.btm");
+        let source = test_location.display().to_string();
+        let results = parse_loginitems_path(&source).unwrap();
+
+        let mut connection = Connection::open_in_memory().unwrap();
+        export_results(&mut connection, &[results]).unwrap();
+
+        let count =
+            |query: &str| -> i64 { connection.query_row(query, [], |row| row.get(0)).unwrap() };
+        assert!(count("SELECT COUNT(*) FROM sources") == 1);
+        assert!(count("SELECT COUNT(*) FROM items") == 1);
+        assert!(count("SELECT COUNT(*) FROM path_components") == 2);
+        assert!(count("SELECT COUNT(*) FROM flags WHERE flag_type = 'target'") > 0);
+        assert!(count("SELECT COUNT(*) FROM toc_records WHERE record_name = 'TARGET_PATH'") == 1);
+        assert!(count("SELECT COUNT(*) FROM items_outside_applications") == 0);
+
+        let (path, creation): (String, String) = connection
+            .query_row(
+                "SELECT path, creation FROM items WHERE source_path = ?1 AND item_index = 0",
+                [&source],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert!(path == "/Applications/Syncthing.app");
+        assert!(creation == "2022-02-02T05:53:09Z");
+    }
+
+    #[test]
+    fn test_export_results_root() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root");
+        let report = parse_loginitems_root(&test_location).unwrap();
+
+        let mut connection = Connection::open_in_memory().unwrap();
+        export_results(&mut connection, &report.results).unwrap();
+
+        let outside: Vec<String> = connection
+            .prepare("SELECT path FROM items_outside_applications")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert!(outside.contains(&"/Users/alex/.local/updater".to_string()));
+        assert!(outside
+            .iter()
+            .all(|path| !path.starts_with("/Applications/")));
+
+        // Exporting twice into the same database fails instead of mixing results
+        assert!(export_results(&mut connection, &report.results).is_err());
+    }
+}
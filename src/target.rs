@@ -0,0 +1,274 @@
+//! Resolve LoginItems targets
+//!
+//! Provides a library to check what a login item bookmark points to on a (mounted) filesystem now:
+//! existence, inode, size, modified time and SHA-256.
+
+use std::{
+    fs::{read_link, symlink_metadata, File, Metadata},
+    io::{self, BufReader},
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use log::warn;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{loginitems::LoginItemsData, timestamp::CocoaTimestamp};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetStatus {
+    pub resolved_path: String,       // Target path under the root directory
+    pub exists: bool,                // Target exists
+    pub is_directory: bool,          // Target is a directory (ex: app bundle)
+    pub symlink_target: String,      // Link target if the target or a parent is a symlink
+    pub inode: u64,                  // Target inode (0 if not available)
+    pub inode_matches: Option<bool>, // Inode matches the last CNID (None if not available)
+    pub size: u64,                   // Target size
+    pub modified: CocoaTimestamp,    // Target modified time
+    pub sha256: String,              // SHA-256 of the target (files only)
+}
+
+/// Resolve the loginitem target path relative to a root directory. Returns None if the loginitem has no path
+pub fn resolve_target(root: &Path, loginitem: &LoginItemsData) -> Option<TargetStatus> {
+    if loginitem.path.is_empty() {
+        return None;
+    }
+    let resolved_path = match target_path(root, &loginitem.path) {
+        Some(resolved_path) => resolved_path,
+        None => {
+            warn!(
+                "Bookmark path {:?} escapes the root directory",
+                loginitem.path
+            );
+            return None;
+        }
+    };
+
+    let mut status = TargetStatus {
+        resolved_path: resolved_path.display().to_string(),
+        exists: false,
+        is_directory: false,
+        symlink_target: String::new(),
+        inode: 0,
+        inode_matches: None,
+        size: 0,
+        modified: CocoaTimestamp::default(),
+        sha256: String::new(),
+    };
+
+    if let Some(parent) = resolved_path.parent() {
+        if let Some(link) = symlink_below_root(root, parent) {
+            warn!("Target parent {} is a symlink", link.display());
+            if let Ok(link_target) = read_link(&link) {
+                status.symlink_target = link_target.display().to_string();
+            }
+            return Some(status);
+        }
+    }
+    let metadata = match symlink_metadata(&resolved_path) {
+        Ok(metadata) => metadata,
+        Err(_) => return Some(status),
+    };
+    status.exists = true;
+    status.is_directory = metadata.is_dir();
+    status.size = metadata.len();
+    if let Ok(modified) = metadata.modified() {
+        if let Ok(duration) = modified.duration_since(UNIX_EPOCH) {
+            status.modified = CocoaTimestamp::from_unix(duration.as_secs_f64());
+        }
+    }
+
+    status.inode = inode(&metadata);
+    if let Some(cnid) = loginitem.cnid_path.last() {
+        if status.inode != 0 {
+            status.inode_matches = Some(status.inode == *cnid as u64);
+        }
+    }
+
+    if metadata.file_type().is_symlink() {
+        if let Ok(link) = read_link(&resolved_path) {
+            status.symlink_target = link.display().to_string();
+        }
+    } else if metadata.is_file() {
+        match sha256_file(&resolved_path) {
+            Ok(hash) => status.sha256 = hash,
+            Err(err) => warn!("Failed to hash {}: {:?}", resolved_path.display(), err),
+        }
+    }
+    Some(status)
+}
+
+/// Join the bookmark path components to the root. Components that leave the root are not allowed
+pub(crate) fn target_path(root: &Path, path: &[String]) -> Option<PathBuf> {
+    let mut target = root.to_path_buf();
+    for component in path {
+        let mut components = Path::new(component).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(name)), None) => target.push(name),
+            _ => return None,
+        }
+    }
+    Some(target)
+}
+
+/// Get the first symlink in a path below the root directory. The root directory itself may be a link.
+/// Links below the root are never followed: an absolute link in an offline image would resolve on the examiner's
+/// system instead of in the image. Paths outside of the root directory are returned as is
+pub(crate) fn symlink_below_root(root: &Path, path: &Path) -> Option<PathBuf> {
+    let relative_path = match path.strip_prefix(root) {
+        Ok(relative_path) => relative_path,
+        Err(_) => return Some(path.to_path_buf()),
+    };
+    let mut current = root.to_path_buf();
+    for component in relative_path.components() {
+        current.push(component);
+        if let Ok(metadata) = symlink_metadata(&current) {
+            if metadata.file_type().is_symlink() {
+                return Some(current);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(unix)]
+pub(crate) fn inode(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn inode(_metadata: &Metadata) -> u64 {
+    0
+}
+
+/// Get the UID and GID that own a file
+#[cfg(unix)]
+pub(crate) fn owner_ids(metadata: &Metadata) -> (u32, u32) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.uid(), metadata.gid())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn owner_ids(_metadata: &Metadata) -> (u32, u32) {
+    (0, 0)
+}
+
+/// Get the SHA-256 of a file as lower-case hex
+pub fn sha256_file(path: &Path) -> Result<String, io::Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = Sha256::new();
+    io::copy(&mut reader, &mut hasher)?;
+    let hash: Vec<String> = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    Ok(hash.concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{resolve_target, sha256_file, symlink_below_root, target_path};
+    use crate::loginitems::LoginItemsData;
+
+    fn sierra_loginitem() -> LoginItemsData {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/backgrounditems_sierra.btm");
+        let results = LoginItemsData::parse_loginitems(&test_location.display().to_string())
+            .unwrap()
+            .results;
+        results.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn test_resolve_target() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root");
+        let mut loginitem = sierra_loginitem();
+
+        let status = resolve_target(&test_location, &loginitem).unwrap();
+        assert!(status.exists);
+        assert!(status.is_directory);
+        assert!(status.sha256.is_empty());
+        assert!(status.modified.is_valid());
+        assert!(status.inode_matches == Some(false));
+
+        // Resolve to a file and pretend the bookmark recorded its inode
+        loginitem
+            .path
+            .extend(["Contents", "MacOS", "Syncthing"].map(String::from));
+        let status = resolve_target(&test_location, &loginitem).unwrap();
+        assert!(!status.is_directory);
+        assert!(status.size == 8611);
+        assert!(
+            status.sha256 == "017da3e02b2b5677f9b07ec082b73a1346fb944d2931a74c8d49291d0e9ea172"
+        );
+        loginitem.cnid_path.push(status.inode as i64);
+        let status = resolve_target(&test_location, &loginitem).unwrap();
+        assert!(status.inode_matches == Some(true));
+
+        loginitem.path = vec!["Applications".to_string(), "Missing.app".to_string()];
+        let status = resolve_target(&test_location, &loginitem).unwrap();
+        assert!(!status.exists);
+    }
+
+    #[test]
+    fn test_target_path() {
+        let root = Path::new("/mnt/image");
+        let path = ["Applications", "Syncthing.app"].map(String::from);
+        assert!(target_path(root, &path).unwrap() == root.join("Applications/Syncthing.app"));
+
+        let path = ["Applications", "..", "..", "etc"].map(String::from);
+        assert!(target_path(root, &path).is_none());
+        let path = ["/etc"].map(String::from);
+        assert!(target_path(root, &path).is_none());
+        let path = ["Applications/../../etc"].map(String::from);
+        assert!(target_path(root, &path).is_none());
+    }
+
+    #[test]
+    fn test_sha256_file() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root/missing");
+        assert!(sha256_file(&test_location).is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_resolve_target_symlink() {
+        let root = std::env::temp_dir().join(format!("loginitems_target_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        // Absolute link that would leave the root directory if followed
+        std::os::unix::fs::symlink("/", root.join("Applications")).unwrap();
+
+        let loginitem = sierra_loginitem();
+        let status = resolve_target(&root, &loginitem).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(!status.exists);
+        assert!(status.symlink_target == "/");
+        assert!(status.sha256.is_empty());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_symlink_below_root() {
+        let root = std::env::temp_dir().join(format!("loginitems_symlink_{}", std::process::id()));
+        let bundle = root.join("Applications/Syncthing.app");
+        std::fs::create_dir_all(&bundle).unwrap();
+        std::os::unix::fs::symlink("/", bundle.join("Contents")).unwrap();
+
+        let link = symlink_below_root(&root, &bundle.join("Contents/MacOS/Syncthing"));
+        let bundle_link = symlink_below_root(&root, &bundle);
+        let outside = symlink_below_root(&root, Path::new("/etc/hosts"));
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(link == Some(bundle.join("Contents")));
+        assert!(bundle_link.is_none());
+        assert!(outside == Some(PathBuf::from("/etc/hosts")));
+    }
+}
@@ -0,0 +1,359 @@
+//! Timeline LoginItems results
+//!
+//! Provides mactime bodyfile and L2T CSV output so login item timestamps can be merged into a super-timeline.
+//! One event is created for every valid timestamp: target creation, volume creation and the source file modified time.
+
+use std::{fmt, fs::metadata, io::Write, path::Path, time::UNIX_EPOCH};
+
+use log::warn;
+use serde::Serialize;
+
+use crate::{
+    loginitems::{LoginItemsData, LoginItemsResults},
+    target,
+    timestamp::CocoaTimestamp,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum TimestampType {
+    /// Bookmark creation timestamp of the target
+    TargetCreation,
+    /// Bookmark creation timestamp of the target volume
+    VolumeCreation,
+    /// Modified time of the LoginItems source file, launchd job PLIST or helper app bundle
+    SourceModified,
+}
+
+impl fmt::Display for TimestampType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimestampType::TargetCreation => write!(f, "Target Creation"),
+            TimestampType::VolumeCreation => write!(f, "Volume Creation"),
+            TimestampType::SourceModified => write!(f, "Source Modified"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimelineEvent<'a> {
+    pub timestamp: CocoaTimestamp,        // Event time
+    pub timestamp_type: TimestampType,    // What the timestamp records
+    pub name: String,                     // Login item path, or the source path
+    pub inode: u64,                       // Last CNID of the login item, or the source inode
+    pub uid: i32,                         // Login item UID, or the source owner UID
+    pub gid: u32,                         // Source owner GID (0 for login item events)
+    pub size: u64,                        // Target size (root scans only)
+    pub source: &'a str,                  // LoginItems file the event came from
+    pub owner: &'a str,                   // User that owns the LoginItems file
+    pub item: Option<&'a LoginItemsData>, // Login item (None for source events)
+}
+
+/// L2T CSV columns written by `write_l2t_csv`
+pub const L2T_CSV_HEADER: [&str; 17] = [
+    "date",
+    "time",
+    "timezone",
+    "MACB",
+    "source",
+    "sourcetype",
+    "type",
+    "user",
+    "host",
+    "short",
+    "desc",
+    "version",
+    "filename",
+    "inode",
+    "notes",
+    "format",
+    "extra",
+];
+
+/// Get an event for every valid timestamp in the results, sorted by time
+pub fn timeline_events(results: &[LoginItemsResults]) -> Vec<TimelineEvent<'_>> {
+    let mut events: Vec<TimelineEvent> = Vec::new();
+    for result in results {
+        if Path::new(&result.path).is_dir() {
+            // launchd and helper results come from a directory, every item has its own source
+            for loginitem in &result.results {
+                let item_source = match (&loginitem.launchd, &loginitem.target) {
+                    (Some(job), _) => &job.plist_path,
+                    (None, Some(target)) if loginitem.is_bundled => &target.resolved_path,
+                    _ => continue,
+                };
+                if let Some(event) = source_event(item_source, result) {
+                    events.push(event);
+                }
+            }
+        } else if let Some(event) = source_event(&result.path, result) {
+            events.push(event);
+        }
+
+        for loginitem in &result.results {
+            let timestamps = [
+                (TimestampType::TargetCreation, loginitem.creation),
+                (TimestampType::VolumeCreation, loginitem.volume_creation),
+            ];
+            for (timestamp_type, timestamp) in timestamps {
+                if !timestamp.is_valid() {
+                    continue;
+                }
+                events.push(TimelineEvent {
+                    timestamp,
+                    timestamp_type,
+                    name: format!("/{}", loginitem.path.join("/")),
+                    inode: match loginitem.cnid_path.last() {
+                        Some(cnid) => *cnid as u64,
+                        None => 0,
+                    },
+                    uid: loginitem.uid,
+                    gid: 0,
+                    size: match &loginitem.target {
+                        Some(target) => target.size,
+                        None => 0,
+                    },
+                    source: &result.path,
+                    owner: &result.owner,
+                    item: Some(loginitem),
+                });
+            }
+        }
+    }
+    events.sort_by(|first, second| first.timestamp.raw.total_cmp(&second.timestamp.raw));
+    events
+}
+
+/// Get the modified time event of a source file or directory
+fn source_event<'a>(path: &str, result: &'a LoginItemsResults) -> Option<TimelineEvent<'a>> {
+    let source_metadata = match metadata(path) {
+        Ok(source_metadata) => source_metadata,
+        Err(err) => {
+            warn!("Failed to get modified time of {}: {}", path, err);
+            return None;
+        }
+    };
+    let duration = source_metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?;
+    let (uid, gid) = target::owner_ids(&source_metadata);
+    let event = TimelineEvent {
+        timestamp: CocoaTimestamp::from_unix(duration.as_secs_f64()),
+        timestamp_type: TimestampType::SourceModified,
+        name: path.to_string(),
+        inode: target::inode(&source_metadata),
+        uid: uid as i32,
+        gid,
+        size: source_metadata.len(),
+        source: &result.path,
+        owner: &result.owner,
+        item: None,
+    };
+    Some(event)
+}
+
+/// Write events as mactime bodyfile lines (MD5|name|inode|mode|uid|gid|size|atime|mtime|ctime|crtime)
+pub fn write_bodyfile<W: Write>(
+    mut writer: W,
+    events: &[TimelineEvent<'_>],
+) -> std::io::Result<()> {
+    for event in events {
+        let seconds = event.timestamp.unix().unwrap_or_default().floor() as i64;
+        let (mtime, crtime) = match event.timestamp_type {
+            TimestampType::SourceModified => (seconds, 0),
+            TimestampType::TargetCreation | TimestampType::VolumeCreation => (0, seconds),
+        };
+        writeln!(
+            writer,
+            "0|{} (LoginItems {})|{}|0|{}|{}|{}|0|{}|0|{}",
+            event.name.replace('|', "_"),
+            event.timestamp_type,
+            event.inode,
+            event.uid,
+            event.gid,
+            event.size,
+            mtime,
+            crtime
+        )?;
+    }
+    writer.flush()
+}
+
+/// Write events as L2T CSV rows
+pub fn write_l2t_csv<W: Write>(
+    writer: W,
+    events: &[TimelineEvent<'_>],
+    host: &str,
+) -> std::io::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(L2T_CSV_HEADER)?;
+    for event in events {
+        writer.write_record(l2t_row(event, host))?;
+    }
+    writer.flush()
+}
+
+/// Get the L2T CSV columns for an event
+fn l2t_row(event: &TimelineEvent<'_>, host: &str) -> Vec<String> {
+    // rfc3339 is YYYY-MM-DDTHH:MM:SS[.ffffff]Z
+    let timestamp = event.timestamp.rfc3339().unwrap_or_default();
+    let date = match (
+        timestamp.get(0..4),
+        timestamp.get(5..7),
+        timestamp.get(8..10),
+    ) {
+        (Some(year), Some(month), Some(day)) => format!("{}/{}/{}", month, day, year),
+        _ => String::new(),
+    };
+    let time = timestamp.get(11..19).unwrap_or_default().to_string();
+
+    let (macb, event_type) = match event.timestamp_type {
+        TimestampType::TargetCreation => ("...B", "Creation Time"),
+        TimestampType::VolumeCreation => ("...B", "Volume Creation Time"),
+        TimestampType::SourceModified => ("M...", "Content Modification Time"),
+    };
+    let (user, source_type, desc) = match event.item {
+        Some(item) => (
+            item.username.to_string(),
+            item.source_type().to_string(),
+            format!(
+                "{} {} App ID: {} Volume: {}",
+                event.timestamp_type, event.name, item.app_id, item.volume_path
+            ),
+        ),
+        None => (
+            event.owner.to_string(),
+            String::new(),
+            format!("{} {}", event.timestamp_type, event.name),
+        ),
+    };
+
+    vec![
+        date,
+        time,
+        "UTC".to_string(),
+        macb.to_string(),
+        "LOG".to_string(),
+        "macOS LoginItems".to_string(),
+        event_type.to_string(),
+        user,
+        host.to_string(),
+        format!("{}: {}", event.timestamp_type, event.name),
+        desc,
+        "2".to_string(),
+        event.source.to_string(),
+        event.inode.to_string(),
+        String::new(),
+        "loginitems".to_string(),
+        format!("source_type: {}; owner: {}", source_type, event.owner),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{
+        timeline_events, write_bodyfile, write_l2t_csv, TimelineEvent, TimestampType,
+        L2T_CSV_HEADER,
+    };
+    use crate::{
+        parser::{parse_loginitems_path, parse_loginitems_root},
+        target,
+    };
+
+    #[test]
+    fn test_timeline_events() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/backgrounditems_sierra.btm");
+        let results = [parse_loginitems_path(&test_location.display().to_string()).unwrap()];
+        let events = timeline_events(&results);
+
+        let creation = events
+            .iter()
+            .find(|event| event.timestamp_type == TimestampType::TargetCreation)
+            .unwrap();
+        assert!(creation.name == "/Applications/Syncthing.app");
+        assert!(creation.inode == *results[0].results[0].cnid_path.last().unwrap() as u64);
+        assert!(events
+            .iter()
+            .any(|event| event.timestamp_type == TimestampType::SourceModified));
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].timestamp.raw <= pair[1].timestamp.raw));
+
+        let mut output: Vec<u8> = Vec::new();
+        write_bodyfile(&mut output, &events).unwrap();
+        let bodyfile = String::from_utf8(output).unwrap();
+        assert!(bodyfile.lines().count() == events.len());
+        let line = bodyfile
+            .lines()
+            .find(|line| line.contains("(LoginItems Target Creation)"))
+            .unwrap();
+        let fields: Vec<&str> = line.split('|').collect();
+        assert!(fields.len() == 11);
+        assert!(fields[2] == creation.inode.to_string());
+        assert!(fields[10] == "1643781189");
+
+        let mut output: Vec<u8> = Vec::new();
+        write_l2t_csv(&mut output, &events, "Sams-MacBook-Pro").unwrap();
+        let mut reader = csv::Reader::from_reader(output.as_slice());
+        assert!(reader.headers().unwrap().len() == L2T_CSV_HEADER.len());
+        let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        let row = rows.iter().find(|row| &row[6] == "Creation Time").unwrap();
+        assert!(&row[0] == "02/02/2022");
+        assert!(&row[1] == "05:53:09");
+        assert!(&row[3] == "...B");
+        assert!(&row[8] == "Sams-MacBook-Pro");
+        assert!(row[16].contains("BackgroundItems"));
+    }
+
+    #[test]
+    fn test_timeline_events_root() {
+        let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_location.push("tests/test_data/root");
+        let report = parse_loginitems_root(&test_location).unwrap();
+        let events = timeline_events(&report.results);
+
+        let sources: Vec<&TimelineEvent> = events
+            .iter()
+            .filter(|event| event.timestamp_type == TimestampType::SourceModified)
+            .collect();
+        // Directories are not sources, their job PLISTs and helper bundles are
+        assert!(sources
+            .iter()
+            .all(|event| !event.name.ends_with("LaunchDaemons")
+                && !event.name.ends_with("Applications")));
+        let daemon = sources
+            .iter()
+            .find(|event| {
+                event
+                    .name
+                    .ends_with("LaunchDaemons/com.docker.vmnetd.plist")
+            })
+            .unwrap();
+        assert!(daemon.source.ends_with("root/Library/LaunchDaemons"));
+        assert!(sources
+            .iter()
+            .any(|event| event.name.ends_with("LaunchAgents/com.apple.updates.plist")));
+        assert!(sources
+            .iter()
+            .any(|event| event.name.ends_with("LoginItems/DockerHelper.app")));
+
+        // Owner comes from the file, not the login item
+        let (uid, gid) = target::owner_ids(&std::fs::metadata(&daemon.name).unwrap());
+        assert!(daemon.uid == uid as i32);
+        assert!(daemon.gid == gid);
+        let mut output: Vec<u8> = Vec::new();
+        write_bodyfile(&mut output, &events).unwrap();
+        let bodyfile = String::from_utf8(output).unwrap();
+        let line = bodyfile
+            .lines()
+            .find(|line| line.contains("com.docker.vmnetd.plist (LoginItems Source Modified)"))
+            .unwrap();
+        let fields: Vec<&str> = line.split('|').collect();
+        assert!(fields[4] == uid.to_string());
+        assert!(fields[5] == gid.to_string());
+    }
+}
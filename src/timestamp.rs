@@ -0,0 +1,189 @@
+//! macOS Cocoa timestamps
+//!
+//! Provides a timestamp type for the seconds since 2001-01-01 UTC values stored in bookmarks.
+
+use std::fmt;
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CocoaTimestamp {
+    pub raw: f64, // Seconds since 2001-01-01 00:00:00 UTC
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TimestampStatus {
+    Valid,
+    Unset,      // Zero, the record was not in the bookmark
+    NotANumber, // NaN or infinite
+    Negative,   // Before 2001-01-01
+    FarFuture,  // After 2100-01-01
+}
+
+impl CocoaTimestamp {
+    // Seconds between 1970-01-01 and 2001-01-01
+    const UNIX_OFFSET: f64 = 978307200.0;
+    // 2100-01-01 00:00:00 UTC
+    const MAX_RAW: f64 = 3124137600.0;
+
+    pub fn new(raw: f64) -> CocoaTimestamp {
+        CocoaTimestamp { raw }
+    }
+
+    /// Get timestamp from seconds since 1970-01-01 UTC
+    pub fn from_unix(unix: f64) -> CocoaTimestamp {
+        CocoaTimestamp {
+            raw: unix - CocoaTimestamp::UNIX_OFFSET,
+        }
+    }
+
+    /// Check if the timestamp is a sensible date
+    pub fn status(&self) -> TimestampStatus {
+        if !self.raw.is_finite() {
+            TimestampStatus::NotANumber
+        } else if self.raw == 0.0 {
+            TimestampStatus::Unset
+        } else if self.raw < 0.0 {
+            TimestampStatus::Negative
+        } else if self.raw > CocoaTimestamp::MAX_RAW {
+            TimestampStatus::FarFuture
+        } else {
+            TimestampStatus::Valid
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.status() == TimestampStatus::Valid
+    }
+
+    /// Get seconds since 1970-01-01 UTC
+    pub fn unix(&self) -> Option<f64> {
+        if !self.is_valid() {
+            return None;
+        }
+        Some(self.raw + CocoaTimestamp::UNIX_OFFSET)
+    }
+
+    /// Get RFC 3339 UTC timestamp (ex: 2022-02-02T05:53:09Z). Fractional seconds are kept to the microsecond
+    pub fn rfc3339(&self) -> Option<String> {
+        let unix = self.unix()?;
+        let micros_per_second: i64 = 1000000;
+        let total_micros = (unix * micros_per_second as f64).round() as i64;
+        let seconds = total_micros.div_euclid(micros_per_second);
+        let micros = total_micros.rem_euclid(micros_per_second);
+
+        let seconds_per_day: i64 = 86400;
+        let (year, month, day) = civil_from_days(seconds.div_euclid(seconds_per_day));
+        let day_seconds = seconds.rem_euclid(seconds_per_day);
+        let mut timestamp = format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            day_seconds / 3600,
+            day_seconds % 3600 / 60,
+            day_seconds % 60
+        );
+        if micros != 0 {
+            timestamp.push_str(&format!(".{:06}", micros));
+        }
+        timestamp.push('Z');
+        Some(timestamp)
+    }
+}
+
+/// Convert days since 1970-01-01 to year, month and day
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Valid timestamps are displayed as RFC 3339, others as the reason and raw value
+impl fmt::Display for CocoaTimestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rfc3339() {
+            Some(timestamp) => write!(f, "{}", timestamp),
+            None if self.status() == TimestampStatus::Unset => Ok(()),
+            None => write!(f, "{:?} ({})", self.status(), self.raw),
+        }
+    }
+}
+
+/// Timestamps are serialized with the raw value, status and RFC 3339 string. The string is null if the timestamp is not valid
+impl Serialize for CocoaTimestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CocoaTimestamp", 3)?;
+        state.serialize_field("raw", &self.raw)?;
+        state.serialize_field("status", &self.status())?;
+        state.serialize_field("rfc3339", &self.rfc3339())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CocoaTimestamp, TimestampStatus};
+
+    #[test]
+    fn test_rfc3339() {
+        let timestamp = CocoaTimestamp::new(665473989.0);
+        assert!(timestamp.unix() == Some(1643781189.0));
+        assert!(CocoaTimestamp::from_unix(1643781189.0) == timestamp);
+        assert!(timestamp.rfc3339().unwrap() == "2022-02-02T05:53:09Z");
+        assert!(timestamp.to_string() == "2022-02-02T05:53:09Z");
+
+        let timestamp = CocoaTimestamp::new(241134516.5);
+        assert!(timestamp.rfc3339().unwrap() == "2008-08-22T21:48:36.500000Z");
+
+        // Leap day
+        let timestamp = CocoaTimestamp::new(730857600.0);
+        assert!(timestamp.rfc3339().unwrap() == "2024-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn test_status() {
+        assert!(CocoaTimestamp::new(0.0).status() == TimestampStatus::Unset);
+        assert!(CocoaTimestamp::new(f64::NAN).status() == TimestampStatus::NotANumber);
+        assert!(CocoaTimestamp::new(-5.0).status() == TimestampStatus::Negative);
+        assert!(CocoaTimestamp::new(1e12).status() == TimestampStatus::FarFuture);
+
+        let timestamp = CocoaTimestamp::new(-5.0);
+        assert!(timestamp.rfc3339().is_none());
+        assert!(timestamp.to_string() == "Negative (-5)");
+        assert!(CocoaTimestamp::new(0.0).to_string().is_empty());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let timestamp = CocoaTimestamp::new(665473989.0);
+        assert!(
+            serde_json::to_string(&timestamp).unwrap()
+                == r#"{"raw":665473989.0,"status":"Valid","rfc3339":"2022-02-02T05:53:09Z"}"#
+        );
+        let timestamp = CocoaTimestamp::new(-5.0);
+        assert!(
+            serde_json::to_string(&timestamp).unwrap()
+                == r#"{"raw":-5.0,"status":"Negative","rfc3339":null}"#
+        );
+        // JSON has no NaN, the status keeps the reason
+        let timestamp = CocoaTimestamp::new(f64::NAN);
+        assert!(
+            serde_json::to_string(&timestamp).unwrap()
+                == r#"{"raw":null,"status":"NotANumber","rfc3339":null}"#
+        );
+    }
+}
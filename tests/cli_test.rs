@@ -0,0 +1,183 @@
+use std::{path::PathBuf, process::Command};
+
+fn loginitems() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_loginitems"))
+}
+
+fn test_location(path: &str) -> PathBuf {
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push(path);
+    test_location
+}
+
+#[test]
+fn cli_parse_test() {
+    let output = loginitems()
+        .arg("parse")
+        .arg(test_location("tests/test_data/backgrounditems_sierra.btm"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(results["results"][0]["localized_name"] == "Syncthing");
+}
+
+#[test]
+fn cli_scan_root_test() {
+    let output_path =
+        std::env::temp_dir().join(format!("loginitems_cli_{}.csv", std::process::id()));
+    let output = loginitems()
+        .args(["scan", "--format", "csv", "--output"])
+        .arg(&output_path)
+        .arg("--root")
+        .arg(test_location("tests/test_data/root"))
+        .output()
+        .unwrap();
+    let csv_data = std::fs::read(&output_path).unwrap();
+    std::fs::remove_file(&output_path).unwrap();
+
+    // Corrupt backgrounditems.btm for alex is reported on stderr
+    assert!(output.status.code() == Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("/Users/alex/"));
+    assert!(output.stdout.is_empty());
+    let mut reader = csv::Reader::from_reader(csv_data.as_slice());
+    assert!(reader.records().count() > 1);
+}
+
+#[test]
+fn cli_scan_ndjson_test() {
+    let output = loginitems()
+        .args(["scan", "--format", "ndjson", "--root"])
+        .arg(test_location("tests/test_data/root"))
+        .output()
+        .unwrap();
+    assert!(output.status.code() == Some(2));
+
+    let records: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(records.len() > 1);
+    assert!(records
+        .iter()
+        .all(|record| record["host"] == "Sams-MacBook-Pro"));
+    assert!(records
+        .iter()
+        .any(|record| record["source_type"] == "LaunchDaemon"));
+}
+
+#[test]
+fn cli_parse_bodyfile_test() {
+    let output = loginitems()
+        .args(["parse", "--format", "bodyfile"])
+        .arg(test_location("tests/test_data/backgrounditems_sierra.btm"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let bodyfile = String::from_utf8_lossy(&output.stdout);
+    assert!(bodyfile
+        .lines()
+        .any(|line| line.contains("/Applications/Syncthing.app (LoginItems Target Creation)")));
+    assert!(bodyfile.lines().all(|line| line.split('|').count() == 11));
+}
+
+#[test]
+fn cli_scan_sqlite_test() {
+    let output_path =
+        std::env::temp_dir().join(format!("loginitems_cli_{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&output_path);
+    let output = loginitems()
+        .args(["scan", "--format", "sqlite", "--output"])
+        .arg(&output_path)
+        .arg("--root")
+        .arg(test_location("tests/test_data/root"))
+        .output()
+        .unwrap();
+    let database = std::fs::read(&output_path).unwrap();
+    std::fs::remove_file(&output_path).unwrap();
+
+    assert!(output.status.code() == Some(2));
+    assert!(database.starts_with(b"SQLite format 3"));
+
+    let output = loginitems()
+        .args(["parse", "--format", "sqlite"])
+        .arg(test_location("tests/test_data/backgrounditems_sierra.btm"))
+        .output()
+        .unwrap();
+    assert!(output.status.code() == Some(1));
+}
+
+#[test]
+fn cli_dump_test() {
+    let output = loginitems()
+        .args(["dump", "--format", "csv"])
+        .arg(test_location("tests/test_data/backgrounditems_sierra.btm"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("TARGET_PATH"));
+}
+
+#[test]
+fn cli_dump_ndjson_test() {
+    let output = loginitems()
+        .args(["dump", "--format", "ndjson"])
+        .arg(test_location("tests/test_data/backgrounditems_sierra.btm"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let records: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(records.len() > 1);
+    assert!(records.iter().all(|record| record.is_object()));
+}
+
+#[test]
+fn cli_diff_test() {
+    let output = loginitems()
+        .arg("diff")
+        .arg(test_location("tests/test_data/backgrounditems_sierra.btm"))
+        .arg(test_location("tests/test_data/backgrounditems_sierra.btm"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let changes: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(changes.as_array().unwrap().is_empty());
+}
+
+#[test]
+fn cli_diff_root_test() {
+    let output = loginitems()
+        .arg("diff")
+        .arg(test_location("tests/test_data/root"))
+        .arg(test_location("tests/test_data/root"))
+        .output()
+        .unwrap();
+
+    // Corrupt backgrounditems.btm for alex is reported on stderr, the diff is still written
+    assert!(output.status.code() == Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("/Users/alex/"));
+    let changes: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(changes.as_array().unwrap().is_empty());
+}
+
+#[test]
+fn cli_failure_test() {
+    let output = loginitems()
+        .arg("parse")
+        .arg(test_location("tests/test_data/missing.btm"))
+        .output()
+        .unwrap();
+    assert!(output.status.code() == Some(1));
+    assert!(!output.stderr.is_empty());
+
+    let output = loginitems()
+        .args(["scan", "--format", "xml"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
@@ -1,6 +1,8 @@
 use std::path::PathBuf;
 
-use macos_loginitems::parser::{parse_loginitems_path, parse_loginitems_system};
+use macos_loginitems::parser::{
+    parse_loginitems_path, parse_loginitems_root, parse_loginitems_system,
+};
 
 #[test]
 fn loginitems_test() {
@@ -22,25 +24,87 @@ fn loginitems_test() {
     let extension = "64cb7eaa9a1bbccc4e1397c9f2a411ebe539cd29;00000000;00000000;0000000000000020;com.apple.app-sandbox.read-write;01;01000004;00000000000ac62a;/applications/syncthing.app\u{0}";
     let target_flags = [2, 15, 0];
 
-    assert!(loginitems_data.results[0].creation == creation);
+    assert!(loginitems_data.results[0].creation.raw == creation);
+    assert!(loginitems_data.results[0].creation.to_string() == "2022-02-02T05:53:09Z");
     assert!(loginitems_data.results[0].path == path);
     assert!(loginitems_data.results[0].cnid_path == cnid);
     assert!(loginitems_data.results[0].volume_path == volume_path);
     assert!(loginitems_data.results[0].volume_url == volume_url);
     assert!(loginitems_data.results[0].volume_name == volume_name);
     assert!(loginitems_data.results[0].volume_uuid == volume_uuid);
-    assert!(loginitems_data.results[0].volume_creation == volume_creation);
+    assert!(loginitems_data.results[0].volume_creation.raw == volume_creation);
     assert!(loginitems_data.results[0].volume_size == volume_size);
     assert!(loginitems_data.results[0].volume_flag == volume_flags);
     assert!(loginitems_data.results[0].volume_root == volume_root);
     assert!(loginitems_data.results[0].localized_name == localized_name);
     assert!(loginitems_data.results[0].security_extension == extension);
+    let token = loginitems_data.results[0]
+        .security_extension_token
+        .as_ref()
+        .unwrap();
+    assert!(token.inode == 706090);
+    assert!(token.path == "/applications/syncthing.app");
     assert!(loginitems_data.results[0].target_flags == target_flags);
+    assert!(loginitems_data.results[0].target_flag_names == ["IsDirectory"]);
+    assert!(
+        loginitems_data.results[0].volume_flag_names
+            == ["IsLocal", "IsInternal", "SupportsPersistentIDs"]
+    );
+}
+
+#[test]
+fn loginitems_btm_test() {
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/BackgroundItems-v4.btm");
+    let loginitems_data = parse_loginitems_path(&test_location.display().to_string()).unwrap();
+
+    let app = loginitems_data.results[0].btm.as_ref().unwrap();
+    let login_item = loginitems_data.results[1].btm.as_ref().unwrap();
+    assert!(app.name == "Syncthing");
+    assert!(app.item_type == 2);
+    assert!(app.disposition == 3);
+    assert!(app.embedded_identifiers == [login_item.identifier.as_str()]);
+    assert!(login_item.parent_identifier == app.identifier);
+    assert!(login_item.url == "file:///Applications/Syncthing.app/");
+    assert!(loginitems_data.results[1].localized_name == "Syncthing");
+}
+
+#[test]
+fn loginitems_root_test() {
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/root");
+    let report = parse_loginitems_root(&test_location).unwrap();
+    let source = |suffix: &str| {
+        report
+            .results
+            .iter()
+            .find(|results| results.path.ends_with(suffix))
+            .unwrap()
+    };
+
+    let btm_path = test_location
+        .join("Users/Shared/BTM/A1B2C3D4-0000-4000-8000-0000000001F5/2/BackgroundItems-v4.btm");
+    assert!(source("BackgroundItems-v4.btm").path == btm_path.display().to_string());
+    let background_items = source("backgrounditems.btm");
+    assert!(background_items.owner == "sam");
+    assert!(background_items.results[0].path == ["Applications", "Syncthing.app"]);
+    assert!(source("loginitems.501.plist")
+        .results
+        .iter()
+        .any(|loginitem| loginitem.app_id == "com.csaba.fitzl.shield"));
+}
+
+#[test]
+fn loginitems_root_missing_test() {
+    let mut test_location = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_location.push("tests/test_data/root/Users/sam");
+    let results = parse_loginitems_root(&test_location);
+    assert!(results.is_err());
 }
 
 #[test]
 #[ignore]
 fn loginitems_system() {
-    let results = parse_loginitems_system().unwrap();
-    assert!(results.len() > 0);
+    let report = parse_loginitems_system().unwrap();
+    assert!(!report.results.is_empty());
 }